@@ -1,4 +1,7 @@
 import datetime
+import os
+import re
+import sys
 
 from typing import Dict
 from typing import Tuple
@@ -751,6 +754,102 @@ SECOND_SECRET='sensitive value with spaces'
 
 """)
 
+        #####################
+        # Key rules
+        rules = "--uppercase --replace-invalid --strip-prefix first_ --map non.posix.key=NON_POSIX "
+        result = self.run_cli(cmd_env, shell_cmd + " " + rules)
+        self.assertResultSuccess(result)
+        self.assertEqual(result.out(), """\
+FIRST_PARAM_SECRET=top-secret-sci
+NON_POSIX=posix_value_invalid_key
+PARAM=posix_compliant_value
+SECOND_PARAM='a value with spaces'
+SECOND_SECRET='sensitive value with spaces'
+
+""")
+
+        result = self.run_cli(cmd_env, dotenv_cmd + " --add-prefix CT_ --uppercase --starts-with ct_second --export")
+        self.assertResultSuccess(result)
+        self.assertEqual(result.out(), """\
+export CT_SECOND_PARAM="a value with spaces"
+export CT_SECOND_SECRET="sensitive value with spaces"
+
+""")
+
+        #####################
+        # Client-side formats
+        export_cmd = base_cmd + f"--project {proj_name} param export "
+        result = self.run_cli(cmd_env, export_cmd + "json --starts-with first")
+        self.assertResultSuccess(result)
+        self.assertEqual(result.out(), f"""\
+{{
+  "FIRST_PARAM_SECRET": "{REDACTED}",
+  "first_param": "posix_compliant_value"
+}}
+
+""")
+
+        result = self.run_cli(cmd_env, export_cmd + "yaml --nest . --contains posix")
+        self.assertResultSuccess(result)
+        self.assertEqual(result.out(), """\
+---
+non:
+  posix:
+    key: posix_value_invalid_key
+
+""")
+
+        result = self.run_cli(cmd_env, export_cmd + "properties -s --uppercase --starts-with second")
+        self.assertResultSuccess(result)
+        self.assertEqual(result.out(), """\
+SECOND_PARAM=a value with spaces
+SECOND_SECRET=sensitive value with spaces
+
+""")
+
+        result = self.run_cli(cmd_env, export_cmd + "toml -s --ends-with secret")
+        self.assertResultSuccess(result)
+        self.assertEqual(result.out(), """\
+FIRST_PARAM_SECRET = "top-secret-sci"
+second_secret = "sensitive value with spaces"
+
+""")
+
+        k8s_opts = "--k8s-name my-config --namespace prod --label app=web --ends-with secret -s"
+        result = self.run_cli(cmd_env, export_cmd + "k8s-secret " + k8s_opts)
+        self.assertResultSuccess(result)
+        self.assertEqual(result.out(), """\
+---
+apiVersion: v1
+kind: Secret
+metadata:
+  name: my-config
+  namespace: prod
+  labels:
+    app: web
+type: Opaque
+data:
+  FIRST_PARAM_SECRET: dG9wLXNlY3JldC1zY2k=
+  second_secret: c2Vuc2l0aXZlIHZhbHVlIHdpdGggc3BhY2Vz
+
+""")
+
+        result = self.run_cli(cmd_env, export_cmd + "k8s-configmap --starts-with first_param -s")
+        self.assertResultSuccess(result)
+        self.assertIn("kind: ConfigMap\n", result.out())
+        self.assertIn("  first_param: posix_compliant_value\n", result.out())
+        self.assertIn("  FIRST_PARAM_SECRET: top-secret-sci\n", result.out())
+
+        result = self.run_cli(cmd_env, export_cmd + "k8s-secret --label no-equals")
+        self.assertResultError(result, "Use a '=' to separate the label key and value")
+
+        # the secret data cannot be masked
+        result = self.run_cli(cmd_env, export_cmd + "k8s-secret --k8s-name my-config")
+        self.assertResultError(result, "The 'k8s-secret' format needs the secret values")
+
+        result = self.run_cli(cmd_env, export_cmd + "k8s-configmap --k8s-name ''")
+        self.assertResultError(result, "The Kubernetes resource name cannot be empty")
+
         # cleanup (no need to delete individual parameters)
         self.delete_project(cmd_env, proj_name)
 
@@ -1418,6 +1517,28 @@ Parameter,{env_a} ({modified_p2_a}),{env_b} ({modified_p2_b})
 {param2},{value2a},{value2b}
 """)
 
+        #####################
+        # More than two environments/times
+
+        # only shows the rows with differences, unless asking for all
+        result = self.run_cli(cmd_env, diff_csv + f"-s -e default -e '{env_a}' -e '{env_b}'")
+        self.assertResultSuccess(result)
+        self.assertEqual(result.out(), f"""\
+Parameter,default,{env_a},{env_b}
+{param1},{value1d},{same},{same}
+{param2},{value2d},{value2a},{value2b}
+""")
+        result = self.run_cli(cmd_env, diff_csv + f"-s -e '{env_a}' -e '{env_a}' -e '{env_b}' --as-of '{modified_p2_b}' --as-of '{modified_p2_b}' --as-of '{created_p2_b}'")
+        self.assertResultWarning(result, "Invalid comparing an environment to itself")
+
+        result = self.run_cli(cmd_env, diff_csv + f"--as-of '{created_p2_b}' --as-of '{modified_p2_b}' --as-of '{modified_p2_b}'")
+        self.assertResultWarning(result, "Invalid comparing an environment to itself")
+
+        result = self.run_cli(cmd_env, diff_csv + f"--as-of '{created_p2_b}' --as-of '{modified_p2_b}' --all")
+        self.assertResultSuccess(result)
+        self.assertIn(f"Parameter,{created_p2_b},{modified_p2_b}", result.out())
+        self.assertIn(f"{param1},", result.out())
+
         #####################
         # Error cases
 
@@ -1444,12 +1565,9 @@ Parameter,{env_a} ({modified_p2_a}),{env_b} ({modified_p2_b})
         result = self.run_cli(cmd_env, sub_cmd + f"differences -e '{env_a}' -e 'missing'")
         self.assertResultError(result, "Did not find environment 'missing'")
 
-        # too many specified
-        result = self.run_cli(cmd_env, sub_cmd + "diff -e env1 --env env2 -e env3")
-        self.assertResultWarning(result, "Can specify a maximum of 2 environment values")
-
-        result = self.run_cli(cmd_env, sub_cmd + "diff --as-of 2021-08-01 --as-of 2021-08-02 --as-of 2021-08-03")
-        self.assertResultWarning(result, "Can specify a maximum of 2 as-of values")
+        # the environments and times need to line up
+        result = self.run_cli(cmd_env, sub_cmd + "diff -e env1 --env env2 -e env3 --as-of 2021-08-01 --as-of 2021-08-02")
+        self.assertResultWarning(result, "Cannot compare 3 environment(s) to 2 time(s)")
 
         # cleanup
         self.delete_environment(cmd_env, env_a)
@@ -2896,5 +3014,757 @@ Name,Value,Project
         entries = self.get_cli_entries(cmd_env, run_cmd, "parameter-drift")
         self.assertEqual(len(entries), len(find_by_prop(entries, PROP_CHANGE, "added")))
 
+        # compare to a dotenv file, ignoring some of the names
+        PROP_FILE = "File"
+        filename = self.make_name("drift") + ".env"
+        self.write_file(filename, f"""\
+# comments are ignored
+{param1}="{value1}"
+export {param3}='{value3}'
+{param5}={value5}
+IGNORED_1=one
+IGNORED_2=two
+""")
+        file_cmd = drift_cmd + f"--file '{filename}' --exclude 'IGNORED_*' --exclude {param7} "
+        entries = self.get_cli_entries(cmd_env, file_cmd + "-f json", "parameter-drift")
+        self.assertEqual(
+            sorted([e.get(PROP_NAME) for e in entries]),
+            [param2, param4, param6],
+        )
+        entry = find_by_prop(entries, PROP_NAME, param2)[0]
+        self.assertEqual(entry.get(PROP_CHANGE), "removed")
+        self.assertEqual(entry.get(PROP_FILE), empty)
+
+        # the same values in a YAML file
+        yaml_file = self.make_name("drift") + ".yaml"
+        self.write_file(yaml_file, f"""\
+{param1}: {value1}
+{param2}: {value2}
+{param3}: {value3}
+{param4}: {value4}
+{param5}: {value5}
+{param6}: {value6}
+""")
+        result = self.run_cli(cmd_env, drift_cmd + f"--file '{yaml_file}' --exit-code")
+        self.assertResultSuccess(result)
+        self.assertIn("No drift found", result.out())
+
+        # non-zero exit code when there is drift
+        result = self.run_cli(cmd_env, file_cmd + "--exit-code")
+        self.assertEqual(result.return_value, 59)
+        self.assertIn(param2, result.out())
+
+        result = self.run_cli(cmd_env, drift_cmd + "--file no-such-file.env")
+        self.assertResultError(result, "Failed to read file 'no-such-file.env'")
+
+        self.delete_file(filename)
+        self.delete_file(yaml_file)
+
+        # cleanup
+        self.delete_project(cmd_env, proj_name)
+
+    def test_parameter_apply(self):
+        cmd_env = self.get_cmd_env()
+        base_cmd = self.get_cli_base_cmd()
+        proj_name = self.make_name("param-apply")
+        env_name = self.make_name("apply-env")
+        self.create_project(cmd_env, proj_name)
+        self.create_environment(cmd_env, env_name)
+        self.set_param(cmd_env, proj_name, "EXTRA_PARAM", "extra-value")
+        self.set_param(cmd_env, proj_name, "SAME_PARAM", "same-value")
+        apply_cmd = base_cmd + f"--project '{proj_name}' param apply -f manifest.yaml "
+
+        manifest = f"""\
+parameters:
+  - name: SAME_PARAM
+    values:
+      default: same-value
+  - name: DB_PORT
+    description: Database port
+    type: integer
+    rules:
+      max: 65535
+    values:
+      default: 5432
+      {env_name}: 6543
+  - name: DB_PASSWORD
+    secret: true
+    values:
+      default: my-secret-value
+"""
+        self.write_file("manifest.yaml", manifest)
+
+        # the plan is shown, but nothing changes without confirmation
+        result = self.run_cli(cmd_env, apply_cmd)
+        self.assertResultSuccess(result)
+        self.assertIn("DB_PORT", result.out())
+        self.assertIn("create", result.out())
+        self.assertNotIn("SAME_PARAM", result.out())
+        self.assertNotIn("my-secret-value", result.out())
+        self.assertIsNone(self.get_param(cmd_env, proj_name, "DB_PORT"))
+
+        result = self.run_cli(cmd_env, apply_cmd + "--format json")
+        self.assertResultSuccess(result)
+        self.assertIn('"parameter-plan"', result.out())
+        self.assertIn('"DB_PORT"', result.out())
+
+        result = self.run_cli(cmd_env, apply_cmd + "--confirm")
+        self.assertResultSuccess(result)
+        self.assertIn(f"Applied 5 change(s) to project '{proj_name}'", result.out())
+        self.verify_param(cmd_env, proj_name, "DB_PORT", "5432")
+        self.verify_param(cmd_env, proj_name, "DB_PORT", "6543", env=env_name)
+        self.verify_param(cmd_env, proj_name, "DB_PASSWORD", "my-secret-value")
+        param = self.get_param(cmd_env, proj_name, "DB_PORT")
+        self.assertEqual(param.get("Type"), "integer")
+        self.assertEqual(param.get("Description"), "Database port")
+        self.assertIsNotNone(self.get_param(cmd_env, proj_name, "EXTRA_PARAM"))
+
+        # applying again has nothing to do
+        result = self.run_cli(cmd_env, apply_cmd + "--confirm")
+        self.assertResultSuccess(result)
+        self.assertIn(f"No changes needed for project '{proj_name}'", result.out())
+
+        # updates and pruning
+        self.write_file("manifest.yaml", manifest.replace("5432", "5433"))
+        result = self.run_cli(cmd_env, apply_cmd + "--prune --confirm")
+        self.assertResultSuccess(result)
+        self.assertIn("Deleted parameter 'EXTRA_PARAM'", result.out())
+        self.verify_param(cmd_env, proj_name, "DB_PORT", "5433")
+        self.assertIsNone(self.get_param(cmd_env, proj_name, "EXTRA_PARAM"))
+
+        # rule violations are reported
+        self.write_file("manifest.yaml", manifest.replace("5432", "70000"))
+        result = self.run_cli(cmd_env, apply_cmd + "--confirm")
+        self.assertResultError(result, "Rule violation")
+
+        # bad manifests
+        self.write_file("manifest.yaml", "parameters:\n  - name: A\n    rules:\n      maximum: 3\n")
+        result = self.run_cli(cmd_env, apply_cmd)
+        self.assertResultError(result, "invalid rule type 'maximum'")
+        self.write_file("manifest.yaml", "parameters:\n  - name: A\n    values:\n      no-such-env: a\n")
+        result = self.run_cli(cmd_env, apply_cmd)
+        self.assertResultError(result, "no-such-env")
+        self.write_file("manifest.yaml", "parameters:\n  - name: A\n    values:\n      default: 1.10\n")
+        result = self.run_cli(cmd_env, apply_cmd)
+        self.assertResultError(result, "quote numbers that are not integers")
+
+        # cleanup
+        self.delete_file("manifest.yaml")
+        self.delete_project(cmd_env, proj_name)
+        self.delete_environment(cmd_env, env_name)
+
+    def test_parameter_history(self):
+        cmd_env = self.get_cmd_env()
+        base_cmd = self.get_cli_base_cmd()
+        proj_name = self.make_name("param-hist")
+        self.create_project(cmd_env, proj_name)
+        param_cmd = base_cmd + f"--project '{proj_name}' param "
+
+        # take a baseline before we have any parameter history
+        result = self.run_cli(cmd_env, param_cmd + "history")
+        self.assertResultSuccess(result)
+        self.assertIn("No parameter history in project", result.out())
+
+        param1 = "param1"
+        param2 = "param2"
+        secret_a = "first-secret"
+        secret_b = "second-secret"
+        self.set_param(cmd_env, proj_name, param1, "value-a")
+        self.set_param(cmd_env, proj_name, param2, secret_a, secret=True)
+        modified_at = self.get_param(cmd_env, proj_name, param1).get(PROP_MODIFIED)
+        self.set_param(cmd_env, proj_name, param1, "value-b")
+        self.set_param(cmd_env, proj_name, param2, secret_b, secret=True)
+        user = self.current_username(cmd_env)
+
+        # complete history, with the secrets redacted
+        result = self.run_cli(cmd_env, param_cmd + "history -f csv")
+        self.assertResultSuccess(result)
+        self.assertIn("Date,User,Action,Name,Environment,Old Value,New Value", result.out())
+        self.assertIn(f",{user},create value,{param1},{DEFAULT_ENV_NAME},,value-a", result.out())
+        self.assertIn(f",{user},update value,{param1},{DEFAULT_ENV_NAME},value-a,value-b", result.out())
+        self.assertIn(f",{user},update value,{param2},{DEFAULT_ENV_NAME},{REDACTED},{REDACTED}", result.out())
+        self.assertNotIn(secret_a, result.out())
+        self.assertNotIn(secret_b, result.out())
+
+        # focus on one parameter, and show the secrets
+        result = self.run_cli(cmd_env, param_cmd + f"history '{param2}' -s -f json")
+        self.assertResultSuccess(result)
+        self.assertNotIn(param1, result.out())
+        self.assertNotIn('"Name"', result.out())
+        self.assertIn(secret_a, result.out())
+        self.assertIn(secret_b, result.out())
+
+        # further focus on older updates using a time
+        result = self.run_cli(cmd_env, param_cmd + f"history '{param1}' --as-of '{modified_at}' -f csv")
+        self.assertResultSuccess(result)
+        self.assertIn("value-a", result.out())
+        self.assertNotIn("value-b", result.out())
+
+        result = self.run_cli(cmd_env, param_cmd + "history 'no-such-param'")
+        self.assertResultError(result, f"Did not find parameter 'no-such-param' in project '{proj_name}'")
+
+        # cleanup
+        self.delete_project(cmd_env, proj_name)
+
+    def test_parameter_rollback(self):
+        cmd_env = self.get_cmd_env()
+        base_cmd = self.get_cli_base_cmd()
+        proj_name = self.make_name("param-rollback")
+        self.create_project(cmd_env, proj_name)
+        param_cmd = base_cmd + f"--project '{proj_name}' param "
+
+        param1 = "param1"
+        param2 = "param2"
+        param3 = "param3"
+        self.set_param(cmd_env, proj_name, param1, "value-a")
+        self.set_param(cmd_env, proj_name, param2, "{{ param1 }}", evaluate=True)
+        self.set_param(cmd_env, proj_name, param3, "secret-a", secret=True)
+        modified_at = self.get_param(cmd_env, proj_name, param3).get(PROP_MODIFIED)
+
+        self.set_param(cmd_env, proj_name, param1, "value-b")
+        self.set_param(cmd_env, proj_name, param2, "static-value", evaluate=False)
+        self.set_param(cmd_env, proj_name, param3, "secret-b", secret=True)
+
+        # the evaluated value comes back as a reference, not the resolved text
+        result = self.run_cli(cmd_env, param_cmd + f"rollback {param2} --to '{modified_at}' -y")
+        self.assertResultSuccess(result)
+        self.assertIn("static-value", result.out())
+        self.assertIn(f"Rolled back parameter '{param2}'", result.out())
+        self.verify_param(cmd_env, proj_name, param2, "value-b")
+
+        # the whole environment shows the changes, but only changes when confirmed
+        result = self.run_cli(cmd_env, param_cmd + f"rollback --all --to '{modified_at}'")
+        self.assertResultSuccess(result)
+        self.assertIn(param1, result.out())
+        self.assertIn(param3, result.out())
+        self.assertIn(REDACTED, result.out())
+        self.assertNotIn("secret-a", result.out())
+        self.verify_param(cmd_env, proj_name, param1, "value-b")
+
+        result = self.run_cli(cmd_env, param_cmd + f"rollback --all --to '{modified_at}' -y")
+        self.assertResultSuccess(result)
+        self.verify_param(cmd_env, proj_name, param1, "value-a")
+        self.verify_param(cmd_env, proj_name, param2, "value-a")
+        self.verify_param(cmd_env, proj_name, param3, "secret-a")
+
+        result = self.run_cli(cmd_env, param_cmd + f"rollback --all --to '{modified_at}' -y")
+        self.assertResultSuccess(result)
+        self.assertIn("No parameter values to roll back", result.out())
+
+        # parameters need to exist
+        result = self.run_cli(cmd_env, param_cmd + f"rollback no-such-param --to '{modified_at}'")
+        self.assertResultError(result, "Did not find parameter 'no-such-param'")
+
+        # cleanup
+        self.delete_project(cmd_env, proj_name)
+
+    def test_parameter_promote(self):
+        cmd_env = self.get_cmd_env()
+        base_cmd = self.get_cli_base_cmd()
+        proj_name = self.make_name("param-promote")
+        stage_name = self.make_name("promote-stage")
+        prod_name = self.make_name("promote-prod")
+        self.create_project(cmd_env, proj_name)
+        self.create_environment(cmd_env, stage_name)
+        self.create_environment(cmd_env, prod_name)
+        param_cmd = base_cmd + f"--project '{proj_name}' param "
+        promote_cmd = param_cmd + f"promote --from '{stage_name}' --to '{prod_name}' "
+
+        param1 = "param1"
+        param2 = "param2"
+        param3 = "param3"
+        param4 = "other4"
+        self.set_param(cmd_env, proj_name, param1, "default-1")
+        self.set_param(cmd_env, proj_name, param1, "stage-1", env=stage_name)
+        self.set_param(cmd_env, proj_name, param2, "default-2")
+        self.set_param(cmd_env, proj_name, param2, "prod-2", env=prod_name)
+        self.set_param(cmd_env, proj_name, param3, "stage-3", secret=True, env=stage_name)
+        self.set_param(cmd_env, proj_name, param4, "stage-4", env=stage_name)
+
+        # shows the plan, but nothing changes without confirmation
+        result = self.run_cli(cmd_env, promote_cmd)
+        self.assertResultSuccess(result)
+        self.assertIn(param1, result.out())
+        self.assertIn(param2, result.out())
+        self.assertIn(param3, result.out())
+        self.assertIn(param4, result.out())
+        self.assertIn(REDACTED, result.out())
+        self.assertNotIn("stage-3", result.out())
+        self.verify_param(cmd_env, proj_name, param1, "default-1", env=prod_name)
+
+        # limit the parameters, and leave out the secrets
+        result = self.run_cli(cmd_env, promote_cmd + "--starts-with param --skip-secrets -y")
+        self.assertResultSuccess(result)
+        self.assertIn(f"Created tag 'promote-{prod_name}-", result.out())
+        self.assertIn(f"Promoted parameter '{param1}'", result.out())
+        self.assertIn(f"Promoted parameter '{param2}'", result.out())
+        self.assertNotIn(param3, result.out())
+        self.assertNotIn(param4, result.out())
+        self.assertIn("Promoted 2 change(s)", result.out())
+        self.verify_param(cmd_env, proj_name, param1, "stage-1", env=prod_name)
+        self.verify_param(cmd_env, proj_name, param2, "default-2", env=prod_name)
+
+        # explicitly named secrets are skipped with a warning
+        result = self.run_cli(cmd_env, promote_cmd + f"--keys {param3} --skip-secrets -y")
+        self.assertResultWarning(result, f"Skipping parameter '{param3}', since it is a secret")
+        self.assertIn("No parameters to promote", result.out())
+
+        # the named tag is on the source environment
+        result = self.run_cli(cmd_env, promote_cmd + f"--keys {param3},{param4} --tag promo-tag -y")
+        self.assertResultSuccess(result)
+        self.assertIn(f"Created tag 'promo-tag' in environment '{stage_name}'", result.out())
+        self.verify_param(cmd_env, proj_name, param3, "stage-3", env=prod_name)
+        self.verify_param(cmd_env, proj_name, param4, "stage-4", env=prod_name)
+        result = self.run_cli(cmd_env, base_cmd + f"env tag list '{stage_name}'")
+        self.assertIn("promo-tag", result.out())
+
+        result = self.run_cli(cmd_env, promote_cmd + "-y")
+        self.assertResultSuccess(result)
+        self.assertIn("No parameters to promote", result.out())
+
+        # overrides that match the inherited value get removed
+        self.set_param(cmd_env, proj_name, param2, "default-2", env=prod_name)
+        result = self.run_cli(cmd_env, promote_cmd + "--remove-inherited -y")
+        self.assertResultSuccess(result)
+        self.assertIn(f"Removed override for parameter '{param2}'", result.out())
+        result = self.run_cli(cmd_env, param_cmd + f"env {param2}")
+        self.assertNotIn(prod_name, result.out())
+
+        # parameters need to exist
+        result = self.run_cli(cmd_env, promote_cmd + "--keys no-such-param")
+        self.assertResultError(result, "Did not find parameter 'no-such-param'")
+
+        # cleanup
+        self.delete_project(cmd_env, proj_name)
+        self.delete_environment(cmd_env, prod_name)
+        self.delete_environment(cmd_env, stage_name)
+
+    def test_parameter_diff_semantic(self):
+        base_cmd = self.get_cli_base_cmd()
+        cmd_env = self.get_cmd_env()
+        proj_name = self.make_name("param-semantic")
+        self.create_project(cmd_env, proj_name)
+        env_a = self.make_name("psem-env-a")
+        self.create_environment(cmd_env, env_a)
+        env_b = self.make_name("psem-env-b")
+        self.create_environment(cmd_env, env_b)
+
+        param1 = "config"
+        param2 = "plain"
+        self.set_param(cmd_env, proj_name, param1, '{"name": "app", "port": 80}', env=env_a)
+        self.set_param(cmd_env, proj_name, param1, "port: 80\nname: app\n", env=env_b)
+        self.set_param(cmd_env, proj_name, param2, "value-a", env=env_a)
+        self.set_param(cmd_env, proj_name, param2, "value-b", env=env_b)
+
+        diff_cmd = base_cmd + f"--project '{proj_name}' param diff -e '{env_a}' -e '{env_b}' -f csv "
+        result = self.run_cli(cmd_env, diff_cmd)
+        self.assertResultSuccess(result)
+        self.assertIn(f"{param1},", result.out())
+
+        # reordered keys are not a difference, and plain values are still compared
+        result = self.run_cli(cmd_env, diff_cmd + "--semantic")
+        self.assertResultSuccess(result)
+        self.assertEqual(result.out(), f"""\
+Parameter,Path,{env_a},{env_b}
+{param2},,value-a,value-b
+""")
+
+        self.set_param(cmd_env, proj_name, param1, "port: 8080\nname: app\ndebug: true\n", env=env_b)
+        result = self.run_cli(cmd_env, diff_cmd + "--semantic")
+        self.assertResultSuccess(result)
+        self.assertEqual(result.out(), f"""\
+Parameter,Path,{env_a},{env_b}
+{param1},debug,,true
+{param1},port,80,8080
+{param2},,value-a,value-b
+""")
+
+        result = self.run_cli(cmd_env, diff_cmd + "--semantic --all")
+        self.assertResultSuccess(result)
+        self.assertIn(f'{param1},name,"""app""","""app"""', result.out())
+
+        result = self.run_cli(cmd_env, diff_cmd + "--semantic -p fqn")
+        self.assertResultError(result, "cannot be used with")
+
+        # cleanup
+        self.delete_project(cmd_env, proj_name)
+        self.delete_environment(cmd_env, env_a)
+        self.delete_environment(cmd_env, env_b)
+
+    def test_parameter_search(self):
+        base_cmd = self.get_cli_base_cmd()
+        cmd_env = self.get_cmd_env()
+        proj1 = self.make_name("param-search-1")
+        proj2 = self.make_name("param-search-2")
+        self.create_project(cmd_env, proj1)
+        self.create_project(cmd_env, proj2)
+        env_name = self.make_name("search-env")
+        self.create_environment(cmd_env, env_name)
+
+        needle = self.make_name("SrchKey")
+        self.set_param(cmd_env, proj1, f"{needle}_host", "db.example.com")
+        self.set_param(cmd_env, proj2, f"{needle}_PORT", "5432", param_type="integer")
+        self.set_param(cmd_env, proj2, "other-value", f"url-{needle}")
+        self.set_param(cmd_env, proj2, "other-secret", f"secret-{needle}", secret=True)
+        self.set_param(cmd_env, proj1, f"{needle}_host", "db.stage.com", env=env_name)
+
+        search_cmd = base_cmd + "param search -f json "
+
+        # names are matched in all projects, ignoring case
+        entries = self.get_cli_entries(cmd_env, search_cmd + f"'{needle.lower()}'", "parameter-search")
+        self.assertEqual(len(entries), 2)
+        self.assertEqual(entries[0]["Project"], proj1)
+        self.assertEqual(entries[0]["Parameter"], f"{needle}_host")
+        self.assertEqual(entries[0]["Value"], "db.example.com")
+        self.assertEqual(entries[1]["Project"], proj2)
+        self.assertEqual(entries[1]["Type"], "integer")
+
+        entries = self.get_cli_entries(
+            cmd_env, search_cmd + f"'{needle}' --type integer", "parameter-search"
+        )
+        self.assertEqual(len(entries), 1)
+        self.assertEqual(entries[0]["Parameter"], f"{needle}_PORT")
+
+        entries = self.get_cli_entries(
+            cmd_env, search_cmd + f"--regex '^{needle}_[a-z]+$'", "parameter-search"
+        )
+        self.assertEqual(len(entries), 1)
+        self.assertEqual(entries[0]["Parameter"], f"{needle}_host")
+
+        # secret values are only searched when asked
+        entries = self.get_cli_entries(cmd_env, search_cmd + f"'{needle}' --values", "parameter-search")
+        self.assertEqual(len(entries), 3)
+        self.assertEqual(find_by_prop(entries, "Parameter", "other-secret"), [])
+        entries = self.get_cli_entries(
+            cmd_env, search_cmd + f"'{needle}' --values --secrets", "parameter-search"
+        )
+        self.assertEqual(len(entries), 4)
+        self.assertEqual(find_by_prop(entries, "Parameter", "other-secret")[0]["Value"], f"secret-{needle}")
+
+        # values from several environments
+        entries = self.get_cli_entries(
+            cmd_env,
+            search_cmd + f"'{needle}_host' --environment default --environment '{env_name}'",
+            "parameter-search",
+        )
+        self.assertEqual(len(entries), 2)
+        self.assertEqual(entries[0]["Environment"], "default")
+        self.assertEqual(entries[1]["Environment"], env_name)
+        self.assertEqual(entries[1]["Value"], "db.stage.com")
+
+        result = self.run_cli(cmd_env, base_cmd + f"param search 'no-{needle}-here'")
+        self.assertResultSuccess(result)
+        self.assertIn(f"No parameters found matching 'no-{needle}-here'", result.out())
+
+        result = self.run_cli(cmd_env, base_cmd + "param search --regex '[unclosed'")
+        self.assertResultError(result, "regex parse error")
+
+        # cleanup
+        self.delete_project(cmd_env, proj1)
+        self.delete_project(cmd_env, proj2)
+        self.delete_environment(cmd_env, env_name)
+
+    def test_parameter_references(self):
+        base_cmd = self.get_cli_base_cmd()
+        cmd_env = self.get_cmd_env()
+        parent_name = self.make_name("param-refs-parent")
+        child_name = self.make_name("param-refs-child")
+        self.create_project(cmd_env, parent_name)
+        self.create_project(cmd_env, child_name, parent=parent_name)
+        env_name = self.make_name("refs-env")
+        self.create_environment(cmd_env, env_name)
+
+        param1 = "db_host"
+        param2 = "db_url"
+        param3 = "db_label"
+        temp1 = "parent-temp"
+        temp2 = "child-temp"
+        self.set_param(cmd_env, parent_name, param1, "db.example.com")
+        self.set_param(cmd_env, parent_name, param2, "postgres://{{ db_host }}:5432", evaluate=True)
+        self.set_param(cmd_env, parent_name, param3, "static")
+        self.set_param(
+            cmd_env, parent_name, param3, "{{ cloudtruth.parameters.db_host | upper }}",
+            env=env_name, evaluate=True
+        )
+        self.set_template(cmd_env, parent_name, temp1, body="host={{db_host}}\nurl={{ db_url }}\n")
+        self.set_template(cmd_env, child_name, temp2, body="# uses {{ db_host }}\n")
+
+        refs_cmd = base_cmd + f"--project '{parent_name}' param references "
+        entries = self.get_cli_entries(cmd_env, refs_cmd + f"{param1} -f json", "parameter-references")
+        self.assertEqual(len(entries), 4)
+        self.assertEqual(entries[0]["Project"], child_name)
+        self.assertEqual(entries[0]["Type"], "template")
+        self.assertEqual(entries[0]["Name"], temp2)
+        self.assertEqual(entries[1]["Project"], parent_name)
+        self.assertEqual(entries[1]["Type"], "parameter")
+        self.assertEqual(entries[1]["Name"], param3)
+        self.assertEqual(entries[1]["Environment"], env_name)
+        self.assertEqual(entries[1]["Reference"], "{{ cloudtruth.parameters.db_host | upper }}")
+        self.assertEqual(entries[2]["Name"], param2)
+        self.assertEqual(entries[2]["Environment"], "default")
+        self.assertEqual(entries[3]["Name"], temp1)
+        self.assertEqual(entries[3]["Reference"], "{{db_host}}")
+
+        result = self.run_cli(cmd_env, refs_cmd + "no-such-param")
+        self.assertResultError(result, "Did not find parameter 'no-such-param'")
+
+        # only the descendants are scanned
+        result = self.run_cli(cmd_env, base_cmd + f"--project '{child_name}' param refs {param2}")
+        self.assertResultSuccess(result)
+        self.assertIn(f"No references to parameter '{param2}' found in project '{child_name}'", result.out())
+
+        # deleting warns about the references (the server may still refuse the delete)
+        result = self.run_cli(cmd_env, base_cmd + f"--project '{parent_name}' param delete -y {param1}")
+        self.assertIn(f"Parameter '{param1}' is referenced 4 time(s)", result.err())
+
+        # cleanup
+        self.delete_project(cmd_env, child_name)
+        self.delete_project(cmd_env, parent_name)
+        self.delete_environment(cmd_env, env_name)
+
+    def test_parameter_rename(self):
+        base_cmd = self.get_cli_base_cmd()
+        cmd_env = self.get_cmd_env()
+        parent_name = self.make_name("param-rename-parent")
+        child_name = self.make_name("param-rename-child")
+        self.create_project(cmd_env, parent_name)
+        self.create_project(cmd_env, child_name, parent=parent_name)
+
+        param1 = "old_host"
+        param2 = "db_url"
+        new_name = "new_host"
+        temp1 = "child-temp"
+        self.set_param(cmd_env, parent_name, param1, "db.example.com")
+        self.set_param(cmd_env, parent_name, param2, "postgres://{{ old_host }}:5432", evaluate=True)
+        self.set_template(cmd_env, child_name, temp1, body="host={{old_host | upper}}\n")
+
+        rename_cmd = base_cmd + f"--project '{parent_name}' param rename "
+
+        # dry-run shows the changes without making them
+        result = self.run_cli(cmd_env, rename_cmd + f"{param1} {new_name} --dry-run -f csv")
+        self.assertResultSuccess(result)
+        self.assertIn(f"Would rename parameter '{param1}' to '{new_name}'", result.out())
+        self.assertIn(f"{parent_name},parameter,{param2},default,{{{{ old_host }}}},{{{{ new_host }}}}", result.out())
+        self.assertIn(f"{child_name},template,{temp1},,{{{{old_host | upper}}}},{{{{new_host | upper}}}}", result.out())
+        self.verify_param(cmd_env, parent_name, param1, "db.example.com")
+
+        # names are checked
+        result = self.run_cli(cmd_env, rename_cmd + f"no-such-param {new_name} -y")
+        self.assertResultError(result, "Did not find parameter 'no-such-param'")
+        result = self.run_cli(cmd_env, rename_cmd + f"{param1} {param2} -y")
+        self.assertResultError(result, f"Parameter '{param2}' already exists")
+
+        result = self.run_cli(cmd_env, rename_cmd + f"{param1} {new_name} -y")
+        self.assertResultSuccess(result)
+        self.assertIn(f"Renamed parameter '{param1}' to '{new_name}'", result.out())
+        self.assertIn(f"Updated template '{temp1}' in project '{child_name}'", result.out())
+        self.assertIn(f"Updated parameter '{param2}' in environment 'default'", result.out())
+
+        # the values and references are kept
+        self.verify_param(cmd_env, parent_name, new_name, "db.example.com")
+        self.verify_param(cmd_env, parent_name, param2, "postgres://db.example.com:5432")
+        result = self.run_cli(cmd_env, base_cmd + f"--project '{child_name}' template get {temp1} --raw")
+        self.assertResultSuccess(result)
+        self.assertIn("host={{new_host | upper}}", result.out())
+
+        # cleanup
+        self.delete_project(cmd_env, child_name)
+        self.delete_project(cmd_env, parent_name)
+
+    def test_parameter_validate(self):
+        base_cmd = self.get_cli_base_cmd()
+        cmd_env = self.get_cmd_env()
+        proj_name = self.make_name("param-validate")
+        self.create_project(cmd_env, proj_name)
+        type_name = self.make_name("validate-port")
+        self.create_type(cmd_env, type_name, parent="integer", extra="--min 1 --max 65535")
+
+        param1 = "port"
+        param2 = "label"
+        self.set_param(cmd_env, proj_name, param1, "8080", param_type=type_name, extra="--max 9000")
+        self.set_param(cmd_env, proj_name, param2, "abc", extra="--max-len 5 --regex '^[a-z]+$'")
+
+        param_cmd = base_cmd + f"--project '{proj_name}' param "
+        result = self.run_cli(cmd_env, param_cmd + f"validate {param1} 8081")
+        self.assertResultSuccess(result)
+        self.assertIn(f"Value for parameter '{param1}' is valid.", result.out())
+
+        # all the violations are reported at once
+        result = self.run_cli(cmd_env, param_cmd + f"validate {param1} 70000")
+        self.assertResultError(result, f"Value for parameter '{param1}' has 2 violation(s)")
+        self.assertIn("parameter max rule: Value must be at most 9000", result.err())
+        self.assertIn(f"type '{type_name}' max rule: Value must be at most 65535", result.err())
+
+        result = self.run_cli(cmd_env, param_cmd + f"validate {param1} -5")
+        self.assertResultError(result, f"type '{type_name}' min rule: Value must be at least 1")
+
+        result = self.run_cli(cmd_env, param_cmd + f"validate {param1} eighty")
+        self.assertResultError(result, "type 'integer': Value is not an integer")
+
+        result = self.run_cli(cmd_env, param_cmd + f"validate {param2} Abcdef")
+        self.assertResultError(result, "has 2 violation(s)")
+        self.assertIn("parameter max-len rule: Value must be at most 5 characters", result.err())
+        self.assertIn("parameter regex rule: Value must match the regular expression", result.err())
+
+        result = self.run_cli(cmd_env, param_cmd + "validate no-such-param value")
+        self.assertResultError(result, "Did not find parameter 'no-such-param'")
+
+        # set --check uses the updated rules, and does not change anything
+        result = self.run_cli(cmd_env, param_cmd + f"set {param1} --value 9500 --check")
+        self.assertResultError(result, "parameter max rule: Value must be at most 9000")
+        result = self.run_cli(cmd_env, param_cmd + f"set {param1} --value 9500 --max 10000 --check")
+        self.assertResultSuccess(result)
+        self.assertIn(f"Value for parameter '{param1}' is valid.", result.out())
+        result = self.run_cli(cmd_env, param_cmd + f"set {param2} --value 123456 --no-regex --check")
+        self.assertResultError(result, "has 1 violation(s)")
+
+        # expressions the CLI cannot compile (e.g. lookahead) are left to the server
+        result = self.run_cli(cmd_env, param_cmd + f"set {param2} --value abc --regex '^(?=a)[a-z]+$' --check")
+        self.assertResultSuccess(result)
+        self.assertIn("The parameter regex rule was not checked locally", result.err())
+        self.verify_param(cmd_env, proj_name, param1, "8080")
+        self.verify_param(cmd_env, proj_name, param2, "abc")
+
+        # new parameters only have the rules and type given
+        result = self.run_cli(cmd_env, param_cmd + "set new-param --value maybe --type boolean --check")
+        self.assertResultError(result, "type 'boolean': Value is not a boolean")
+        result = self.run_cli(cmd_env, param_cmd + "list")
+        self.assertNotIn("new-param", result.out())
+
+        # cleanup
+        self.delete_project(cmd_env, proj_name)
+        self.delete_type(cmd_env, type_name)
+
+    def test_parameter_list_filter_sort(self):
+        base_cmd = self.get_cli_base_cmd()
+        cmd_env = self.get_cmd_env()
+        proj_name = self.make_name("param-list-filter")
+        self.create_project(cmd_env, proj_name)
+
+        self.set_param(cmd_env, proj_name, "DB_HOST", "db.example.com")
+        self.set_param(cmd_env, proj_name, "DB_PORT", "5432", param_type="integer")
+        self.set_param(cmd_env, proj_name, "DB_PASSWORD", "hunter2", secret=True)
+        self.set_param(cmd_env, proj_name, "APP_RETRIES", "3", param_type="integer")
+
+        list_cmd = base_cmd + f"--project '{proj_name}' param ls "
+        result = self.run_cli(cmd_env, list_cmd + "--filter type=integer")
+        self.assertResultSuccess(result)
+        self.assertEqual(result.out(), "APP_RETRIES\nDB_PORT\n")
+
+        result = self.run_cli(cmd_env, list_cmd + "--filter 'name~^DB_' --filter secret=false")
+        self.assertResultSuccess(result)
+        self.assertEqual(result.out(), "DB_HOST\nDB_PORT\n")
+
+        # numbers are compared as numbers, and sorting works with any property
+        result = self.run_cli(cmd_env, list_cmd + "--filter 'value>4' --filter type=integer")
+        self.assertResultSuccess(result)
+        self.assertEqual(result.out(), "DB_PORT\n")
+        result = self.run_cli(cmd_env, list_cmd + "--filter type=integer --sort value:desc --columns name,value -f csv")
+        self.assertResultSuccess(result)
+        self.assertEqual(result.out(), "Name,Value\nDB_PORT,5432\nAPP_RETRIES,3\n")
+
+        result = self.run_cli(cmd_env, list_cmd + "--columns name,secret,type -s -f json --filter 'name~PASS'")
+        self.assertResultSuccess(result)
+        entries = eval(result.out()).get("parameter")
+        self.assertEqual(entries, [{"Name": "DB_PASSWORD", "Secret": "true", "Param Type": "string"}])
+
+        result = self.run_cli(cmd_env, list_cmd + "--filter 'modified-at>2000-01-01' --sort name:desc")
+        self.assertResultSuccess(result)
+        self.assertEqual(result.out(), "DB_PORT\nDB_PASSWORD\nDB_HOST\nAPP_RETRIES\n")
+
+        result = self.run_cli(cmd_env, list_cmd + "--filter 'name~[bad'")
+        self.assertResultError(result, "Invalid regular expression in filter 'name~[bad'")
+        result = self.run_cli(cmd_env, list_cmd + "--filter unknown=1")
+        self.assertResultError(result, "Unknown property 'unknown'")
+        result = self.run_cli(cmd_env, list_cmd + "--columns name,bogus")
+        self.assertResultError(result, "isn't a valid value")
+
+        # cleanup
+        self.delete_project(cmd_env, proj_name)
+
+    def test_parameter_edit(self):
+        base_cmd = self.get_cli_base_cmd()
+        cmd_env = self.get_cmd_env()
+        proj_name = self.make_name("param-edit")
+        self.create_project(cmd_env, proj_name)
+
+        # the "editor" writes the NEW_VALUE into the file, and records the file permissions
+        editor_file = "edit-param.py"
+        mode_file = "edit-param-mode.txt"
+        self.write_file(editor_file, "\n".join([
+            "import os",
+            "import sys",
+            "with open(os.environ['MODE_FILE'], 'w') as f:",
+            "    f.write(oct(os.stat(sys.argv[1]).st_mode & 0o777))",
+            "if 'NEW_VALUE' in os.environ:",
+            "    with open(sys.argv[1], 'w') as f:",
+            "        f.write(os.environ['NEW_VALUE'])",
+            "",
+        ]))
+        self.write_file(mode_file, "")
+        cmd_env["EDITOR"] = f"{sys.executable} {editor_file}"
+        cmd_env["VISUAL"] = cmd_env["EDITOR"]
+        cmd_env["MODE_FILE"] = mode_file
+
+        param1 = "multi-line"
+        param2 = "my-secret"
+        param3 = "port"
+        self.set_param(cmd_env, proj_name, param1, "first line")
+        self.set_param(cmd_env, proj_name, param2, "ssshhhh", secret=True)
+        self.set_param(cmd_env, proj_name, param3, "8080", param_type="integer", extra="--max 9000")
+
+        param_cmd = base_cmd + f"--project '{proj_name}' param "
+
+        # the editor does not change the value
+        cmd_env.pop("NEW_VALUE", None)
+        result = self.run_cli(cmd_env, param_cmd + f"edit {param1} -y")
+        self.assertResultSuccess(result)
+        self.assertIn(f"Nothing to update in parameter '{param1}'", result.out())
+
+        cmd_env["NEW_VALUE"] = "first line\nsecond line\n"
+        result = self.run_cli(cmd_env, param_cmd + f"edit {param1} -y")
+        self.assertResultSuccess(result)
+        self.assertIn("-first line", result.out())
+        self.assertIn("+second line", result.out())
+        self.assertIn(f"Updated parameter '{param1}' in project '{proj_name}'", result.out())
+        self.verify_param(cmd_env, proj_name, param1, "first line\nsecond line\n")
+
+        # secrets are not shown, and the temporary file is private
+        cmd_env["NEW_VALUE"] = "new-secret-value"
+        result = self.run_cli(cmd_env, param_cmd + f"edit {param2} -y")
+        self.assertResultSuccess(result)
+        self.assertIn(f"The secret value of parameter '{param2}' changed", result.out())
+        self.assertNotIn("ssshhhh", result.out())
+        self.assertNotIn("new-secret-value", result.out())
+        if os.name != "nt":
+            with open(mode_file) as f:
+                self.assertEqual(f.read(), "0o600")
+        self.verify_param(cmd_env, proj_name, param2, "new-secret-value")
+
+        cmd_env["NEW_VALUE"] = "another-secret"
+        result = self.run_cli(cmd_env, param_cmd + f"edit {param2} -y --secrets")
+        self.assertResultSuccess(result)
+        self.assertIn("-new-secret-value", result.out())
+        self.assertIn("+another-secret", result.out())
+
+        # values are checked against the rules before updating
+        cmd_env["NEW_VALUE"] = "9500"
+        result = self.run_cli(cmd_env, param_cmd + f"edit {param3} -y")
+        self.assertResultError(result, "parameter max rule: Value must be at most 9000")
+        # the rejected value is kept
+        saved = re.search(r"The edited value was saved to '([^']+)'", result.err())
+        self.assertIsNotNone(saved)
+        with open(saved.group(1)) as f:
+            self.assertEqual(f.read(), "9500")
+        os.remove(saved.group(1))
+        cmd_env["NEW_VALUE"] = "eighty"
+        result = self.run_cli(cmd_env, param_cmd + f"edit {param3} -y")
+        self.assertResultError(result, "type 'integer': Value is not an integer")
+        self.verify_param(cmd_env, proj_name, param3, "8080")
+
+        result = self.run_cli(cmd_env, param_cmd + "edit no-such-param -y")
+        self.assertResultError(result, "Did not find parameter 'no-such-param'")
+
         # cleanup
+        self.delete_file(editor_file)
         self.delete_project(cmd_env, proj_name)
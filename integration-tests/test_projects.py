@@ -1,6 +1,7 @@
 from testcase import TestCase
 from testcase import find_by_prop
 from testcase import TEST_PAGE_SIZE
+from testcase import DEFAULT_ENV_NAME
 
 
 class TestProjects(TestCase):
@@ -290,3 +291,70 @@ class TestProjects(TestCase):
         for idx in range(proj_count):
             proj_name = self.make_name(f"pag-{idx}")
             self.delete_project(cmd_env, proj_name)
+
+    def test_project_copy(self):
+        base_cmd = self.get_cli_base_cmd()
+        cmd_env = self.get_cmd_env()
+        src_name = self.make_name("copy-src")
+        dest_name = self.make_name("copy-dest")
+        env_name = self.make_name("copy-env")
+        self.create_project(cmd_env, src_name)
+        self.create_environment(cmd_env, env_name)
+
+        param1 = "param1"
+        param2 = "param2"
+        param3 = "param3"
+        param4 = "param4"
+        temp1 = "temp1"
+        self.set_param(cmd_env, src_name, param1, "value-1")
+        self.set_param(cmd_env, src_name, param1, "env-value-1", env=env_name)
+        self.set_param(cmd_env, src_name, param2, "{{ param1 }}", evaluate=True)
+        self.set_param(cmd_env, src_name, param3, "secret-3", secret=True)
+        src_cmd = base_cmd + f"--project '{src_name}' "
+        result = self.run_cli(cmd_env, src_cmd + f"param set {param1} --max-len 20")
+        self.assertResultSuccess(result)
+        self.set_template(cmd_env, src_name, temp1, "param1={{ param1 }}")
+        # evaluated values can reference templates, too
+        self.set_param(cmd_env, src_name, param4, "{{ cloudtruth.templates.temp1 }}", evaluate=True)
+
+        # the dry run does not create anything
+        copy_cmd = base_cmd + f"proj copy '{src_name}' '{dest_name}' --templates "
+        result = self.run_cli(cmd_env, copy_cmd + "--dry-run")
+        self.assertResultSuccess(result)
+        self.assertIn(f"Would copy parameter '{param1}' with 1 rule(s)", result.out())
+        self.assertIn(f"Would copy template '{temp1}'", result.out())
+        self.assertIn("Would copy 4 parameter(s) and 1 template(s)", result.out())
+        result = self.run_cli(cmd_env, base_cmd + "proj ls")
+        self.assertNotIn(dest_name, result.out())
+
+        result = self.run_cli(cmd_env, copy_cmd)
+        self.assertResultSuccess(result)
+        self.assertIn(f"Created project '{dest_name}'", result.out())
+        self.assertIn(f"Copied parameter '{param1}' with 1 rule(s) and 2 value(s)", result.out())
+        self.assertIn("Copied 4 parameter(s) and 1 template(s)", result.out())
+        self.verify_param(cmd_env, dest_name, param1, "value-1")
+        self.verify_param(cmd_env, dest_name, param1, "env-value-1", env=env_name)
+        self.verify_param(cmd_env, dest_name, param2, "value-1")
+        self.verify_param(cmd_env, dest_name, param2, "env-value-1", env=env_name)
+        self.verify_param(cmd_env, dest_name, param4, "param1=value-1")
+        entry = self.get_param(cmd_env, dest_name, param3, env=DEFAULT_ENV_NAME, secrets=True)
+        self.assertEqual(entry.get("Value"), "secret-3")
+        self.assertEqual(entry.get("Secret"), "true")
+        dest_cmd = base_cmd + f"--project '{dest_name}' "
+        result = self.run_cli(cmd_env, dest_cmd + f"param set {param1} --value 'this-is-way-too-long-for-the-rule'")
+        self.assertResultError(result, "Rule violation")
+        result = self.run_cli(cmd_env, dest_cmd + f"template get {temp1}")
+        self.assertResultSuccess(result)
+        self.assertIn("param1=value-1", result.out())
+
+        # does not overwrite an existing project
+        result = self.run_cli(cmd_env, copy_cmd)
+        self.assertResultError(result, f"Project '{dest_name}' already exists")
+
+        result = self.run_cli(cmd_env, base_cmd + f"proj copy no-such-project '{dest_name}'")
+        self.assertResultError(result, "Project 'no-such-project' does not exist")
+
+        # cleanup
+        self.delete_project(cmd_env, dest_name)
+        self.delete_project(cmd_env, src_name)
+        self.delete_environment(cmd_env, env_name)
@@ -1,7 +1,9 @@
 import os
+import time
+from copy import deepcopy
 from pathlib import Path
 
-from testcase import CT_PROFILE, CT_API_KEY, PROP_MODIFIED
+from testcase import CT_PROFILE, CT_API_KEY, CT_URL, PROP_MODIFIED
 from testcase import TestCase
 
 
@@ -177,3 +179,333 @@ class TestRun(TestCase):
 
         # cleanup
         self.delete_project(cmd_env, proj_name)
+
+    def test_run_watch(self):
+        base_cmd = self.get_cli_base_cmd()
+        cmd_env = self.get_cmd_env()
+        proj_name = self.make_name("run-watch")
+        printenv = self.get_display_env_command()
+        param_name = "SOME_PARAM_NAME"
+        param_value = "some-value"
+
+        self.create_project(cmd_env, proj_name)
+        self.set_param(cmd_env, proj_name, param_name, param_value)
+
+        # the watch ends when the command exits
+        cmd = base_cmd + f"--project {proj_name} run --watch --interval 1 --debounce 1 -- {printenv}"
+        result = self.run_cli(cmd_env, cmd)
+        self.assertResultSuccess(result)
+        self.assertIn(f"{param_name}={param_value}", result.out())
+
+        # watch options are validated
+        cmd = base_cmd + f"--project {proj_name} run --watch --on-change sigkill -- {printenv}"
+        result = self.run_cli(cmd_env, cmd)
+        self.assertResultError(result, "isn't a valid value")
+
+        # cleanup
+        self.delete_project(cmd_env, proj_name)
+
+    def test_run_exit_code(self):
+        base_cmd = self.get_cli_base_cmd()
+        cmd_env = self.get_cmd_env()
+        proj_name = self.make_name("run-exit-code")
+        self.create_project(cmd_env, proj_name)
+
+        sub_cmd = base_cmd + f"--project {proj_name} run "
+        result = self.run_cli(cmd_env, sub_cmd + "-c 'exit 0'")
+        self.assertResultSuccess(result)
+
+        result = self.run_cli(cmd_env, sub_cmd + "-c 'exit 3'")
+        self.assertEqual(result.return_value, 3)
+
+        if os.name != "nt":
+            # killed by a signal is reported as 128+N
+            result = self.run_cli(cmd_env, sub_cmd + "-c 'kill -TERM \\$\\$'")
+            self.assertEqual(result.return_value, 143)
+            self.assertIn("Process was killed by signal 15", result.err())
+
+        # cleanup
+        self.delete_project(cmd_env, proj_name)
+
+    def test_run_secret_files(self):
+        if os.name == "nt":
+            return
+
+        base_cmd = self.get_cli_base_cmd()
+        cmd_env = self.get_cmd_env()
+        proj_name = self.make_name("run-files")
+        printenv = self.get_display_env_command()
+        secret_name = "SECRET_PARAM"
+        secret_value = "my-secret-value"
+        plain_name = "PLAIN_PARAM"
+        plain_value = "my-plain-value"
+
+        self.create_project(cmd_env, proj_name)
+        self.set_param(cmd_env, proj_name, secret_name, secret_value, secret=True)
+        self.set_param(cmd_env, proj_name, plain_name, plain_value)
+        sub_cmd = base_cmd + f"--project {proj_name} run "
+
+        # secrets get written to files, and the values are not in the environment
+        result = self.run_cli(cmd_env, sub_cmd + f"--secret-files -- {printenv}")
+        self.assertResultSuccess(result)
+        self.assertIn(f"{plain_name}={plain_value}", result.out())
+        self.assertIn(f"{secret_name}_FILE=", result.out())
+        self.assertNotIn(secret_value, result.out())
+
+        # the file has the value, and is only readable by the owner
+        cmd = sub_cmd + f"--secret-files -c 'cat ${secret_name}_FILE; stat -c %a ${secret_name}_FILE'"
+        result = self.run_cli(cmd_env, cmd.replace("$", "\\$"))
+        self.assertResultSuccess(result)
+        self.assertIn(secret_value, result.out())
+        self.assertIn("600", result.out())
+
+        # choose the parameter, and name the variable
+        cmd = sub_cmd + f"--file {plain_name} --file-var 'CT_{{name}}_PATH' -- {printenv}"
+        result = self.run_cli(cmd_env, cmd)
+        self.assertResultSuccess(result)
+        self.assertIn(f"CT_{plain_name}_PATH=", result.out())
+        self.assertNotIn(f"{plain_name}={plain_value}", result.out())
+        self.assertIn(f"{secret_name}={secret_value}", result.out())
+
+        # cleanup
+        self.delete_project(cmd_env, proj_name)
+
+    def test_run_key_rules(self):
+        base_cmd = self.get_cli_base_cmd()
+        cmd_env = self.get_cmd_env()
+        proj_name = self.make_name("run-key-rules")
+        printenv = self.get_display_env_command()
+
+        self.create_project(cmd_env, proj_name)
+        self.set_param(cmd_env, proj_name, "svc.name", "my-service")
+        self.set_param(cmd_env, proj_name, "db-url", "postgres://localhost")
+        sub_cmd = base_cmd + f"--project {proj_name} run "
+
+        rules = "--uppercase --replace-invalid --add-prefix APP_ --map db-url=DATABASE_URL "
+        result = self.run_cli(cmd_env, sub_cmd + rules + f"-- {printenv}")
+        self.assertResultSuccess(result)
+        self.assertIn("APP_SVC_NAME=my-service", result.out())
+        self.assertIn("DATABASE_URL=postgres://localhost", result.out())
+        self.assertNotIn("svc.name=", result.out())
+
+        # collisions after the transformation are an error
+        self.set_param(cmd_env, proj_name, "SVC_NAME", "other-service")
+        result = self.run_cli(cmd_env, sub_cmd + f"--uppercase --replace-invalid -- {printenv}")
+        self.assertResultError(result, "Parameter names collide after transformation: SVC_NAME, svc.name -> SVC_NAME")
+
+        # cleanup
+        self.delete_project(cmd_env, proj_name)
+
+    def test_run_layers(self):
+        base_cmd = self.get_cli_base_cmd()
+        cmd_env = self.get_cmd_env()
+        infra_proj = self.make_name("run-layer-infra")
+        app_proj = self.make_name("run-layer-app")
+        printenv = self.get_display_env_command()
+
+        self.create_project(cmd_env, infra_proj)
+        self.create_project(cmd_env, app_proj)
+        self.set_param(cmd_env, infra_proj, "DB_HOST", "db.example.com")
+        self.set_param(cmd_env, infra_proj, "LOG_LEVEL", "warn")
+        self.set_param(cmd_env, app_proj, "LOG_LEVEL", "debug")
+
+        # the current project takes precedence over the layers
+        sub_cmd = base_cmd + f"--project {app_proj} run --layer {infra_proj} "
+        result = self.run_cli(cmd_env, sub_cmd + f"-- {printenv}")
+        self.assertResultSuccess(result)
+        self.assertIn("DB_HOST=db.example.com", result.out())
+        self.assertIn("LOG_LEVEL=debug", result.out())
+
+        # with exclusive inheritance, conflicting layer values are an error
+        result = self.run_cli(cmd_env, sub_cmd + f"--inherit exclusive -- {printenv}")
+        self.assertResultError(result, "Conflicting definitions in run environment for: LOG_LEVEL")
+
+        # bad layer project name
+        result = self.run_cli(cmd_env, base_cmd + f"--project {app_proj} run --layer no-such-proj -- {printenv}")
+        self.assertResultError(result, "The 'no-such-proj' project could not be found")
+
+        # cleanup
+        self.delete_project(cmd_env, app_proj)
+        self.delete_project(cmd_env, infra_proj)
+
+    def test_run_dry_run(self):
+        base_cmd = self.get_cli_base_cmd()
+        cmd_env = self.get_cmd_env()
+        proj_name = self.make_name("run-dry-run")
+        self.create_project(cmd_env, proj_name)
+        self.set_param(cmd_env, proj_name, "DRY_PLAIN", "plain-value")
+        self.set_param(cmd_env, proj_name, "DRY_SECRET", "secret-value", secret=True)
+        self.set_param(cmd_env, proj_name, "HOME", "ct-home")
+
+        sub_cmd = base_cmd + f"--project {proj_name} run --dry-run --set DRY_SET=set-value "
+        result = self.run_cli(cmd_env, sub_cmd + "-f csv")
+        self.assertResultSuccess(result)
+        self.assertIn(f"DRY_PLAIN,plain-value,cloudtruth ({proj_name}/default),", result.out())
+        self.assertIn(f"DRY_SECRET,*****,cloudtruth ({proj_name}/default),", result.out())
+        self.assertIn("DRY_SET,set-value,override,", result.out())
+        self.assertIn("HOME,ct-home,cloudtruth", result.out())
+        self.assertIn("overlay: cloudtruth over inherited", result.out())
+        self.assertNotIn("secret-value", result.out())
+
+        # secrets are only shown when requested
+        result = self.run_cli(cmd_env, sub_cmd + "-f csv --secrets")
+        self.assertResultSuccess(result)
+        self.assertIn(f"DRY_SECRET,secret-value,cloudtruth ({proj_name}/default),", result.out())
+
+        # underlay keeps the inherited value, and removals are shown
+        result = self.run_cli(cmd_env, sub_cmd + "-f csv -i underlay -r DRY_PLAIN")
+        self.assertResultSuccess(result)
+        self.assertIn("HOME,", result.out())
+        self.assertNotIn("HOME,ct-home", result.out())
+        self.assertIn("underlay: inherited over cloudtruth", result.out())
+        self.assertIn("DRY_PLAIN,,removed,removed by request (cloudtruth)", result.out())
+
+        # collisions are shown, along with the error
+        result = self.run_cli(cmd_env, sub_cmd + "-f csv -i exclusive")
+        self.assertResultError(result, "Conflicting definitions in run environment for: HOME")
+        self.assertIn("exclusive: conflicts with inherited", result.out())
+
+        # cleanup
+        self.delete_project(cmd_env, proj_name)
+
+    def test_run_cache(self):
+        base_cmd = self.get_cli_base_cmd()
+        cmd_env = self.get_cmd_env()
+        proj_name = self.make_name("run-cache")
+        printenv = self.get_display_env_command()
+        self.create_project(cmd_env, proj_name)
+        self.set_param(cmd_env, proj_name, "CACHED_PARAM", "cached-value")
+
+        sub_cmd = base_cmd + f"--project {proj_name} run "
+        result = self.run_cli(cmd_env, sub_cmd + f"--cache -- {printenv}")
+        self.assertResultSuccess(result)
+        self.assertIn("CACHED_PARAM=cached-value", result.out())
+
+        # the cached values are used when the server cannot be reached
+        bad_env = deepcopy(cmd_env)
+        bad_env[CT_URL] = "http://127.0.0.1:1"
+        result = self.run_cli(bad_env, sub_cmd + f"--cache -- {printenv}")
+        self.assertEqual(result.return_value, 0)
+        self.assertIn("CACHED_PARAM=cached-value", result.out())
+        self.assertIn("Using cached parameters", result.err())
+
+        # without the cache, it fails
+        result = self.run_cli(bad_env, sub_cmd + f"--no-cache -- {printenv}")
+        self.assertNotEqual(result.return_value, 0)
+
+        # only the cache
+        result = self.run_cli(cmd_env, sub_cmd + f"--cache-only -- {printenv}")
+        self.assertResultSuccess(result)
+        self.assertIn("CACHED_PARAM=cached-value", result.out())
+
+        # too old
+        time.sleep(2)
+        result = self.run_cli(bad_env, sub_cmd + f"--cache --cache-max-age 1 -- {printenv}")
+        self.assertResultError(result, "are too old")
+
+        result = self.run_cli(cmd_env, base_cmd + "cache clear")
+        self.assertResultSuccess(result)
+        self.assertIn("Removed", result.out())
+        result = self.run_cli(cmd_env, sub_cmd + f"--cache-only -- {printenv}")
+        self.assertResultError(result, f"No cached parameters for {proj_name}/default")
+
+        # cleanup
+        self.delete_project(cmd_env, proj_name)
+
+    def test_run_restart(self):
+        base_cmd = self.get_cli_base_cmd()
+        cmd_env = self.get_cmd_env()
+        proj_name = self.make_name("run-restart")
+        self.create_project(cmd_env, proj_name)
+        self.set_param(cmd_env, proj_name, "RESTART_PARAM", "my-value")
+        count_file = Path(f"{proj_name}.count")
+
+        sub_cmd = base_cmd + f"--project {proj_name} run --restart on-failure --max-restarts 2 --restart-delay 0 "
+        cmd = f"-c 'echo \\$RESTART_PARAM >> {count_file}; exit 3'"
+        result = self.run_cli(cmd_env, sub_cmd + cmd)
+        self.assertEqual(result.return_value, 3)
+        self.assertIn("Command exited with code 3, restarting", result.err())
+        self.assertIn("Command was restarted 2 times", result.err())
+        self.assertEqual(count_file.read_text(), "my-value\n" * 3)
+        count_file.unlink()
+
+        # successful commands are not restarted
+        result = self.run_cli(cmd_env, sub_cmd + f"-c 'echo \\$RESTART_PARAM >> {count_file}'")
+        self.assertResultSuccess(result)
+        self.assertEqual(count_file.read_text(), "my-value\n")
+        count_file.unlink()
+
+        # cleanup
+        self.delete_project(cmd_env, proj_name)
+
+    def test_run_templates(self):
+        if os.name == "nt":
+            return
+
+        base_cmd = self.get_cli_base_cmd()
+        cmd_env = self.get_cmd_env()
+        proj_name = self.make_name("run-templates")
+        temp_name = "my-template"
+        self.create_project(cmd_env, proj_name)
+        self.set_param(cmd_env, proj_name, "TEMPLATE_PARAM", "my-secret-value", secret=True)
+        self.set_template(cmd_env, proj_name, temp_name, body="password: {{TEMPLATE_PARAM}}\n")
+        out_file = Path(f"{proj_name}.yaml")
+
+        # the rendered file is only readable by the owner, and removed afterwards
+        sub_cmd = base_cmd + f"--project {proj_name} run --template {temp_name}={out_file} "
+        result = self.run_cli(cmd_env, sub_cmd + f"-c 'cat {out_file}; stat -c %a {out_file}'")
+        self.assertResultSuccess(result)
+        self.assertIn("password: my-secret-value", result.out())
+        self.assertIn("600", result.out())
+        self.assertFalse(out_file.exists())
+
+        # the file is kept when requested
+        result = self.run_cli(cmd_env, sub_cmd + "--keep-templates -c 'true'")
+        self.assertResultSuccess(result)
+        self.assertEqual(out_file.read_text(), "password: my-secret-value\n")
+        out_file.unlink()
+
+        # unknown template
+        result = self.run_cli(cmd_env, sub_cmd + f"--template missing={out_file} -c 'true'")
+        self.assertResultError(result, "missing")
+        self.assertFalse(out_file.exists())
+
+        result = self.run_cli(cmd_env, base_cmd + f"--project {proj_name} run --template {temp_name} -c 'true'")
+        self.assertResultError(result, "Use a '=' to separate the template name and file path")
+
+        # cleanup
+        self.delete_template(cmd_env, proj_name, temp_name)
+        self.delete_project(cmd_env, proj_name)
+
+    def test_run_placeholders(self):
+        base_cmd = self.get_cli_base_cmd()
+        cmd_env = self.get_cmd_env()
+        proj_name = self.make_name("run-placeholders")
+        secret_value = "my-secret-value"
+        self.create_project(cmd_env, proj_name)
+        self.set_param(cmd_env, proj_name, "ARG_USER", "my-user")
+        self.set_param(cmd_env, proj_name, "ARG_PASSWORD", secret_value, secret=True)
+        sub_cmd = base_cmd + f"--project {proj_name} run "
+
+        # placeholders in the command string
+        result = self.run_cli(cmd_env, sub_cmd + "-c 'echo user={{ct:ARG_USER}} password={{ct:ARG_PASSWORD}}'")
+        self.assertResultSuccess(result)
+        self.assertIn(f"user=my-user password={secret_value}", result.out())
+
+        # placeholders in the arguments, where the spaces warning does not show the secret
+        result = self.run_cli(cmd_env, sub_cmd + "-- 'echo spaced' '--password={{ct:ARG_PASSWORD}}'")
+        self.assertIn("command contains spaces", result.err())
+        self.assertIn("{{ct:ARG_PASSWORD}}", result.out())
+        self.assertNotIn(secret_value, result.out())
+        self.assertNotIn(secret_value, result.err())
+
+        # unknown placeholders are left alone, unless strict
+        result = self.run_cli(cmd_env, sub_cmd + "-c 'echo value={{ct:ARG_MISSING}}'")
+        self.assertResultSuccess(result)
+        self.assertIn("value={{ct:ARG_MISSING}}", result.out())
+        self.assertIn("No CloudTruth parameter for placeholder: ARG_MISSING", result.err())
+        result = self.run_cli(cmd_env, sub_cmd + "--strict -c 'echo value={{ct:ARG_MISSING}}'")
+        self.assertResultError(result, "No CloudTruth parameter for placeholder: ARG_MISSING")
+
+        # cleanup
+        self.delete_project(cmd_env, proj_name)
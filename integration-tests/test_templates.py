@@ -973,3 +973,54 @@ PARAMETER={{{{{param1}}}}}
 
         # cleanup
         self.delete_project(cmd_env, proj_name)
+
+    def test_template_diff_semantic(self):
+        base_cmd = self.get_cli_base_cmd()
+        cmd_env = self.get_cmd_env()
+        proj_name = self.make_name("template-semantic")
+        self.create_project(cmd_env, proj_name)
+        env_a = self.make_name("tsem-env-a")
+        self.create_environment(cmd_env, env_a)
+        env_b = self.make_name("tsem-env-b")
+        self.create_environment(cmd_env, env_b)
+
+        # the same document, with the keys in a different order (and format)
+        param1 = "config"
+        self.set_param(cmd_env, proj_name, param1, '{"name": "app", "port": 80}', env=env_a)
+        self.set_param(cmd_env, proj_name, param1, "port: 80\nname: app\n", env=env_b)
+
+        temp_name = "my-template"
+        proj_cmd = base_cmd + f"--project '{proj_name}' "
+        result = self.run_cli(cmd_env, proj_cmd + f"temp set '{temp_name}' --body '{{{{{param1}}}}}'")
+        self.assertResultSuccess(result)
+
+        diff_cmd = proj_cmd + f"temp diff '{temp_name}' -e '{env_a}' -e '{env_b}' "
+        result = self.run_cli(cmd_env, diff_cmd)
+        self.assertResultSuccess(result)
+        self.assertIn("@@", result.out())
+
+        result = self.run_cli(cmd_env, diff_cmd + "--semantic")
+        self.assertResultSuccess(result)
+        self.assertEqual(result.out(), "")
+
+        # key-path level changes
+        self.set_param(cmd_env, proj_name, param1, "port: 8080\nname: app\ndebug: true\n", env=env_b)
+        result = self.run_cli(cmd_env, diff_cmd + "--semantic")
+        self.assertResultSuccess(result)
+        self.assertEqual(result.out(), f"""\
+--- {temp_name} ({env_a} at current)
++++ {temp_name} ({env_b} at current)
++ debug: true
+~ port: 80 -> 8080
+""")
+
+        # falls back to the text diff for anything else
+        self.set_param(cmd_env, proj_name, param1, "not a document", env=env_b)
+        result = self.run_cli(cmd_env, diff_cmd + "--semantic")
+        self.assertResultSuccess(result)
+        self.assertIn("+not a document", result.out())
+
+        # cleanup
+        self.delete_project(cmd_env, proj_name)
+        self.delete_environment(cmd_env, env_a)
+        self.delete_environment(cmd_env, env_b)
@@ -0,0 +1,24 @@
+use crate::config::Config;
+use crate::database::ParameterCache;
+use crate::lib::warn_missing_subcommand;
+use clap::ArgMatches;
+use color_eyre::eyre::Result;
+
+fn proc_cache_clear() -> Result<()> {
+    let count = match Config::parameter_cache_dir() {
+        Some(dir) => ParameterCache::clear(&dir)?,
+        None => 0,
+    };
+    println!("Removed {} cached parameter file(s).", count);
+    Ok(())
+}
+
+/// Process the 'cache' sub-command
+pub fn process_cache_command(subcmd_args: &ArgMatches) -> Result<()> {
+    if subcmd_args.subcommand_matches("clear").is_some() {
+        proc_cache_clear()?;
+    } else {
+        warn_missing_subcommand("cache");
+    }
+    Ok(())
+}
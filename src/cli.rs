@@ -3,6 +3,7 @@ use clap::{
     Arg, ArgMatches, Shell, SubCommand,
 };
 
+pub const ADD_PREFIX_OPT: &str = "add-prefix";
 pub const ADD_USER_OPT: &str = "username-to-add";
 pub const API_KEY_OPT: &str = "api_key";
 pub const AS_OF_ARG: &str = "datetime|tag";
@@ -16,13 +17,16 @@ pub const INTEGRATION_NAME_ARG: &str = "integration-name";
 pub const INVITE_NAME_ARG: &str = "e-mail";
 pub const JMES_PATH_ARG: &str = "JMES";
 pub const KEY_ARG: &str = "KEY";
+pub const MAP_KEY_OPT: &str = "map";
 pub const NAME_ARG: &str = "NAME";
+pub const NO_KEY_RULES_FLAG: &str = "no-key-rules";
 pub const PARENT_ARG: &str = "parent";
 pub const PROJECT_NAME_OPT: &str = "project";
 pub const PULL_NAME_ARG: &str = "import-name";
 pub const PUSH_NAME_ARG: &str = "push-name";
 pub const RAW_FLAG: &str = "raw";
 pub const RENAME_OPT: &str = "new-name";
+pub const REPLACE_INVALID_FLAG: &str = "replace-invalid";
 pub const RM_USER_OPT: &str = "username-to-remove";
 pub const ROLE_ARG: &str = "role";
 pub const RULE_MAX_ARG: &str = "MAX";
@@ -37,9 +41,12 @@ pub const RULE_NO_MIN_LEN_ARG: &str = "NO-MIN-LEN";
 pub const RULE_NO_REGEX_ARG: &str = "NO-REGEX";
 pub const SHOW_TIMES_FLAG: &str = "show-time";
 pub const SECRETS_FLAG: &str = "secrets";
+pub const STRIP_PREFIX_OPT: &str = "strip-prefix";
+pub const SEMANTIC_FLAG: &str = "semantic";
 pub const TAG_NAME_OPT: &str = "tag";
 pub const TAG_NAME_ARG: &str = "tag-name";
 pub const TEMPLATE_FILE_OPT: &str = "FILE";
+pub const UPPERCASE_FLAG: &str = "uppercase";
 pub const VALUES_FLAG: &str = "values";
 
 pub const DELETE_SUBCMD: &str = "delete";
@@ -58,6 +65,26 @@ pub const TASKS_SUBCMD: &str = "tasks";
 pub const TREE_SUBCMD: &str = "tree";
 
 const TRUE_FALSE_VALUES: &[&str] = &["true", "false"];
+// operators between the property and value of a parameter filter, which `property_filter` shares
+pub const FILTER_OPERATORS: &[char] = &['=', '~', '>', '<'];
+// properties that can be used for filtering, sorting, and columns of the parameter list
+const PARAM_PROPERTIES: &[&str] = &[
+    "name",
+    "value",
+    "type",
+    "environment",
+    "fqn",
+    "jmes-path",
+    "description",
+    "secret",
+    "created-at",
+    "modified-at",
+    "rule-count",
+    "raw",
+    "scope",
+    "project-url",
+    "project-name",
+];
 
 const DELETE_ALIASES: &[&str] = &["del", "d"];
 const DIFF_ALIASES: &[&str] = &["difference", "differ", "diff", "di"];
@@ -247,6 +274,10 @@ fn tag_name_opt() -> Arg<'static, 'static> {
         .help("Filter by tag name")
 }
 
+fn semantic_flag() -> Arg<'static, 'static> {
+    Arg::with_name(SEMANTIC_FLAG).long("semantic")
+}
+
 fn project_name_opt() -> Arg<'static, 'static> {
     Arg::with_name(PROJECT_NAME_OPT)
         .long("project")
@@ -347,6 +378,112 @@ fn i32_validator(arg_value: String) -> Result<(), String> {
     }
 }
 
+fn u64_validator(arg_value: String) -> Result<(), String> {
+    match arg_value.parse::<u64>() {
+        Ok(_) => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn run_layer_validator(arg_value: String) -> Result<(), String> {
+    let proj_env = arg_value.split('@').next().unwrap_or_default();
+    if proj_env.split(':').next().unwrap_or_default().is_empty() {
+        return Err("The layer must specify a project name".to_string());
+    }
+    if arg_value.ends_with(':') || arg_value.ends_with('@') {
+        return Err("The layer has an empty environment or tag name".to_string());
+    }
+    Ok(())
+}
+
+fn key_map_validator(arg_value: String) -> Result<(), String> {
+    match arg_value.split_once('=') {
+        Some((src, dest)) if !src.is_empty() && !dest.is_empty() => Ok(()),
+        _ => Err("Use a '=' to separate the parameter and variable names".to_string()),
+    }
+}
+
+fn k8s_name_validator(arg_value: String) -> Result<(), String> {
+    match arg_value.is_empty() {
+        true => Err("The Kubernetes resource name cannot be empty".to_string()),
+        false => Ok(()),
+    }
+}
+
+fn label_validator(arg_value: String) -> Result<(), String> {
+    match arg_value.split_once('=') {
+        Some((key, _)) if !key.is_empty() => Ok(()),
+        _ => Err("Use a '=' to separate the label key and value".to_string()),
+    }
+}
+
+fn param_property_validator(property: &str) -> Result<(), String> {
+    match PARAM_PROPERTIES.contains(&property) {
+        true => Ok(()),
+        false => Err(format!(
+            "Unknown property '{}' -- use one of: {}",
+            property,
+            PARAM_PROPERTIES.join(", ")
+        )),
+    }
+}
+
+fn param_filter_validator(arg_value: String) -> Result<(), String> {
+    match arg_value.find(FILTER_OPERATORS) {
+        Some(index) => param_property_validator(arg_value[..index].trim()),
+        None => {
+            Err("Use one of '=', '~', '>', or '<' to compare the property and value".to_string())
+        }
+    }
+}
+
+fn param_sort_validator(arg_value: String) -> Result<(), String> {
+    let property = arg_value
+        .strip_suffix(":desc")
+        .or_else(|| arg_value.strip_suffix(":asc"))
+        .unwrap_or(&arg_value);
+    param_property_validator(property)
+}
+
+fn template_file_validator(arg_value: String) -> Result<(), String> {
+    match arg_value.split_once('=') {
+        Some((name, path)) if !name.is_empty() && !path.is_empty() => Ok(()),
+        _ => Err("Use a '=' to separate the template name and file path".to_string()),
+    }
+}
+
+/// Arguments for transforming the parameter names into variable names.
+fn key_rule_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name(UPPERCASE_FLAG)
+            .long("uppercase")
+            .help("Convert parameter names to uppercase"),
+        Arg::with_name(REPLACE_INVALID_FLAG)
+            .long("replace-invalid")
+            .help("Replace characters that are not valid in variable names with '_'"),
+        Arg::with_name(ADD_PREFIX_OPT)
+            .long("add-prefix")
+            .takes_value(true)
+            .value_name("prefix")
+            .help("Add the prefix to parameter names"),
+        Arg::with_name(STRIP_PREFIX_OPT)
+            .long("strip-prefix")
+            .takes_value(true)
+            .value_name("prefix")
+            .help("Remove the prefix from parameter names"),
+        Arg::with_name(MAP_KEY_OPT)
+            .long("map")
+            .takes_value(true)
+            .multiple(true)
+            .value_name("SRC=DEST")
+            .validator(key_map_validator)
+            .help("Use the DEST variable name for the SRC parameter"),
+        Arg::with_name(NO_KEY_RULES_FLAG)
+            .long("no-key-rules")
+            .help("Ignore the key rules from the profile"),
+    ]
+}
+
 fn serialized_format_arg() -> Arg<'static, 'static> {
     Arg::with_name(FORMAT_OPT)
         .takes_value(true)
@@ -796,6 +933,26 @@ pub fn build_cli() -> App<'static, 'static> {
                 .visible_aliases(&["parameter", "params", "param", "par", "pa", "p"])
                 .about("Work with CloudTruth parameters")
                 .subcommands(vec![
+                    SubCommand::with_name("apply")
+                        .about(concat!("Create, update, and (optionally) delete parameters to match ",
+                            "a YAML/JSON manifest, after showing the planned changes"))
+                        .arg(Arg::with_name("manifest")
+                            .short("f")
+                            .long("file")
+                            .takes_value(true)
+                            .required(true)
+                            .value_name("FILE")
+                            .help("Manifest file with the parameters and their values by environment"))
+                        .arg(Arg::with_name("prune")
+                            .long("prune")
+                            .help("Delete the project parameters that are not in the manifest"))
+                        .arg(Arg::with_name(FORMAT_OPT)
+                            .long(FORMAT_OPT)
+                            .takes_value(true)
+                            .default_value("table")
+                            .possible_values(&["table", "csv", "json", "yaml"])
+                            .help("Format for the planned changes"))
+                        .arg(confirm_flag()),
                     SubCommand::with_name(DELETE_SUBCMD)
                         .visible_aliases(DELETE_ALIASES)
                         .about("Delete the parameter from the project")
@@ -803,18 +960,51 @@ pub fn build_cli() -> App<'static, 'static> {
                         .arg(key_arg().help("Name of parameter to delete")),
                     SubCommand::with_name("drift")
                         .visible_aliases(&["dri", "dr"])
-                        .about("Determine drift between current environment and project parameters")
+                        .about(concat!("Determine drift between the project parameters and the current ",
+                            "environment, a file, or a process environment"))
                         .args(&[
                             table_format_options().help("Format for differences"),
                             param_as_of_arg(),
                             secrets_display_flag(),
                             values_flag(),
+                            Arg::with_name("file")
+                                .long("file")
+                                .takes_value(true)
+                                .conflicts_with("pid")
+                                .help(concat!("Compare to the variables in a dotenv file, or a JSON/YAML ",
+                                    "file (by .json, .yaml, or .yml extension)")),
+                            Arg::with_name("pid")
+                                .long("pid")
+                                .takes_value(true)
+                                .validator(u64_validator)
+                                .help("Compare to the environment of a running process (from /proc/<pid>/environ)"),
+                            Arg::with_name("exclude")
+                                .long("exclude")
+                                .takes_value(true)
+                                .multiple(true)
+                                .number_of_values(1)
+                                .value_name("name")
+                                .help("Variable name to ignore, where a trailing '*' matches a prefix"),
+                            Arg::with_name("no-default-excludes")
+                                .long("no-default-excludes")
+                                .help("Do not ignore the shell variables (e.g. PATH, HOME) or CLOUDTRUTH_ variables"),
+                            Arg::with_name("exit-code")
+                                .long("exit-code")
+                                .help("Exit with status 59 when drift is found"),
                         ]),
+                    SubCommand::with_name(EDIT_SUBCMD)
+                        .visible_aliases(EDIT_ALIASES)
+                        .about(concat!("Edit the parameter value for the selected environment in ",
+                            "the editor, and check it before saving"))
+                        .arg(key_arg().help("Name of the parameter to edit"))
+                        .arg(confirm_flag())
+                        .arg(secrets_display_flag().help("Display the changes to a secret value")),
                     SubCommand::with_name("export")
                         .visible_aliases(&["expo", "exp", "ex"])
                         .about(concat!("Export selected parameters to a known output format. ",
                             "Exported parameters are limited to alphanumeric and underscore  in ",
-                            "key names. Formats available are: dotenv, docker, and shell."))
+                            "key names. Formats available are: dotenv, docker, shell, json, yaml, ",
+                            "k8s-secret, k8s-configmap, properties, and toml."))
                         .arg(Arg::with_name("contains")
                             .long("contains")
                             .help("Return parameters with keys containing search")
@@ -831,13 +1021,41 @@ pub fn build_cli() -> App<'static, 'static> {
                             .possible_value("docker")
                             .possible_value("dotenv")
                             .possible_value("shell")
+                            .possible_value("json")
+                            .possible_value("yaml")
+                            .possible_value("k8s-secret")
+                            .possible_value("k8s-configmap")
+                            .possible_value("properties")
+                            .possible_value("toml")
                             .index(1))
+                        .arg(Arg::with_name("k8s-name")
+                            .long("k8s-name")
+                            .takes_value(true)
+                            .validator(k8s_name_validator)
+                            .help("Name of the Kubernetes resource [default: project name]"))
+                        .arg(Arg::with_name("label")
+                            .long("label")
+                            .takes_value(true)
+                            .multiple(true)
+                            .number_of_values(1)
+                            .validator(label_validator)
+                            .help("Label for the Kubernetes resource (as 'key=value')"))
+                        .arg(Arg::with_name("namespace")
+                            .long("namespace")
+                            .takes_value(true)
+                            .help("Namespace for the Kubernetes resource"))
+                        .arg(Arg::with_name("nest")
+                            .long("nest")
+                            .takes_value(true)
+                            .value_name("separator")
+                            .help("Nest the YAML keys by splitting the names on the separator"))
                         .arg(param_as_of_arg())
-                        .arg(secrets_display_flag().help("Display the secret parameter values"))
+                        .arg(secrets_display_flag().help("Display the secret parameter values (required for k8s-secret)"))
                         .arg(Arg::with_name("starts_with")
                             .long("starts-with")
                             .help("Return parameters starting with search")
-                            .takes_value(true)),
+                            .takes_value(true))
+                        .args(&key_rule_args()),
                     SubCommand::with_name("environment")
                         .visible_aliases(&["environ", "env"])
                         .about("Shows the environments with parameter overrides")
@@ -850,6 +1068,13 @@ pub fn build_cli() -> App<'static, 'static> {
                         .arg(show_times_arg())
                         .arg(table_format_options().help("Format for parameter values"))
                         .arg(secrets_display_flag().help("Display secret values in environments")),
+                    SubCommand::with_name(HISTORY_SUBCMD)
+                        .visible_aliases(HISTORY_ALIASES)
+                        .about("Display parameter history")
+                        .arg(key_arg().required(false).help("Parameter name (optional)"))
+                        .arg(as_of_arg().help("Date/time (or tag) of parameter history"))
+                        .arg(table_format_options().help("Format for the parameter history"))
+                        .arg(secrets_display_flag().help("Display secret values in the history")),
                     SubCommand::with_name(GET_SUBCMD)
                         .about("Gets value for parameter in the selected environment")
                         .arg(param_as_of_arg())
@@ -877,12 +1102,138 @@ pub fn build_cli() -> App<'static, 'static> {
                         .arg(Arg::with_name("children")
                             .long("children")
                             .help("Display the parameters defined in a child project"))
+                        .arg(Arg::with_name("filter")
+                            .long("filter")
+                            .takes_value(true)
+                            .multiple(true)
+                            .number_of_values(1)
+                            .validator(param_filter_validator)
+                            .help(concat!("Only list parameters where the property matches (e.g. ",
+                                "'type=integer', 'name~^DB_', or 'modified-at>2026-01-01')")))
+                        .arg(Arg::with_name("sort")
+                            .long("sort")
+                            .takes_value(true)
+                            .value_name("property[:desc]")
+                            .validator(param_sort_validator)
+                            .help("Sort the parameters by the property"))
+                        .arg(Arg::with_name("columns")
+                            .long("columns")
+                            .takes_value(true)
+                            .multiple(true)
+                            .use_delimiter(true)
+                            .possible_values(PARAM_PROPERTIES)
+                            .help("Properties to display (implies --values)"))
                         .arg(values_flag().help("Display parameter information/values"))
                         .arg(param_as_of_arg())
                         .arg(show_times_arg())
                         .arg(table_format_options().help("Format for parameter values data"))
                         .arg(secrets_display_flag().help("Display the secret parameter values"))
                         .arg(immediate_parameters_flag()),
+                    SubCommand::with_name("promote")
+                        .about(concat!("Copy the parameter values from one environment to ",
+                            "another, after tagging the source environment"))
+                        .arg(Arg::with_name("from")
+                            .long("from")
+                            .takes_value(true)
+                            .required(true)
+                            .value_name("environment")
+                            .help("Environment with the values to promote"))
+                        .arg(Arg::with_name("to")
+                            .long("to")
+                            .takes_value(true)
+                            .required(true)
+                            .value_name("environment")
+                            .help("Environment to receive the promoted values"))
+                        .arg(Arg::with_name("keys")
+                            .long("keys")
+                            .takes_value(true)
+                            .multiple(true)
+                            .use_delimiter(true)
+                            .value_name("names")
+                            .help("Only promote the named parameters"))
+                        .arg(Arg::with_name("starts_with")
+                            .long("starts-with")
+                            .takes_value(true)
+                            .conflicts_with("keys")
+                            .help("Only promote parameters starting with search"))
+                        .arg(Arg::with_name("skip-secrets")
+                            .long("skip-secrets")
+                            .help("Do not promote secret parameters"))
+                        .arg(Arg::with_name("skip-external")
+                            .long("skip-external")
+                            .help("Do not promote values that are external references"))
+                        .arg(Arg::with_name("remove-inherited")
+                            .long("remove-inherited")
+                            .help(concat!("Remove the overrides in the destination environment that ",
+                                "match the inherited value")))
+                        .arg(tag_name_opt()
+                            .help("Name of the tag for the source environment [default: promote-<to>-<time>]"))
+                        .arg(confirm_flag())
+                        .arg(secrets_display_flag().help("Display the secret values in the changes"))
+                        .arg(table_format_options().help("Format for the changes")),
+                    SubCommand::with_name("references")
+                        .visible_aliases(&["refs", "ref"])
+                        .about(concat!("Show the templates and evaluated parameters that reference a ",
+                            "parameter in the project and its descendants"))
+                        .arg(key_arg().help("Name of the referenced parameter"))
+                        .arg(table_format_options().help("Format for the references")),
+                    SubCommand::with_name("rename")
+                        .visible_aliases(&["mv"])
+                        .about(concat!("Rename a parameter, and update the templates and evaluated ",
+                            "parameters that reference it"))
+                        .arg(key_arg().help("Current name of the parameter"))
+                        .arg(Arg::with_name("NEW_NAME")
+                            .required(true)
+                            .index(2)
+                            .help("New name for the parameter"))
+                        .arg(Arg::with_name("dry-run")
+                            .long("dry-run")
+                            .help("Show the changes without making them"))
+                        .arg(confirm_flag())
+                        .arg(table_format_options().help("Format for the reference changes")),
+                    SubCommand::with_name("rollback")
+                        .about(concat!("Set the value(s) in the selected environment back to the ",
+                            "values at an earlier time or tag"))
+                        .arg(key_arg()
+                            .required_unless("all")
+                            .help("Name of parameter to roll back"))
+                        .arg(Arg::with_name("all")
+                            .long("all")
+                            .conflicts_with(KEY_ARG)
+                            .help("Roll back all the parameters in the environment"))
+                        .arg(Arg::with_name("to")
+                            .long("to")
+                            .takes_value(true)
+                            .required(true)
+                            .value_name("timestamp|tag")
+                            .help("Date/time (or tag) of the values to restore"))
+                        .arg(confirm_flag())
+                        .arg(secrets_display_flag().help("Display the secret values in the changes"))
+                        .arg(table_format_options().help("Format for the changes")),
+                    SubCommand::with_name("search")
+                        .about("Search for parameters in all the projects")
+                        .arg(Arg::with_name("PATTERN")
+                            .required(true)
+                            .index(1)
+                            .help("Text to find in the parameter names (case-insensitive), or a regular expression with --regex"))
+                        .arg(Arg::with_name("regex")
+                            .long("regex")
+                            .help("Use the pattern as a regular expression"))
+                        .arg(Arg::with_name("values")
+                            .long("values")
+                            .help("Search in the parameter values, too"))
+                        .arg(Arg::with_name("param-type")
+                            .long("type")
+                            .takes_value(true)
+                            .help("Only find parameters of this type"))
+                        .arg(Arg::with_name("environment")
+                            .long("environment")
+                            .takes_value(true)
+                            .multiple(true)
+                            .number_of_values(1)
+                            .help("Environment(s) for the values [default: current environment]"))
+                        .arg(secrets_display_flag().help("Search and display the secret values"))
+                        .arg(table_format_options().help("Format for the matching parameters")),
                     SubCommand::with_name(SET_SUBCMD)
                         .visible_aliases(SET_ALIASES)
                         .about(concat!("Set a value in the selected project/environment for ",
@@ -904,7 +1255,8 @@ pub fn build_cli() -> App<'static, 'static> {
                             .short("p")
                             .long("prompt")
                             .help("Set the static value using unecho'd terminal"))
-                        .arg(rename_option().help("New parameter name"))
+                        .arg(rename_option().help(concat!("New parameter name (use 'parameters rename' ",
+                            "to also update the references)")))
                         .arg(Arg::with_name("secret")
                             .long("secret")
                             .takes_value(true)
@@ -933,6 +1285,10 @@ pub fn build_cli() -> App<'static, 'static> {
                         .arg(rule_no_min_len_arg())
                         .arg(rule_regex_arg())
                         .arg(rule_no_regex_arg())
+                        .arg(Arg::with_name("check")
+                            .long("check")
+                            .help(concat!("Check the value against the parameter rules and type, ",
+                                "without setting anything")))
                         .arg(Arg::with_name("create-child")
                             .long("create-child")
                             .help("Create a parameter in the child project"))
@@ -949,10 +1305,26 @@ pub fn build_cli() -> App<'static, 'static> {
                         .about(concat!("Remove a value/override from the selected ",
                             "project/environment and leaves the parameter in place."))
                         .arg(key_arg().help("Name of parameter to unset")),
+                    SubCommand::with_name("validate")
+                        .about(concat!("Check a value against the parameter rules and type, ",
+                            "and show all the violations"))
+                        .arg(key_arg().help("Name of the parameter"))
+                        .arg(Arg::with_name("VALUE")
+                            .required(true)
+                            .index(2)
+                            .allow_hyphen_values(true)
+                            .help("Value to check")),
                     SubCommand::with_name(DIFF_SUBCMD)
                         .visible_aliases(DIFF_ALIASES)
-                        .about("Show differences between properties from two environments")
-                        .arg(multi_env_name_arg())
+                        .about("Show differences between properties from environments and/or times")
+                        .arg(multi_env_name_arg().help("Environment(s) to be compared"))
+                        .arg(Arg::with_name("all")
+                            .long("all")
+                            .help("Show all the parameters, including the ones without differences"))
+                        .arg(semantic_flag()
+                            .conflicts_with("properties")
+                            .help(concat!("Compare JSON/YAML values by key-path, ",
+                                "instead of as plain text")))
                         .arg(Arg::with_name("properties")
                             .short("p")
                             .long("property")
@@ -973,7 +1345,8 @@ pub fn build_cli() -> App<'static, 'static> {
                             .help("List of the properties to compare."))
                         .arg(param_as_of_arg()
                             .multiple(true)
-                            .help("Up to two times to be compared"))
+                            .help(concat!("Times (or tags) to be compared, one for each environment (or one for ",
+                                "all of three or more environments)")))
                         .arg(table_format_options().help("Display difference format"))
                         .arg(secrets_display_flag().help("Show secret values"))
                         .arg(immediate_parameters_flag()),
@@ -1008,6 +1381,8 @@ pub fn build_cli() -> App<'static, 'static> {
                     .arg(raw_arg().help("Compare unevaluated template bodies"))
                     .arg(multi_env_name_arg())
                     .arg(as_of_arg().multiple(true).help("Up to two times to be compared"))
+                    .arg(semantic_flag().help(concat!("Compare JSON/YAML templates by key-path, ",
+                        "instead of by lines")))
                     .about("Show differences between templates"),
                 SubCommand::with_name(EDIT_SUBCMD)
                     .visible_aliases(EDIT_ALIASES)
@@ -1083,13 +1458,13 @@ pub fn build_cli() -> App<'static, 'static> {
                         .long("command")
                         .short("c")
                         .takes_value(true)
-                        .help("Run this command"),
+                        .help("Run this command, where '{{ct:NAME}}' is replaced by the parameter value"),
                     Arg::with_name("arguments")
                         .takes_value(true)
                         .multiple(true)
                         .allow_hyphen_values(true)
                         .last(true)
-                        .help("Treat the rest of the arguments as the command"),
+                        .help("Treat the rest of the arguments as the command, where '{{ct:NAME}}' is replaced by the parameter value"),
                     Arg::with_name("permissive")
                         .long("permissive")
                         .short("p")
@@ -1097,14 +1472,146 @@ pub fn build_cli() -> App<'static, 'static> {
                     Arg::with_name("strict")
                         .long("strict")
                         .help("Fail when any parameters are unset"),
+                    Arg::with_name("layer")
+                        .long("layer")
+                        .short("l")
+                        .takes_value(true)
+                        .multiple(true)
+                        .value_name("project[:environment[@tag]]")
+                        .validator(run_layer_validator)
+                        .help(concat!(
+                            "Add parameters from another project/environment. Later layers take ",
+                            "precedence, and the current project/environment takes precedence over ",
+                            "all layers"
+                        )),
+                    Arg::with_name("secret-files")
+                        .long("secret-files")
+                        .help("Write secret parameters to files, instead of environment variables"),
+                    Arg::with_name("file")
+                        .long("file")
+                        .takes_value(true)
+                        .multiple(true)
+                        .value_name("name")
+                        .help("Write the parameter to a file, instead of an environment variable"),
+                    Arg::with_name("file-var")
+                        .long("file-var")
+                        .takes_value(true)
+                        .value_name("template")
+                        .default_value("{name}_FILE")
+                        .help("Name of the variable with the file path, where '{name}' is replaced by the parameter name"),
+                    Arg::with_name("template")
+                        .long("template")
+                        .takes_value(true)
+                        .multiple(true)
+                        .value_name("NAME=PATH")
+                        .validator(template_file_validator)
+                        .help("Render the template into the file (readable only by the owner) before running the command"),
+                    Arg::with_name("keep-templates")
+                        .long("keep-templates")
+                        .help("Keep the rendered template files after the command exits"),
+                    Arg::with_name("watch")
+                        .long("watch")
+                        .short("w")
+                        .help("Watch for parameter changes, and restart or signal the command when they change"),
+                    Arg::with_name("interval")
+                        .long("interval")
+                        .takes_value(true)
+                        .value_name("seconds")
+                        .validator(u64_validator)
+                        .default_value("60")
+                        .help("Number of seconds between checks for parameter changes, when watching"),
+                    Arg::with_name("debounce")
+                        .long("debounce")
+                        .takes_value(true)
+                        .value_name("seconds")
+                        .validator(u64_validator)
+                        .default_value("5")
+                        .help("Number of seconds parameter values must be stable before acting on a change, when watching"),
+                    Arg::with_name("on-change")
+                        .long("on-change")
+                        .takes_value(true)
+                        .case_insensitive(true)
+                        .possible_value("restart")
+                        .possible_value("sighup")
+                        .possible_value("sigterm")
+                        .default_value("restart")
+                        .help("Action when watched parameters change: restart the command with the new values, or send it a signal"),
+                    Arg::with_name("restart")
+                        .long("restart")
+                        .takes_value(true)
+                        .case_insensitive(true)
+                        .possible_value("no")
+                        .possible_value("on-failure")
+                        .possible_value("always")
+                        .default_value("no")
+                        .help("When to restart the command after it exits, with the parameters fetched again before each restart"),
+                    Arg::with_name("max-restarts")
+                        .long("max-restarts")
+                        .takes_value(true)
+                        .value_name("count")
+                        .validator(u64_validator)
+                        .default_value("5")
+                        .help("Maximum number of times to restart the command, where 0 is unlimited"),
+                    Arg::with_name("restart-delay")
+                        .long("restart-delay")
+                        .takes_value(true)
+                        .value_name("seconds")
+                        .validator(u64_validator)
+                        .default_value("1")
+                        .help("Number of seconds before the first restart, which doubles (up to a minute) for each quick failure"),
+                    Arg::with_name("dry-run")
+                        .long("dry-run")
+                        .visible_alias("print-env")
+                        .help("Print the computed environment, and where each variable came from, without running a command"),
+                    Arg::with_name(SECRETS_FLAG)
+                        .long(SECRETS_FLAG)
+                        .help("Display secret values in the computed environment"),
+                    table_format_options().help("Format for the computed environment"),
+                    Arg::with_name("cache")
+                        .long("cache")
+                        .help("Cache the parameters locally (encrypted), and use the cache when the server cannot be reached"),
+                    Arg::with_name("no-cache")
+                        .long("no-cache")
+                        .conflicts_with_all(&["cache", "cache-only"])
+                        .help("Do not use the local parameter cache, even when enabled in the profile"),
+                    Arg::with_name("cache-only")
+                        .long("cache-only")
+                        .help("Only use the locally cached parameters, without contacting the server"),
+                    Arg::with_name("cache-max-age")
+                        .long("cache-max-age")
+                        .takes_value(true)
+                        .value_name("seconds")
+                        .validator(u64_validator)
+                        .default_value("86400")
+                        .help("Maximum age of cached parameters that can be used, where 0 is unlimited"),
                     param_as_of_arg(),
                 ])
+                .args(&key_rule_args())
         )
         .subcommand(
             SubCommand::with_name("projects")
                 .visible_aliases(&["project", "proj"])
                 .about("Work with CloudTruth projects")
                 .subcommands(vec![
+                    SubCommand::with_name("copy")
+                        .visible_aliases(&["cp", "clone"])
+                        .about(concat!("Copy the parameters (with rules and values) of a project ",
+                            "into a new project"))
+                        .arg(Arg::with_name("source")
+                            .required(true)
+                            .index(1)
+                            .help("Name of the project to copy"))
+                        .arg(Arg::with_name("destination")
+                            .required(true)
+                            .index(2)
+                            .help("Name of the new project"))
+                        .arg(description_option().help("New project's description [default: source description]"))
+                        .arg(Arg::with_name("templates")
+                            .long("templates")
+                            .help("Copy the templates, too"))
+                        .arg(Arg::with_name("dry-run")
+                            .long("dry-run")
+                            .help("Show what would be copied, without creating anything")),
                     SubCommand::with_name(DELETE_SUBCMD)
                         .visible_aliases(DELETE_ALIASES)
                         .about("Delete specified CloudTruth project")
@@ -1613,6 +2120,14 @@ pub fn build_cli() -> App<'static, 'static> {
                     ])
             ])
         )
+        .subcommand(SubCommand::with_name("cache")
+            .about("Manage the local parameter cache used by 'run'")
+            .subcommands([
+                SubCommand::with_name("clear")
+                    .visible_aliases(&["clr"])
+                    .about("Remove all the locally cached parameters")
+            ])
+        )
         .subcommand(SubCommand::with_name("backup")
             .visible_aliases(&["back", "ba"])
             .about("Manage backups of CloudTruth data")
@@ -21,17 +21,15 @@ impl ConfigEnv {
             server_url: Self::get_override(CT_SERVER_URL),
             source_profile: None,
             accept_invalid_certs: Self::get_accept_invalid_certs(),
+            key_rules: None,
+            run_cache: None,
         }
     }
 
     pub fn get_override(config_name: &str) -> Option<String> {
         let value = env::var(config_name);
 
-        if let Ok(value) = value {
-            Some(value)
-        } else {
-            None
-        }
+        value.ok()
     }
 
     pub fn get_duration_override() -> Option<u64> {
@@ -67,10 +65,7 @@ impl ConfigEnv {
 
     pub fn get_rest_page_size() -> Option<i32> {
         match Self::get_override(CT_REST_PAGE_SIZE) {
-            Some(env_value) => match env_value.trim().parse() {
-                Ok(int_value) => Some(int_value),
-                _ => None,
-            },
+            Some(env_value) => env_value.trim().parse().ok(),
             _ => None,
         }
     }
@@ -130,7 +125,9 @@ mod tests {
                 rest_page_size: None,
                 server_url: Some("http://localhost:7001/graphql".to_string()),
                 source_profile: None,
-                accept_invalid_certs: Some(true)
+                accept_invalid_certs: Some(true),
+                key_rules: None,
+                run_cache: None,
             },
             ConfigEnv::load_profile()
         );
@@ -43,6 +43,12 @@ impl ConfigFile {
               #   description: Read-only user on a different project
               #   project: other-project-name
               #   environment: pre-production
+              #   key_rules:
+              #     uppercase: true
+              #     replace_invalid: true
+              #     map:
+              #       db.url: DATABASE_URL
+              #   run_cache: true
         "#
         )
     }
@@ -135,6 +141,8 @@ impl ConfigFile {
             server_url: None,
             source_profile: source.map(String::from),
             accept_invalid_certs: None,
+            key_rules: None,
+            run_cache: None,
         };
 
         let profiles = config_file.profiles.borrow_mut();
@@ -343,7 +351,8 @@ impl ConfigFile {
 mod tests {
     use crate::config::file::{ConfigFile, ConfigFileError};
     use crate::config::profiles::ProfileDetails;
-    use crate::config::{Action, Frequency, Updates};
+    use crate::config::update::Frequency;
+    use crate::config::{Action, Updates};
     use assert_matches::assert_matches;
     use chrono::NaiveDate;
     use indoc::indoc;
@@ -0,0 +1,235 @@
+use crate::cli::{
+    ADD_PREFIX_OPT, MAP_KEY_OPT, NO_KEY_RULES_FLAG, REPLACE_INVALID_FLAG, STRIP_PREFIX_OPT,
+    UPPERCASE_FLAG,
+};
+use clap::ArgMatches;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::error;
+use std::fmt::{self, Formatter};
+
+/// Rules for transforming parameter names into variable names (e.g. for `run` and `export`).
+///
+/// An explicit `map` entry takes precedence over the other rules. Otherwise, the rules are
+/// applied in order: strip prefix, uppercase, replace invalid characters, and add prefix.
+#[derive(Clone, Deserialize, Debug, PartialEq, Eq, Serialize, Default)]
+#[serde(default)]
+pub struct KeyRules {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uppercase: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replace_invalid: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub add_prefix: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strip_prefix: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub map: BTreeMap<String, String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum KeyRulesError {
+    Collisions(Vec<String>),
+}
+
+impl fmt::Display for KeyRulesError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            KeyRulesError::Collisions(collisions) => {
+                write!(
+                    f,
+                    "Parameter names collide after transformation: {}",
+                    collisions.join("; ")
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for KeyRulesError {}
+
+impl KeyRules {
+    /// Creates the rules from the command line arguments, using the profile `defaults` for
+    /// anything not specified (unless the defaults are disabled).
+    pub fn from_args(args: &ArgMatches, defaults: &KeyRules) -> KeyRules {
+        let mut map = BTreeMap::new();
+        for item in args.values_of_lossy(MAP_KEY_OPT).unwrap_or_default() {
+            // NOTE: the CLI validates there is an '='
+            if let Some((src, dest)) = item.split_once('=') {
+                map.insert(src.to_string(), dest.to_string());
+            }
+        }
+        let cli_rules = KeyRules {
+            uppercase: args.is_present(UPPERCASE_FLAG).then_some(true),
+            replace_invalid: args.is_present(REPLACE_INVALID_FLAG).then_some(true),
+            add_prefix: args.value_of(ADD_PREFIX_OPT).map(String::from),
+            strip_prefix: args.value_of(STRIP_PREFIX_OPT).map(String::from),
+            map,
+        };
+        if args.is_present(NO_KEY_RULES_FLAG) {
+            cli_rules
+        } else {
+            defaults.merge(&cli_rules)
+        }
+    }
+
+    #[must_use]
+    pub(crate) fn merge(&self, other: &Self) -> KeyRules {
+        let mut map = self.map.clone();
+        map.extend(other.map.clone());
+        KeyRules {
+            uppercase: other.uppercase.or(self.uppercase),
+            replace_invalid: other.replace_invalid.or(self.replace_invalid),
+            add_prefix: other.add_prefix.clone().or_else(|| self.add_prefix.clone()),
+            strip_prefix: other
+                .strip_prefix
+                .clone()
+                .or_else(|| self.strip_prefix.clone()),
+            map,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        !self.uppercase.unwrap_or(false)
+            && !self.replace_invalid.unwrap_or(false)
+            && self.add_prefix.is_none()
+            && self.strip_prefix.is_none()
+            && self.map.is_empty()
+    }
+
+    /// Transforms a single parameter name.
+    pub fn transform(&self, name: &str) -> String {
+        if let Some(dest) = self.map.get(name) {
+            return dest.clone();
+        }
+
+        let mut result = name;
+        if let Some(prefix) = &self.strip_prefix {
+            result = result.strip_prefix(prefix.as_str()).unwrap_or(result);
+        }
+        let mut result = result.to_string();
+        if self.uppercase.unwrap_or(false) {
+            result = result.to_uppercase();
+        }
+        if self.replace_invalid.unwrap_or(false) {
+            result = result
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect();
+            if result.starts_with(|c: char| c.is_ascii_digit()) {
+                result.insert(0, '_');
+            }
+        }
+        if let Some(prefix) = &self.add_prefix {
+            result.insert_str(0, prefix);
+        }
+        result
+    }
+
+    /// Transforms all the parameter names, and returns a map of the original name to the new name.
+    ///
+    /// It is an error for more than one parameter to be transformed into the same name.
+    pub fn transform_all<'a, I>(&self, names: I) -> Result<HashMap<String, String>, KeyRulesError>
+    where
+        I: IntoIterator<Item = &'a String>,
+    {
+        let mut sources: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        let mut result = HashMap::new();
+        for name in names {
+            let transformed = self.transform(name);
+            sources
+                .entry(transformed.clone())
+                .or_default()
+                .push(name.clone());
+            result.insert(name.clone(), transformed);
+        }
+
+        let collisions: Vec<String> = sources
+            .into_iter()
+            .filter(|(_, srcs)| srcs.len() > 1)
+            .map(|(dest, mut srcs)| {
+                srcs.sort();
+                format!("{} -> {}", srcs.join(", "), dest)
+            })
+            .collect();
+        if !collisions.is_empty() {
+            return Err(KeyRulesError::Collisions(collisions));
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transform_rules() {
+        let rules = KeyRules::default();
+        assert!(rules.is_empty());
+        assert_eq!(rules.transform("my.param-name"), "my.param-name");
+
+        let rules = KeyRules {
+            uppercase: Some(true),
+            replace_invalid: Some(true),
+            ..KeyRules::default()
+        };
+        assert!(!rules.is_empty());
+        assert_eq!(rules.transform("my.param-name"), "MY_PARAM_NAME");
+        assert_eq!(rules.transform("1st"), "_1ST");
+
+        let mut rules = KeyRules {
+            uppercase: Some(true),
+            add_prefix: Some("APP_".to_string()),
+            strip_prefix: Some("svc_".to_string()),
+            ..KeyRules::default()
+        };
+        rules
+            .map
+            .insert("svc_special".to_string(), "Special".to_string());
+        assert_eq!(rules.transform("svc_name"), "APP_NAME");
+        assert_eq!(rules.transform("other"), "APP_OTHER");
+        assert_eq!(rules.transform("svc_special"), "Special");
+    }
+
+    #[test]
+    fn transform_collisions() {
+        let rules = KeyRules {
+            uppercase: Some(true),
+            replace_invalid: Some(true),
+            ..KeyRules::default()
+        };
+        let names = vec!["a.b".to_string(), "A_B".to_string(), "c".to_string()];
+        assert_eq!(
+            rules.transform_all(&names),
+            Err(KeyRulesError::Collisions(vec![
+                "A_B, a.b -> A_B".to_string()
+            ]))
+        );
+
+        let names = vec!["a.b".to_string(), "c".to_string()];
+        let result = rules.transform_all(&names).unwrap();
+        assert_eq!(result.get("a.b").unwrap(), "A_B");
+        assert_eq!(result.get("c").unwrap(), "C");
+    }
+
+    #[test]
+    fn merge_rules() {
+        let mut defaults = KeyRules {
+            uppercase: Some(true),
+            add_prefix: Some("APP_".to_string()),
+            ..KeyRules::default()
+        };
+        defaults.map.insert("a".to_string(), "A1".to_string());
+        let mut other = KeyRules {
+            add_prefix: Some("SVC_".to_string()),
+            ..KeyRules::default()
+        };
+        other.map.insert("b".to_string(), "B1".to_string());
+
+        let merged = defaults.merge(&other);
+        assert_eq!(merged.uppercase, Some(true));
+        assert_eq!(merged.add_prefix, Some("SVC_".to_string()));
+        assert_eq!(merged.map.len(), 2);
+    }
+}
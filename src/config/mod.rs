@@ -2,14 +2,16 @@ mod config_date;
 pub(crate) mod env;
 mod file;
 mod file_errors;
+mod key_rules;
 mod profiles;
 mod update;
 
 use crate::cli::binary_name;
 use crate::config::env::ConfigEnv;
 use crate::config::file::ConfigFile;
+pub use crate::config::key_rules::KeyRules;
 use crate::config::profiles::{Profile, ProfileDetails};
-pub use crate::config::update::{Action, Frequency, Updates};
+pub use crate::config::update::{Action, Updates};
 use color_eyre::eyre::Result;
 use directories::ProjectDirs;
 use indoc::formatdoc;
@@ -23,6 +25,9 @@ static INSTANCE: OnceCell<Config> = OnceCell::new();
 /// Configuration file name -- location will vary on different systems
 const CONFIG_FILE_NAME: &str = "cli.yml";
 
+/// Cache directory name for parameter values used by `run` -- location will vary on different systems
+const PARAMETER_CACHE_DIR_NAME: &str = "parameters";
+
 /// Default OpenApi server URL
 pub const DEFAULT_SERVER_URL: &str = "https://api.cloudtruth.io";
 
@@ -99,6 +104,8 @@ pub struct Config {
     pub rest_page_size: Option<i32>,
     pub rest_success: Vec<String>,
     pub accept_invalid_certs: Option<bool>,
+    pub key_rules: KeyRules,
+    pub run_cache: bool,
 }
 
 pub struct ValidationError {
@@ -131,6 +138,8 @@ fn profile_into_config(prof_name: &str, profile: &Profile) -> Config {
         rest_success: profile.rest_success.clone(),
         rest_page_size: profile.rest_page_size,
         accept_invalid_certs: profile.accept_invalid_certs,
+        key_rules: profile.key_rules.clone().unwrap_or_default(),
+        run_cache: profile.run_cache.unwrap_or(false),
     }
 }
 
@@ -171,6 +180,11 @@ impl Config {
             .map(|project_dirs| project_dirs.config_dir().join(CONFIG_FILE_NAME))
     }
 
+    pub fn parameter_cache_dir() -> Option<PathBuf> {
+        ProjectDirs::from("com", ORGANIZATION_NAME, APPLICATION_NAME)
+            .map(|project_dirs| project_dirs.cache_dir().join(PARAMETER_CACHE_DIR_NAME))
+    }
+
     pub fn filename() -> String {
         Config::config_file()
             .unwrap()
@@ -200,6 +214,11 @@ impl Config {
         INSTANCE.get().unwrap()
     }
 
+    /// Gets the global configuration, which must already be initialized by `init_global()`.
+    pub fn global() -> &'static Self {
+        INSTANCE.get().expect("Configuration is not initialized")
+    }
+
     fn read_config(config_file: &Path) -> Result<String> {
         let contents = fs::read_to_string(config_file)?;
 
@@ -1,3 +1,4 @@
+use crate::config::key_rules::KeyRules;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Deserialize, Debug, PartialEq, Eq, Serialize, Default)]
@@ -25,6 +26,10 @@ pub struct Profile {
     pub source_profile: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub accept_invalid_certs: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key_rules: Option<KeyRules>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub run_cache: Option<bool>,
 }
 
 // TODO: Rick Porter 4/21, fix this so don't have to udpate when Profile is updated
@@ -79,6 +84,11 @@ impl Profile {
             server_url: other.server_url.clone().or_else(|| self.server_url.clone()),
             source_profile: self.source_profile.clone(),
             accept_invalid_certs: other.accept_invalid_certs.or(self.accept_invalid_certs),
+            key_rules: match (&self.key_rules, &other.key_rules) {
+                (Some(rules), Some(other_rules)) => Some(rules.merge(other_rules)),
+                (rules, other_rules) => other_rules.clone().or_else(|| rules.clone()),
+            },
+            run_cache: other.run_cache.or(self.run_cache),
         }
     }
 
@@ -96,6 +106,8 @@ impl Profile {
             server_url: empty_to_none(&self.server_url),
             source_profile: empty_to_none(&self.source_profile),
             accept_invalid_certs: self.accept_invalid_certs,
+            key_rules: self.key_rules.clone(),
+            run_cache: self.run_cache,
         }
     }
 
@@ -110,11 +122,14 @@ impl Profile {
             && self.server_url.is_none()
             && self.source_profile.is_none()
             && self.accept_invalid_certs.is_none()
+            && self.key_rules.is_none()
+            && self.run_cache.is_none()
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::config::key_rules::KeyRules;
     use crate::config::profiles::Profile;
     use indoc::indoc;
 
@@ -134,6 +149,11 @@ mod tests {
             accept_invalid_certs: Some(true),
             rest_success: vec!["proj".to_string(), "env".to_string()],
             server_url: Some("http://localhost:7001/graphql".to_string()),
+            key_rules: Some(KeyRules {
+                uppercase: Some(true),
+                ..KeyRules::default()
+            }),
+            run_cache: Some(true),
             ..Profile::default()
         };
 
@@ -215,6 +235,18 @@ mod tests {
             ..Profile::default()
         };
         assert!(!prof.is_empty());
+
+        let prof = Profile {
+            key_rules: Some(KeyRules::default()),
+            ..Profile::default()
+        };
+        assert!(!prof.is_empty());
+
+        let prof = Profile {
+            run_cache: Some(false),
+            ..Profile::default()
+        };
+        assert!(!prof.is_empty());
     }
 
     #[test]
@@ -231,6 +263,8 @@ mod tests {
             server_url: Some("".to_string()),
             source_profile: Some("".to_string()),
             accept_invalid_certs: None,
+            key_rules: None,
+            run_cache: None,
         };
 
         let prof2 = prof.remove_empty();
@@ -249,6 +283,8 @@ mod tests {
             server_url: Some("url".to_string()),
             source_profile: Some("src-prof".to_string()),
             accept_invalid_certs: None,
+            key_rules: None,
+            run_cache: None,
         };
         let prof2 = prof.remove_empty();
         assert_eq!(prof, prof2);
@@ -2,15 +2,17 @@ use crate::config::config_date::naive_date_serde;
 use chrono::{Duration, NaiveDate};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialOrd, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialOrd, PartialEq, Eq, Default)]
 pub enum Frequency {
     Daily,
+    #[default]
     Weekly,
     Monthly,
 }
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialOrd, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialOrd, PartialEq, Eq, Default)]
 pub enum Action {
+    #[default]
     Warn,
     Error,
     Update,
@@ -38,18 +40,6 @@ impl Frequency {
     }
 }
 
-impl Default for Frequency {
-    fn default() -> Self {
-        Self::Weekly
-    }
-}
-
-impl Default for Action {
-    fn default() -> Self {
-        Self::Warn
-    }
-}
-
 impl Default for Updates {
     fn default() -> Self {
         Self {
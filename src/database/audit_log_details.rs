@@ -2,9 +2,7 @@ use cloudtruth_restapi::models::AuditTrail;
 
 #[derive(Clone, Debug)]
 pub struct AuditLogDetails {
-    pub id: String,
     pub action: String,
-    pub object_id: String,
     pub object_name: String,
     pub object_type: String,
     pub timestamp: String,
@@ -15,9 +13,7 @@ impl From<&AuditTrail> for AuditLogDetails {
     fn from(api: &AuditTrail) -> Self {
         let user = api.user.clone().unwrap_or_default();
         Self {
-            id: api.id.clone(),
             action: api.action.clone(),
-            object_id: api.object_id.clone(),
             object_name: api.object_name.clone(),
             object_type: api.object_type.clone(),
             timestamp: api.timestamp.clone(),
@@ -0,0 +1,63 @@
+use crate::database::CryptoError;
+use std::error;
+use std::fmt;
+use std::fmt::Formatter;
+
+#[derive(Debug)]
+pub enum CacheError {
+    NoCacheDirectory,
+    NotCached(String),
+    Expired(String, u64),
+    IoError(String),
+    CryptoError(CryptoError),
+    FormatError(String),
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheError::NoCacheDirectory => {
+                write!(f, "Unable to determine the cache directory")
+            }
+            CacheError::NotCached(what) => {
+                write!(f, "No cached parameters for {}", what)
+            }
+            CacheError::Expired(what, age) => {
+                write!(
+                    f,
+                    "Cached parameters for {} are too old ({} seconds)",
+                    what, age
+                )
+            }
+            CacheError::IoError(msg) => {
+                write!(f, "Cache file error: {}", msg)
+            }
+            CacheError::CryptoError(e) => {
+                write!(f, "Cache decryption error: {}", e)
+            }
+            CacheError::FormatError(msg) => {
+                write!(f, "Cache format error: {}", msg)
+            }
+        }
+    }
+}
+
+impl error::Error for CacheError {}
+
+impl From<std::io::Error> for CacheError {
+    fn from(err: std::io::Error) -> Self {
+        CacheError::IoError(err.to_string())
+    }
+}
+
+impl From<CryptoError> for CacheError {
+    fn from(err: CryptoError) -> Self {
+        CacheError::CryptoError(err)
+    }
+}
+
+impl From<serde_json::Error> for CacheError {
+    fn from(err: serde_json::Error) -> Self {
+        CacheError::FormatError(err.to_string())
+    }
+}
@@ -8,7 +8,11 @@ pub struct IntegrationNode {
     pub node_type: String,
     pub secret: bool,
     pub name: String,
+    // the content type and size are not displayed yet, but are kept to describe the node (see the
+    // placeholder nodes in integrations.rs)
+    #[allow(dead_code)]
     pub content_type: String,
+    #[allow(dead_code)]
     pub content_size: i32,
     pub content_data: String,
     pub content_keys: Vec<String>,
@@ -4,21 +4,18 @@ use cloudtruth_restapi::models::Invitation;
 #[derive(Clone, Debug)]
 pub struct InvitationDetails {
     pub id: String,
-    pub url: String,
     pub email: String,
     pub role: String,
     pub inviter_url: String,
     pub inviter_name: String,
     pub state: String,
     pub state_detail: String,
-    pub membership: String,
 }
 
 impl From<&Invitation> for InvitationDetails {
     fn from(api: &Invitation) -> Self {
         Self {
             id: api.id.clone(),
-            url: api.url.clone(),
             email: api.email.clone(),
             role: match &api.role {
                 Some(r) => r.to_string().to_lowercase(),
@@ -28,7 +25,6 @@ impl From<&Invitation> for InvitationDetails {
             inviter_name: "".to_string(), // need to fill in later
             state: api.state.to_lowercase(),
             state_detail: api.state_detail.clone(),
-            membership: api.membership.clone().unwrap_or_default(),
         }
     }
 }
@@ -0,0 +1,76 @@
+use std::error;
+use std::fmt;
+use std::fmt::Formatter;
+
+#[derive(Debug)]
+pub enum ManifestError {
+    FormatError(String),
+    DuplicateParameter(String),
+    InvalidRuleType(String, String),
+    InvalidRule(String, String, String),
+    MissingEnvironment(String),
+    ParentParameter(String, String),
+    InvalidValue(String, String, String),
+}
+
+impl fmt::Display for ManifestError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ManifestError::FormatError(msg) => {
+                write!(f, "Invalid manifest: {}", msg)
+            }
+            ManifestError::DuplicateParameter(name) => {
+                write!(f, "Parameter '{}' is in the manifest more than once", name)
+            }
+            ManifestError::InvalidRuleType(name, rule_type) => {
+                write!(
+                    f,
+                    "Parameter '{}' has an invalid rule type '{}' (use max, min, max-len, min-len, or regex)",
+                    name, rule_type
+                )
+            }
+            ManifestError::InvalidRule(name, rule_type, msg) => {
+                write!(
+                    f,
+                    "Parameter '{}' has an invalid {} rule: {}",
+                    name, rule_type, msg
+                )
+            }
+            ManifestError::MissingEnvironment(env_name) => {
+                write!(
+                    f,
+                    "Environment '{}' is in the manifest, but was not compared",
+                    env_name
+                )
+            }
+            ManifestError::InvalidValue(name, env_name, msg) => {
+                write!(
+                    f,
+                    "Parameter '{}' has an invalid value for environment '{}': {}",
+                    name, env_name, msg
+                )
+            }
+            ManifestError::ParentParameter(name, project) => {
+                write!(
+                    f,
+                    "Parameter '{}' must be set from project '{}'",
+                    name, project
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for ManifestError {}
+
+impl From<serde_json::Error> for ManifestError {
+    fn from(err: serde_json::Error) -> Self {
+        ManifestError::FormatError(err.to_string())
+    }
+}
+
+impl From<serde_yaml::Error> for ManifestError {
+    fn from(err: serde_yaml::Error) -> Self {
+        ManifestError::FormatError(err.to_string())
+    }
+}
@@ -7,6 +7,7 @@ mod audit_log_summary;
 mod audit_logs;
 mod backup_error;
 mod backups;
+mod cache_error;
 mod crypto;
 mod crypto_algorithm;
 mod crypto_error;
@@ -28,10 +29,14 @@ mod integrations;
 mod invitation_details;
 mod invitation_error;
 mod invitations;
+mod manifest_error;
 mod openapi;
+mod parameter_cache;
 mod parameter_details;
 mod parameter_error;
 mod parameter_export;
+mod parameter_history;
+mod parameter_manifest;
 mod parameter_rules;
 mod parameters;
 mod project_details;
@@ -61,7 +66,8 @@ pub use audit_log_errors::AuditLogError;
 pub use audit_log_summary::AuditLogSummary;
 pub use audit_logs::AuditLogs;
 pub use backup_error::BackupError;
-pub use backups::{BackupSnapshotDetails, Backups};
+pub use backups::Backups;
+pub use cache_error::CacheError;
 pub use crypto::{
     secret_encode_wrap, secret_unwrap_decode, valid_encoding, ENCODED_PART_COUNT, ENCRYPTION_PREFIX,
 };
@@ -70,7 +76,7 @@ pub use crypto_error::CryptoError;
 pub use environment_details::EnvironmentDetails;
 pub use environment_error::EnvironmentError;
 pub use environment_tag::EnvironmentTag;
-pub use environments::{EnvironmentUrlMap, Environments};
+pub use environments::Environments;
 pub use group_details::GroupDetails;
 pub use group_error::GroupError;
 pub use groups::Groups;
@@ -85,15 +91,19 @@ pub use integrations::Integrations;
 pub use invitation_details::InvitationDetails;
 pub use invitation_error::InvitationError;
 pub use invitations::Invitations;
+pub use manifest_error::ManifestError;
 pub use openapi::{
     auth_details, extract_details, extract_from_json, last_from_url, page_size, parent_id_from_url,
     response_message, OpenApiConfig, NO_PAGE_COUNT, NO_PAGE_SIZE, WRAP_SECRETS,
 };
+pub use parameter_cache::{CachedValues, ParameterCache};
 pub use parameter_details::ParameterDetails;
 pub use parameter_error::ParameterError;
 pub use parameter_export::{ParamExportFormat, ParamExportOptions};
-pub use parameter_rules::{ParamRuleType, ParameterRuleDetail};
-pub use parameters::{ParameterDetailMap, Parameters};
+pub use parameter_history::ParameterHistory;
+pub use parameter_manifest::{ManifestEnvironment, ParameterManifest, PlanAction};
+pub use parameter_rules::{base_type_violation, ParamRuleType, ParameterRuleDetail};
+pub use parameters::{ParameterDetailMap, ParameterValueMap, Parameters};
 pub use project_details::ProjectDetails;
 pub use project_error::ProjectError;
 pub use projects::Projects;
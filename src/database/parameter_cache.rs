@@ -0,0 +1,343 @@
+use crate::database::{
+    secret_encode_wrap, secret_unwrap_decode, CacheError, CryptoAlgorithm, ParameterValueMap,
+    ResolvedDetails,
+};
+use chrono::Utc;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const CACHE_ALGORITHM: CryptoAlgorithm = CryptoAlgorithm::AesGcm;
+const CACHE_EXTENSION: &str = "cache";
+// distinguishes the name lookups from the values, which are keyed by IDs
+const NAMES_MARKER: &str = "names";
+
+/// The cached data, which is encrypted as a whole before being written.
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    created_at: i64,
+    values: ParameterValueMap,
+}
+
+/// The project/environment IDs for the names given by the user, which is used to find the cached
+/// values when the names cannot be resolved by the server.
+#[derive(Serialize, Deserialize)]
+struct NameEntry {
+    project_name: String,
+    project_id: String,
+    environment_name: String,
+    environment_id: String,
+}
+
+/// The parameter values loaded from the cache.
+#[derive(Debug)]
+pub struct CachedValues {
+    pub resolved: ResolvedDetails,
+    pub values: ParameterValueMap,
+    pub age: Duration,
+}
+
+/// Local cache of resolved parameter values, stored per (profile, project ID, environment ID, tag).
+///
+/// The files are encrypted with a key derived from the API key, and the file names are hashed so
+/// they do not reveal the project/environment names.
+pub struct ParameterCache {
+    dir: PathBuf,
+    profile: String,
+    token: Vec<u8>,
+}
+
+/// Writes a file that is only accessible by the owner.
+fn write_private_file(path: &Path, content: &str) -> std::io::Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(content.as_bytes())
+}
+
+/// Creates a directory that is only accessible by the owner.
+fn create_private_dir(path: &Path) -> std::io::Result<()> {
+    fs::create_dir_all(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(0o700))?;
+    }
+    Ok(())
+}
+
+impl ParameterCache {
+    pub fn new(dir: &Path, profile: &str, api_key: &str) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+            profile: profile.to_string(),
+            token: api_key.as_bytes().to_vec(),
+        }
+    }
+
+    /// Description of the cached item for messages, e.g. `project/environment@tag`.
+    fn description(project: &str, environment: &str, as_of: Option<&str>) -> String {
+        match as_of {
+            Some(as_of) => format!("{}/{}@{}", project, environment, as_of),
+            None => format!("{}/{}", project, environment),
+        }
+    }
+
+    fn filename(&self, items: &[&str]) -> PathBuf {
+        let mut digest = Sha256::new();
+        for item in [self.profile.as_str()].iter().chain(items) {
+            digest.update(item.as_bytes());
+            digest.update([0]);
+        }
+        self.dir.join(format!(
+            "{}.{}",
+            hex::encode(digest.finalize()),
+            CACHE_EXTENSION
+        ))
+    }
+
+    fn values_filename(&self, resolved: &ResolvedDetails, as_of: Option<&str>) -> PathBuf {
+        self.filename(&[
+            resolved.project_id(),
+            resolved.environment_id(),
+            as_of.unwrap_or_default(),
+        ])
+    }
+
+    fn names_filename(&self, project: &str, environment: &str) -> PathBuf {
+        self.filename(&[NAMES_MARKER, project, environment])
+    }
+
+    fn write_entry<T: Serialize>(&self, filename: &Path, entry: &T) -> Result<(), CacheError> {
+        let plaintext = serde_json::to_string(entry)?;
+        let encrypted = secret_encode_wrap(CACHE_ALGORITHM, &self.token, &plaintext)?;
+
+        // write to a temporary file, so a reader never sees a partial file
+        create_private_dir(&self.dir)?;
+        let temp_file = filename.with_extension("tmp");
+        write_private_file(&temp_file, &encrypted)?;
+        fs::rename(&temp_file, filename)?;
+        Ok(())
+    }
+
+    fn read_entry<T: DeserializeOwned>(&self, filename: &Path) -> Result<T, CacheError> {
+        let encrypted = fs::read_to_string(filename)?;
+        let plaintext = secret_unwrap_decode(&self.token, encrypted.trim())?;
+        Ok(serde_json::from_str(&plaintext)?)
+    }
+
+    /// Stores the parameter values for the resolved project/environment, replacing any previous
+    /// values. The `project` and `environment` are the names (or IDs) given by the user, which
+    /// are remembered along with the display names.
+    pub fn store(
+        &self,
+        project: &str,
+        environment: &str,
+        resolved: &ResolvedDetails,
+        as_of: Option<&str>,
+        values: &ParameterValueMap,
+    ) -> Result<(), CacheError> {
+        let entry = CacheEntry {
+            created_at: Utc::now().timestamp(),
+            values: values.clone(),
+        };
+        self.write_entry(&self.values_filename(resolved, as_of), &entry)?;
+
+        let names = NameEntry {
+            project_name: resolved.project_display_name().to_string(),
+            project_id: resolved.project_id().to_string(),
+            environment_name: resolved.environment_display_name().to_string(),
+            environment_id: resolved.environment_id().to_string(),
+        };
+        let mut filenames = vec![self.names_filename(project, environment)];
+        filenames.push(self.names_filename(&names.project_name, &names.environment_name));
+        filenames.dedup();
+        for filename in filenames {
+            self.write_entry(&filename, &names)?;
+        }
+        Ok(())
+    }
+
+    /// Gets the project/environment IDs the names resolved to when the values were cached.
+    pub fn resolve(&self, project: &str, environment: &str) -> Result<ResolvedDetails, CacheError> {
+        let filename = self.names_filename(project, environment);
+        if !filename.exists() {
+            return Err(CacheError::NotCached(Self::description(
+                project,
+                environment,
+                None,
+            )));
+        }
+        let names: NameEntry = self.read_entry(&filename)?;
+        Ok(ResolvedDetails::new(
+            names.environment_name,
+            names.environment_id,
+            names.project_name,
+            names.project_id,
+        ))
+    }
+
+    /// Loads the cached parameter values. It is an error when nothing is cached, or the cached
+    /// values are older than the `max_age`.
+    pub fn load(
+        &self,
+        resolved: &ResolvedDetails,
+        as_of: Option<&str>,
+        max_age: Option<Duration>,
+    ) -> Result<CachedValues, CacheError> {
+        let description = Self::description(
+            resolved.project_display_name(),
+            resolved.environment_display_name(),
+            as_of,
+        );
+        let filename = self.values_filename(resolved, as_of);
+        if !filename.exists() {
+            return Err(CacheError::NotCached(description));
+        }
+        let entry: CacheEntry = self.read_entry(&filename)?;
+        let age = Duration::from_secs((Utc::now().timestamp() - entry.created_at).max(0) as u64);
+        if let Some(max_age) = max_age {
+            if age > max_age {
+                return Err(CacheError::Expired(description, age.as_secs()));
+            }
+        }
+        Ok(CachedValues {
+            resolved: resolved.clone(),
+            values: entry.values,
+            age,
+        })
+    }
+
+    /// Removes all the cache files in the directory, and returns the number of files removed.
+    pub fn clear(dir: &Path) -> Result<usize, CacheError> {
+        let mut count = 0;
+        if !dir.exists() {
+            return Ok(count);
+        }
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) == Some(CACHE_EXTENSION) {
+                fs::remove_file(&path)?;
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::parameters::ParameterValueEntry;
+    use assert_matches::assert_matches;
+
+    fn resolved() -> ResolvedDetails {
+        ResolvedDetails::new(
+            "my-env".to_string(),
+            "env-id".to_string(),
+            "my-proj".to_string(),
+            "proj-id".to_string(),
+        )
+    }
+
+    #[test]
+    fn store_and_load() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ParameterCache::new(dir.path(), "default", "my-api-key");
+        let mut values = ParameterValueMap::new();
+        values.insert(
+            "SECRET".to_string(),
+            ParameterValueEntry {
+                value: "shhh".to_string(),
+                secret: true,
+                error: "".to_string(),
+            },
+        );
+
+        let result = cache.load(&resolved(), None, None);
+        assert_matches!(result, Err(CacheError::NotCached(_)));
+        let result = cache.resolve("my-proj", "my-env");
+        assert_matches!(result, Err(CacheError::NotCached(_)));
+
+        cache
+            .store("MY-PROJ", "env-id", &resolved(), None, &values)
+            .unwrap();
+        let cached = cache.load(&resolved(), None, None).unwrap();
+        assert_eq!(cached.values, values);
+        assert_eq!(cached.resolved.project_id(), "proj-id");
+        assert_eq!(cached.resolved.environment_id(), "env-id");
+        assert!(cached.age <= Duration::from_secs(1));
+
+        // the names given by the user and the display names both find the IDs
+        for (project, environment) in [("MY-PROJ", "env-id"), ("my-proj", "my-env")] {
+            let found = cache.resolve(project, environment).unwrap();
+            assert_eq!(found.project_id(), "proj-id");
+            assert_eq!(found.environment_id(), "env-id");
+            assert_eq!(found.project_display_name(), "my-proj");
+            assert_eq!(found.environment_display_name(), "my-env");
+        }
+        let result = cache.resolve("MY-PROJ", "my-env");
+        assert_matches!(result, Err(CacheError::NotCached(_)));
+
+        // the values and names are not in plaintext
+        for filename in [
+            cache.values_filename(&resolved(), None),
+            cache.names_filename("my-proj", "my-env"),
+        ] {
+            let content = fs::read_to_string(&filename).unwrap();
+            assert!(!content.contains("shhh"));
+            assert!(!content.contains("SECRET"));
+            assert!(!content.contains("my-proj"));
+        }
+
+        // tags, profiles, and API keys are all separate
+        let result = cache.load(&resolved(), Some("my-tag"), None);
+        assert_matches!(result, Err(CacheError::NotCached(_)));
+        let other = ParameterCache::new(dir.path(), "other", "my-api-key");
+        let result = other.load(&resolved(), None, None);
+        assert_matches!(result, Err(CacheError::NotCached(_)));
+        let other = ParameterCache::new(dir.path(), "default", "other-api-key");
+        let result = other.load(&resolved(), None, None);
+        assert_matches!(result, Err(CacheError::CryptoError(_)));
+
+        assert_eq!(ParameterCache::clear(dir.path()).unwrap(), 3);
+        let result = cache.load(&resolved(), None, None);
+        assert_matches!(result, Err(CacheError::NotCached(_)));
+    }
+
+    #[test]
+    fn expired() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = ParameterCache::new(dir.path(), "default", "my-api-key");
+        cache
+            .store(
+                "my-proj",
+                "my-env",
+                &resolved(),
+                None,
+                &ParameterValueMap::new(),
+            )
+            .unwrap();
+
+        // rewrite the entry, so it looks old
+        let filename = cache.values_filename(&resolved(), None);
+        let entry = CacheEntry {
+            created_at: Utc::now().timestamp() - 120,
+            values: ParameterValueMap::new(),
+        };
+        cache.write_entry(&filename, &entry).unwrap();
+
+        let result = cache.load(&resolved(), None, Some(Duration::from_secs(60)));
+        assert_matches!(result, Err(CacheError::Expired(_, _)));
+        let result = cache.load(&resolved(), None, Some(Duration::from_secs(600)));
+        assert!(result.is_ok());
+    }
+}
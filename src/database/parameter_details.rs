@@ -26,7 +26,8 @@ pub struct ParameterDetails {
     pub fqn: String,
     pub jmes_path: String,
     pub evaluated: bool,
-    pub raw_value: String, // the unevaluated value
+    pub raw_value: String,              // the unevaluated value
+    pub referenced_params: Vec<String>, // urls of the parameters the raw value references
     pub created_at: String,
     pub modified_at: String,
 
@@ -82,6 +83,7 @@ impl ParameterDetails {
         self.jmes_path = env_value.external_filter.clone().unwrap_or_default();
         self.evaluated = env_value.interpolated.unwrap_or(false);
         self.raw_value = env_value.internal_value.clone().unwrap_or_default();
+        self.referenced_params = env_value.referenced_parameters.clone();
         self.created_at = env_value.created_at.clone();
         self.modified_at = env_value.modified_at.clone();
         self.error = env_value.external_error.clone().unwrap_or_default();
@@ -128,6 +130,7 @@ impl Default for ParameterDetails {
             jmes_path: "".to_string(),
             evaluated: false,
             raw_value: "".to_string(),
+            referenced_params: vec![],
             created_at: "".to_string(),
             modified_at: "".to_string(),
             error: "".to_string(),
@@ -193,6 +196,7 @@ impl From<&Parameter> for ParameterDetails {
             jmes_path: env_value.external_filter.clone().unwrap_or_default(),
             evaluated: env_value.interpolated.unwrap_or(false),
             raw_value: env_value.internal_value.clone().unwrap_or_default(),
+            referenced_params: env_value.referenced_parameters.clone(),
             created_at: env_value.created_at.clone(),
             modified_at: env_value.modified_at.clone(),
 
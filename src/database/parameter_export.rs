@@ -1,3 +1,7 @@
+use crate::database::ParameterError;
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
 use std::str::FromStr;
 
 #[derive(Debug)]
@@ -5,6 +9,13 @@ pub enum ParamExportFormat {
     Docker,
     Dotenv,
     Shell,
+    // the formats below are only rendered by the CLI
+    Json,
+    Yaml,
+    K8sSecret,
+    K8sConfigMap,
+    Properties,
+    Toml,
 }
 
 /// Converts to ParamExportFormat from a &str.
@@ -16,11 +27,219 @@ impl FromStr for ParamExportFormat {
             "docker" => Ok(ParamExportFormat::Docker),
             "dotenv" => Ok(ParamExportFormat::Dotenv),
             "shell" => Ok(ParamExportFormat::Shell),
+            "json" => Ok(ParamExportFormat::Json),
+            "yaml" => Ok(ParamExportFormat::Yaml),
+            "k8s-secret" => Ok(ParamExportFormat::K8sSecret),
+            "k8s-configmap" => Ok(ParamExportFormat::K8sConfigMap),
+            "properties" => Ok(ParamExportFormat::Properties),
+            "toml" => Ok(ParamExportFormat::Toml),
             _ => Err(()),
         }
     }
 }
 
+/// Quotes the value for the shell, when it contains anything beyond "safe" characters.
+fn shell_quote(value: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "_-.,:/@%+=".contains(c);
+    if !value.is_empty() && value.chars().all(safe) {
+        value.to_string()
+    } else {
+        format!("'{}'", value.replace('\'', "'\"'\"'"))
+    }
+}
+
+/// Escapes the text for a Java properties file, which is ISO-8859-1 so anything beyond ASCII uses
+/// a unicode escape. The `key` flag also escapes the separator and comment characters.
+fn properties_escape(text: &str, key: bool) -> String {
+    let mut result = String::new();
+    for (index, c) in text.chars().enumerate() {
+        match c {
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            ' ' if key || index == 0 => result.push_str("\\ "),
+            '=' | ':' | '#' | '!' if key => {
+                result.push('\\');
+                result.push(c);
+            }
+            ' '..='~' => result.push(c),
+            _ => {
+                let mut units = [0u16; 2];
+                for unit in c.encode_utf16(&mut units) {
+                    result.push_str(&format!("\\u{:04x}", unit));
+                }
+            }
+        }
+    }
+    result
+}
+
+/// Quotes the text as a TOML basic string (or key, when it is not a bare key).
+fn toml_quote(text: &str) -> String {
+    let mut result = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            c if c.is_control() => result.push_str(&format!("\\u{:04X}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result.push('"');
+    result
+}
+
+fn toml_key(name: &str) -> String {
+    let bare = |c: char| c.is_ascii_alphanumeric() || c == '_' || c == '-';
+    if !name.is_empty() && name.chars().all(bare) {
+        name.to_string()
+    } else {
+        toml_quote(name)
+    }
+}
+
+/// Adds the value to the map using the `separator` to split the name into nested maps. When a part
+/// of the name is already used for a value, the rest of the name is kept as a single key.
+fn insert_nested(map: &mut Map<String, Value>, name: &str, separator: &str, value: Value) {
+    if let Some((first, rest)) = name.split_once(separator) {
+        if !first.is_empty() && !rest.is_empty() {
+            let child = map
+                .entry(first.to_string())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Value::Object(child_map) = child {
+                insert_nested(child_map, rest, separator, value);
+                return;
+            }
+        }
+    }
+    map.insert(name.to_string(), value);
+}
+
+#[derive(Serialize)]
+struct K8sMetadata {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    namespace: Option<String>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    labels: BTreeMap<String, String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct K8sManifest {
+    api_version: &'static str,
+    kind: &'static str,
+    metadata: K8sMetadata,
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    secret_type: Option<&'static str>,
+    data: BTreeMap<String, String>,
+}
+
+impl ParamExportFormat {
+    /// Formats rendered by the CLI, instead of the server.
+    pub fn is_client_side(&self) -> bool {
+        !matches!(
+            self,
+            ParamExportFormat::Docker | ParamExportFormat::Dotenv | ParamExportFormat::Shell
+        )
+    }
+
+    /// Formats the (sorted) name/value pairs as the export body.
+    pub fn format_body(
+        &self,
+        items: &[(String, String)],
+        options: &ParamExportOptions,
+    ) -> Result<String, ParameterError> {
+        let export = options.export.unwrap_or(false);
+        match self {
+            ParamExportFormat::Json | ParamExportFormat::Yaml => {
+                let mut map = Map::new();
+                for (name, value) in items {
+                    let value = Value::String(value.clone());
+                    match (self, &options.nest_separator) {
+                        (ParamExportFormat::Yaml, Some(sep)) if !sep.is_empty() => {
+                            insert_nested(&mut map, name, sep, value)
+                        }
+                        _ => {
+                            map.insert(name.clone(), value);
+                        }
+                    }
+                }
+                let doc = Value::Object(map);
+                match self {
+                    ParamExportFormat::Json => serde_json::to_string_pretty(&doc)
+                        .map(|body| body + "\n")
+                        .map_err(|e| ParameterError::UnhandledError(e.to_string())),
+                    _ => serde_yaml::to_string(&doc)
+                        .map_err(|e| ParameterError::UnhandledError(e.to_string())),
+                }
+            }
+            ParamExportFormat::K8sSecret | ParamExportFormat::K8sConfigMap => {
+                let secret = matches!(self, ParamExportFormat::K8sSecret);
+                let manifest = K8sManifest {
+                    api_version: "v1",
+                    kind: if secret { "Secret" } else { "ConfigMap" },
+                    metadata: K8sMetadata {
+                        name: options.k8s_name.clone().unwrap_or_default(),
+                        namespace: options.k8s_namespace.clone(),
+                        labels: options.k8s_labels.iter().cloned().collect(),
+                    },
+                    secret_type: if secret { Some("Opaque") } else { None },
+                    data: items
+                        .iter()
+                        .map(|(name, value)| match secret {
+                            true => (name.clone(), base64::encode(value)),
+                            false => (name.clone(), value.clone()),
+                        })
+                        .collect(),
+                };
+                serde_yaml::to_string(&manifest)
+                    .map_err(|e| ParameterError::UnhandledError(e.to_string()))
+            }
+            _ => Ok(items
+                .iter()
+                .map(|(name, value)| self.format_line(name, value, export) + "\n")
+                .collect()),
+        }
+    }
+
+    /// Formats a single variable declaration. The `export` is ignored for docker, since it is not
+    /// understood in an env-file, and for the formats that are not shell-like.
+    pub fn format_line(&self, name: &str, value: &str, export: bool) -> String {
+        let prefix = if export { "export " } else { "" };
+        match self {
+            ParamExportFormat::Docker => format!("{}={}", name, value),
+            ParamExportFormat::Dotenv => format!(
+                "{}{}=\"{}\"",
+                prefix,
+                name,
+                value
+                    .replace('\\', "\\\\")
+                    .replace('"', "\\\"")
+                    .replace('\n', "\\n")
+            ),
+            ParamExportFormat::Shell => format!("{}{}={}", prefix, name, shell_quote(value)),
+            ParamExportFormat::Properties => format!(
+                "{}={}",
+                properties_escape(name, true),
+                properties_escape(value, false)
+            ),
+            ParamExportFormat::Toml => format!("{} = {}", toml_key(name), toml_quote(value)),
+            // the documents are rendered by `format_body`, so this is just a (flat) YAML entry
+            ParamExportFormat::Json
+            | ParamExportFormat::Yaml
+            | ParamExportFormat::K8sSecret
+            | ParamExportFormat::K8sConfigMap => {
+                format!("{}: {}", Value::from(name), Value::from(value))
+            }
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct ParamExportOptions {
     pub format: ParamExportFormat,
@@ -31,4 +250,160 @@ pub struct ParamExportOptions {
     pub secrets: Option<bool>,
     pub as_of: Option<String>,
     pub tag: Option<String>,
+    /// Separator for splitting the names into nested YAML keys
+    pub nest_separator: Option<String>,
+    pub k8s_name: Option<String>,
+    pub k8s_namespace: Option<String>,
+    pub k8s_labels: Vec<(String, String)>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_lines() {
+        let docker = ParamExportFormat::Docker;
+        assert_eq!(docker.format_line("KEY", "a value", true), "KEY=a value");
+
+        let dotenv = ParamExportFormat::Dotenv;
+        assert_eq!(
+            dotenv.format_line("KEY", "a value", false),
+            "KEY=\"a value\""
+        );
+        assert_eq!(
+            dotenv.format_line("KEY", "say \"hi\"\n", true),
+            "export KEY=\"say \\\"hi\\\"\\n\""
+        );
+
+        let shell = ParamExportFormat::Shell;
+        assert_eq!(
+            shell.format_line("KEY", "simple_value", false),
+            "KEY=simple_value"
+        );
+        assert_eq!(shell.format_line("KEY", "a value", false), "KEY='a value'");
+        assert_eq!(
+            shell.format_line("KEY", "it's", true),
+            "export KEY='it'\"'\"'s'"
+        );
+        assert_eq!(shell.format_line("KEY", "", false), "KEY=''");
+    }
+
+    fn options(format: ParamExportFormat) -> ParamExportOptions {
+        ParamExportOptions {
+            format,
+            starts_with: None,
+            ends_with: None,
+            contains: None,
+            export: Some(true),
+            secrets: None,
+            as_of: None,
+            tag: None,
+            nest_separator: None,
+            k8s_name: Some("my-app".to_string()),
+            k8s_namespace: None,
+            k8s_labels: vec![],
+        }
+    }
+
+    fn items(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn format_client_lines() {
+        let props = ParamExportFormat::Properties;
+        assert_eq!(props.format_line("db.url", "a=b", true), "db.url=a=b");
+        assert_eq!(
+            props.format_line("key name:x", " two\nlines\\ é", false),
+            "key\\ name\\:x=\\ two\\nlines\\\\ \\u00e9"
+        );
+
+        let toml = ParamExportFormat::Toml;
+        assert_eq!(
+            toml.format_line("KEY_1", "plain", true),
+            "KEY_1 = \"plain\""
+        );
+        assert_eq!(
+            toml.format_line("db.url", "say \"hi\"\n", false),
+            "\"db.url\" = \"say \\\"hi\\\"\\n\""
+        );
+    }
+
+    #[test]
+    fn format_documents() {
+        let values = items(&[("A", "1"), ("B_HOST", "x\"y"), ("B_PORT", "80")]);
+        let opts = options(ParamExportFormat::Json);
+        assert_eq!(
+            opts.format.format_body(&values, &opts).unwrap(),
+            "{\n  \"A\": \"1\",\n  \"B_HOST\": \"x\\\"y\",\n  \"B_PORT\": \"80\"\n}\n"
+        );
+
+        let mut opts = options(ParamExportFormat::Yaml);
+        assert_eq!(
+            opts.format.format_body(&values, &opts).unwrap(),
+            "---\nA: \"1\"\nB_HOST: \"x\\\"y\"\nB_PORT: \"80\"\n"
+        );
+        opts.nest_separator = Some("_".to_string());
+        assert_eq!(
+            opts.format.format_body(&values, &opts).unwrap(),
+            "---\nA: \"1\"\nB:\n  HOST: \"x\\\"y\"\n  PORT: \"80\"\n"
+        );
+
+        // names that conflict with a value are not nested
+        let values = items(&[("A", "1"), ("A_B", "2")]);
+        assert_eq!(
+            opts.format.format_body(&values, &opts).unwrap(),
+            "---\nA: \"1\"\nA_B: \"2\"\n"
+        );
+
+        let opts = options(ParamExportFormat::Toml);
+        assert_eq!(
+            opts.format.format_body(&values, &opts).unwrap(),
+            "A = \"1\"\nA_B = \"2\"\n"
+        );
+    }
+
+    #[test]
+    fn format_k8s() {
+        let values = items(&[("PASSWORD", "secret")]);
+        let mut opts = options(ParamExportFormat::K8sSecret);
+        opts.k8s_namespace = Some("prod".to_string());
+        opts.k8s_labels = vec![("app".to_string(), "web".to_string())];
+        assert_eq!(
+            opts.format.format_body(&values, &opts).unwrap(),
+            concat!(
+                "---\n",
+                "apiVersion: v1\n",
+                "kind: Secret\n",
+                "metadata:\n",
+                "  name: my-app\n",
+                "  namespace: prod\n",
+                "  labels:\n",
+                "    app: web\n",
+                "type: Opaque\n",
+                "data:\n",
+                "  PASSWORD: c2VjcmV0\n",
+            )
+        );
+
+        let opts = options(ParamExportFormat::K8sConfigMap);
+        assert!(!ParamExportFormat::Shell.is_client_side());
+        assert!(opts.format.is_client_side());
+        assert_eq!(
+            opts.format.format_body(&values, &opts).unwrap(),
+            concat!(
+                "---\n",
+                "apiVersion: v1\n",
+                "kind: ConfigMap\n",
+                "metadata:\n",
+                "  name: my-app\n",
+                "data:\n",
+                "  PASSWORD: secret\n",
+            )
+        );
+    }
 }
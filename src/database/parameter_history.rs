@@ -0,0 +1,44 @@
+use crate::database::HistoryAction;
+use cloudtruth_restapi::models::{HistoryModelEnum, ParameterTimelineEntry};
+
+#[derive(Debug, Clone)]
+pub struct ParameterHistory {
+    pub id: String,
+    pub name: String,
+
+    // these are from the timeline
+    pub date: String,
+    pub change_type: HistoryAction,
+    pub model: String,
+    pub user_id: String,
+    pub user_name: String,
+    /// The (id, name) of the environments affected by the change
+    pub environments: Vec<(String, String)>,
+}
+
+impl From<&ParameterTimelineEntry> for ParameterHistory {
+    fn from(api: &ParameterTimelineEntry) -> Self {
+        let api_param = api.history_parameter.clone().unwrap_or_default();
+        let model = match api.history_model.as_deref() {
+            Some(HistoryModelEnum::Parameter) => "parameter",
+            Some(HistoryModelEnum::ParameterRule) => "rule",
+            Some(HistoryModelEnum::Value) => "value",
+            _ => "unknown",
+        };
+        ParameterHistory {
+            id: api_param.id.clone(),
+            name: api_param.name.clone(),
+
+            date: api.history_date.clone(),
+            change_type: HistoryAction::from(*api.history_type.clone().unwrap_or_default()),
+            model: model.to_string(),
+            user_id: api.history_user.clone().unwrap_or_default(),
+            user_name: "".to_string(), // must currently be resolved later
+            environments: api
+                .history_environments
+                .iter()
+                .map(|e| (e.id.clone(), e.name.clone()))
+                .collect(),
+        }
+    }
+}
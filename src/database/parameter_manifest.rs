@@ -0,0 +1,656 @@
+use crate::database::{ManifestError, ParamRuleType, ParameterDetailMap, ParameterDetails};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fmt::Formatter;
+use std::str::FromStr;
+
+/// A scalar in the manifest, which may be written without quotes (e.g. `port: 5432`). Numbers
+/// with a fraction are parsed, so they can be rejected (since `1.10` would become `1.1`).
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+enum ManifestScalar {
+    Text(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+}
+
+impl ManifestScalar {
+    fn is_float(&self) -> bool {
+        matches!(self, Self::Float(_))
+    }
+}
+
+impl fmt::Display for ManifestScalar {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Text(v) => write!(f, "{}", v),
+            Self::Integer(v) => write!(f, "{}", v),
+            Self::Float(v) => write!(f, "{}", v),
+            Self::Boolean(v) => write!(f, "{}", v),
+        }
+    }
+}
+
+// quoting keeps the text of the number exactly as written
+const FLOAT_VALUE_MSG: &str = "quote numbers that are not integers (e.g. \"1.10\")";
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ManifestValueDetails {
+    value: Option<ManifestScalar>,
+    fqn: Option<String>,
+    #[serde(rename = "jmes-path")]
+    jmes_path: Option<String>,
+    #[serde(default)]
+    evaluate: bool,
+}
+
+/// The value for an environment is either just the value, or the value details.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(untagged)]
+enum ManifestValueSpec {
+    Scalar(ManifestScalar),
+    Details(ManifestValueDetails),
+}
+
+/// The desired value of a parameter in an environment.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(from = "ManifestValueSpec")]
+pub struct ManifestValue {
+    pub value: Option<String>,
+    pub fqn: Option<String>,
+    pub jmes_path: Option<String>,
+    pub evaluate: bool,
+    /// The value was an unquoted number that is not an integer
+    float: bool,
+}
+
+impl From<ManifestValueSpec> for ManifestValue {
+    fn from(spec: ManifestValueSpec) -> Self {
+        match spec {
+            ManifestValueSpec::Scalar(value) => Self {
+                value: Some(value.to_string()),
+                float: value.is_float(),
+                ..Default::default()
+            },
+            ManifestValueSpec::Details(details) => Self {
+                float: matches!(details.value, Some(ManifestScalar::Float(_))),
+                value: details.value.map(|v| v.to_string()),
+                fqn: details.fqn,
+                jmes_path: details.jmes_path,
+                evaluate: details.evaluate,
+            },
+        }
+    }
+}
+
+/// The desired state of a parameter. Properties that are not specified are left unchanged.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestParameter {
+    pub name: String,
+    pub description: Option<String>,
+    pub secret: Option<bool>,
+    #[serde(rename = "type")]
+    pub param_type: Option<String>,
+    rules: Option<BTreeMap<String, ManifestScalar>>,
+    /// Values by environment name
+    #[serde(default)]
+    pub values: BTreeMap<String, ManifestValue>,
+}
+
+/// A declarative list of parameters for a project.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParameterManifest {
+    pub parameters: Vec<ManifestParameter>,
+}
+
+/// The parameters in an environment, used to compare against the manifest.
+pub struct ManifestEnvironment {
+    pub id: String,
+    pub name: String,
+    pub parameters: ParameterDetailMap,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PlanAction {
+    Create,
+    Update,
+    Delete,
+    NoChange,
+}
+
+impl fmt::Display for PlanAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Create => write!(f, "create"),
+            Self::Update => write!(f, "update"),
+            Self::Delete => write!(f, "delete"),
+            Self::NoChange => write!(f, "no-op"),
+        }
+    }
+}
+
+/// One step of the plan, which is either for the parameter (no environment), or for the value of
+/// the parameter in an environment.
+#[derive(Clone, Debug)]
+pub struct PlanItem {
+    pub name: String,
+    pub env_name: String,
+    pub env_id: String,
+    pub action: PlanAction,
+    /// The names of the properties that change
+    pub changes: Vec<String>,
+    pub param_id: String,
+    pub val_id: String,
+}
+
+impl PlanItem {
+    pub fn get_property(&self, property_name: &str) -> String {
+        match property_name {
+            "name" => self.name.clone(),
+            "environment" => self.env_name.clone(),
+            "action" => self.action.to_string(),
+            "changes" => self.changes.join(", "),
+            _ => format!("Unhandled property name '{}'", property_name),
+        }
+    }
+
+    pub fn get_properties(&self, fields: &[&str]) -> Vec<String> {
+        fields.iter().map(|p| self.get_property(p)).collect()
+    }
+}
+
+impl ManifestValue {
+    /// Gets the names of the properties that differ from the existing value.
+    fn changes(&self, current: &ParameterDetails) -> Vec<String> {
+        let mut changes: Vec<String> = vec![];
+        if let Some(fqn) = &self.fqn {
+            if !current.external || &current.fqn != fqn {
+                changes.push("fqn".to_string());
+            }
+            if current.jmes_path != self.jmes_path.clone().unwrap_or_default() {
+                changes.push("jmes-path".to_string());
+            }
+        } else {
+            // evaluated values are compared with the unevaluated value
+            let current_value = if current.evaluated {
+                &current.raw_value
+            } else {
+                &current.value
+            };
+            if current.external || Some(current_value) != self.value.as_ref() {
+                changes.push("value".to_string());
+            }
+        }
+        if current.evaluated != self.evaluate {
+            changes.push("evaluate".to_string());
+        }
+        changes
+    }
+
+    /// Gets the names of the properties being set on a new value.
+    fn settings(&self) -> Vec<String> {
+        let mut settings: Vec<String> = vec![];
+        if self.fqn.is_some() {
+            settings.push("fqn".to_string());
+        } else {
+            settings.push("value".to_string());
+        }
+        if self.jmes_path.is_some() {
+            settings.push("jmes-path".to_string());
+        }
+        if self.evaluate {
+            settings.push("evaluate".to_string());
+        }
+        settings
+    }
+}
+
+impl ManifestParameter {
+    /// Gets the rules, when specified in the manifest.
+    pub fn rules(&self) -> Option<Vec<(ParamRuleType, String)>> {
+        self.rules.as_ref().map(|rules| {
+            rules
+                .iter()
+                .map(|(rule_type, constraint)| {
+                    (
+                        ParamRuleType::from_str(rule_type).unwrap(),
+                        constraint.to_string(),
+                    )
+                })
+                .collect()
+        })
+    }
+
+    fn validate(&self) -> Result<(), ManifestError> {
+        if let Some(rules) = &self.rules {
+            for (rule_type, constraint) in rules {
+                if ParamRuleType::from_str(rule_type).is_err() {
+                    return Err(ManifestError::InvalidRuleType(
+                        self.name.clone(),
+                        rule_type.clone(),
+                    ));
+                }
+                if constraint.is_float() {
+                    return Err(ManifestError::InvalidRule(
+                        self.name.clone(),
+                        rule_type.clone(),
+                        FLOAT_VALUE_MSG.to_string(),
+                    ));
+                }
+            }
+        }
+        for (env_name, value) in &self.values {
+            let invalid = |msg: &str| {
+                ManifestError::InvalidValue(self.name.clone(), env_name.clone(), msg.to_string())
+            };
+            if value.value.is_some() == value.fqn.is_some() {
+                return Err(invalid("specify either a value or an fqn"));
+            }
+            if value.jmes_path.is_some() && value.fqn.is_none() {
+                return Err(invalid("a jmes-path requires an fqn"));
+            }
+            if value.float {
+                return Err(invalid(FLOAT_VALUE_MSG));
+            }
+        }
+        Ok(())
+    }
+
+    /// Gets the names of the parameter properties that differ from the existing parameter.
+    fn changes(&self, current: &ParameterDetails) -> Vec<String> {
+        let mut changes: Vec<String> = vec![];
+        if let Some(description) = &self.description {
+            if description != &current.description {
+                changes.push("description".to_string());
+            }
+        }
+        if let Some(secret) = self.secret {
+            if secret != current.secret {
+                changes.push("secret".to_string());
+            }
+        }
+        if let Some(param_type) = &self.param_type {
+            if param_type != &current.param_type {
+                changes.push("type".to_string());
+            }
+        }
+        if let Some(rules) = self.rules() {
+            let mut desired: Vec<String> =
+                rules.iter().map(|(t, c)| format!("{}={}", t, c)).collect();
+            let mut existing: Vec<String> = current
+                .rules
+                .iter()
+                .map(|r| format!("{}={}", r.rule_type, r.constraint))
+                .collect();
+            desired.sort();
+            existing.sort();
+            if desired != existing {
+                changes.push("rules".to_string());
+            }
+        }
+        changes
+    }
+
+    /// Gets the names of the properties being set on a new parameter.
+    fn settings(&self) -> Vec<String> {
+        let mut settings: Vec<String> = vec![];
+        if self.description.is_some() {
+            settings.push("description".to_string());
+        }
+        if self.secret.is_some() {
+            settings.push("secret".to_string());
+        }
+        if self.param_type.is_some() {
+            settings.push("type".to_string());
+        }
+        if self.rules.is_some() {
+            settings.push("rules".to_string());
+        }
+        settings
+    }
+}
+
+impl ParameterManifest {
+    /// Parses the manifest from JSON or YAML content.
+    pub fn parse(content: &str, json: bool) -> Result<Self, ManifestError> {
+        let manifest: Self = if json {
+            serde_json::from_str(content)?
+        } else {
+            serde_yaml::from_str(content)?
+        };
+        let mut names = HashSet::new();
+        for param in &manifest.parameters {
+            if !names.insert(param.name.as_str()) {
+                return Err(ManifestError::DuplicateParameter(param.name.clone()));
+            }
+            param.validate()?;
+        }
+        Ok(manifest)
+    }
+
+    /// Gets the (sorted) names of the environments that have values in the manifest.
+    pub fn environment_names(&self) -> Vec<String> {
+        let mut result: Vec<String> = self
+            .parameters
+            .iter()
+            .flat_map(|p| p.values.keys().cloned())
+            .collect();
+        result.sort();
+        result.dedup();
+        result
+    }
+
+    /// Compares the manifest to the existing parameters in the project, and gets the steps to
+    /// make the project match the manifest. The `current` parameters are from any environment,
+    /// and the `environments` need to include all the environments used by the manifest (or an
+    /// error is returned).
+    ///
+    /// With `prune`, the project parameters that are not in the manifest are deleted.
+    pub fn plan(
+        &self,
+        proj_id: &str,
+        current: &ParameterDetailMap,
+        environments: &[ManifestEnvironment],
+        prune: bool,
+    ) -> Result<Vec<PlanItem>, ManifestError> {
+        let mut result: Vec<PlanItem> = vec![];
+        for param in &self.parameters {
+            let existing = current.get(&param.name);
+            let (action, changes, param_id) = match existing {
+                None => (PlanAction::Create, param.settings(), "".to_string()),
+                Some(details) if !details.project_url.contains(proj_id) => {
+                    return Err(ManifestError::ParentParameter(
+                        param.name.clone(),
+                        details.project_name.clone(),
+                    ));
+                }
+                Some(details) => {
+                    let changes = param.changes(details);
+                    let action = if changes.is_empty() {
+                        PlanAction::NoChange
+                    } else {
+                        PlanAction::Update
+                    };
+                    (action, changes, details.id.clone())
+                }
+            };
+            result.push(PlanItem {
+                name: param.name.clone(),
+                env_name: "".to_string(),
+                env_id: "".to_string(),
+                action,
+                changes,
+                param_id: param_id.clone(),
+                val_id: "".to_string(),
+            });
+
+            for (env_name, value) in &param.values {
+                let env = environments
+                    .iter()
+                    .find(|e| &e.name == env_name)
+                    .ok_or_else(|| ManifestError::MissingEnvironment(env_name.clone()))?;
+                // only values set in this environment count (not inherited ones)
+                let env_value = env
+                    .parameters
+                    .get(&param.name)
+                    .filter(|d| !d.val_id.is_empty() && d.env_url.contains(&env.id));
+                let (action, changes, val_id) = match env_value {
+                    None => (PlanAction::Create, value.settings(), "".to_string()),
+                    Some(details) => {
+                        let changes = value.changes(details);
+                        let action = if changes.is_empty() {
+                            PlanAction::NoChange
+                        } else {
+                            PlanAction::Update
+                        };
+                        (action, changes, details.val_id.clone())
+                    }
+                };
+                result.push(PlanItem {
+                    name: param.name.clone(),
+                    env_name: env_name.clone(),
+                    env_id: env.id.clone(),
+                    action,
+                    changes,
+                    param_id: param_id.clone(),
+                    val_id,
+                });
+            }
+        }
+
+        if prune {
+            let mut extras: Vec<&ParameterDetails> = current
+                .values()
+                .filter(|d| d.project_url.contains(proj_id))
+                .filter(|d| !self.parameters.iter().any(|p| p.name == d.key))
+                .collect();
+            extras.sort_by(|l, r| l.key.cmp(&r.key));
+            for details in extras {
+                result.push(PlanItem {
+                    name: details.key.clone(),
+                    env_name: "".to_string(),
+                    env_id: "".to_string(),
+                    action: PlanAction::Delete,
+                    changes: vec![],
+                    param_id: details.id.clone(),
+                    val_id: "".to_string(),
+                });
+            }
+        }
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::ParameterRuleDetail;
+    use assert_matches::assert_matches;
+    use indoc::indoc;
+
+    const PROJ_URL: &str = "https://example.com/api/v1/projects/proj-id/";
+    const ENV_URL: &str = "https://example.com/api/v1/environments/env-id/";
+
+    fn details(key: &str, value: &str) -> ParameterDetails {
+        ParameterDetails {
+            id: format!("{}-id", key),
+            key: key.to_string(),
+            project_url: PROJ_URL.to_string(),
+            project_name: "my-proj".to_string(),
+            val_id: format!("{}-val-id", key),
+            value: value.to_string(),
+            env_url: ENV_URL.to_string(),
+            env_name: "default".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parse_manifest() {
+        let content = indoc! {r#"
+            parameters:
+              - name: DB_HOST
+                description: Database host
+                secret: false
+                type: string
+                rules:
+                  max-len: 100
+                values:
+                  default: db.example.com
+                  production:
+                    value: "{{ db_host }}"
+                    evaluate: true
+              - name: DB_PORT
+                values:
+                  default: 5432
+              - name: DB_PASSWORD
+                secret: true
+                values:
+                  default:
+                    fqn: aws://my-secret
+                    jmes-path: password
+        "#};
+        let manifest = ParameterManifest::parse(content, false).unwrap();
+        assert_eq!(manifest.parameters.len(), 3);
+        assert_eq!(manifest.environment_names(), vec!["default", "production"]);
+        let host = &manifest.parameters[0];
+        assert_eq!(
+            host.rules(),
+            Some(vec![(ParamRuleType::MaxLen, "100".to_string())])
+        );
+        assert_eq!(
+            host.values["default"].value.as_deref(),
+            Some("db.example.com")
+        );
+        assert!(host.values["production"].evaluate);
+        assert_eq!(
+            manifest.parameters[1].values["default"].value.as_deref(),
+            Some("5432")
+        );
+        let password = &manifest.parameters[2].values["default"];
+        assert_eq!(password.value, None);
+        assert_eq!(password.fqn.as_deref(), Some("aws://my-secret"));
+        assert_eq!(password.jmes_path.as_deref(), Some("password"));
+
+        let json = r#"{"parameters": [{"name": "A", "values": {"default": "a"}}]}"#;
+        let manifest = ParameterManifest::parse(json, true).unwrap();
+        assert_eq!(
+            manifest.parameters[0].values["default"].value.as_deref(),
+            Some("a")
+        );
+    }
+
+    #[test]
+    fn invalid_manifest() {
+        let content = "parameters:\n  - name: A\n  - name: A\n";
+        let result = ParameterManifest::parse(content, false);
+        assert_matches!(result, Err(ManifestError::DuplicateParameter(_)));
+
+        let content = "parameters:\n  - name: A\n    rules:\n      maximum: 10\n";
+        let result = ParameterManifest::parse(content, false);
+        assert_matches!(result, Err(ManifestError::InvalidRuleType(_, _)));
+
+        let content =
+            "parameters:\n  - name: A\n    values:\n      default:\n        jmes-path: a\n";
+        let result = ParameterManifest::parse(content, false);
+        assert_matches!(result, Err(ManifestError::InvalidValue(_, _, _)));
+
+        let content = "parameters:\n  - name: A\n    unknown: true\n";
+        let result = ParameterManifest::parse(content, false);
+        assert_matches!(result, Err(ManifestError::FormatError(_)));
+
+        // numbers that are not integers would lose their text (e.g. `1.10` becomes `1.1`)
+        let content = "parameters:\n  - name: A\n    values:\n      default: 1.10\n";
+        let result = ParameterManifest::parse(content, false);
+        assert_matches!(result, Err(ManifestError::InvalidValue(_, _, _)));
+        let content = "parameters:\n  - name: A\n    values:\n      default:\n        value: 1.0\n";
+        let result = ParameterManifest::parse(content, false);
+        assert_matches!(result, Err(ManifestError::InvalidValue(_, _, _)));
+        let content = "parameters:\n  - name: A\n    rules:\n      max: 2.5\n";
+        let result = ParameterManifest::parse(content, false);
+        assert_matches!(result, Err(ManifestError::InvalidRule(_, _, _)));
+        let content = "parameters:\n  - name: A\n    values:\n      default: \"1.10\"\n";
+        let manifest = ParameterManifest::parse(content, false).unwrap();
+        assert_eq!(
+            manifest.parameters[0].values["default"].value.as_deref(),
+            Some("1.10")
+        );
+    }
+
+    #[test]
+    fn plan_changes() {
+        let content = indoc! {r#"
+            parameters:
+              - name: SAME
+                values:
+                  default: same-value
+              - name: CHANGED
+                description: new description
+                rules:
+                  max: 10
+                values:
+                  default: new-value
+                  other: other-value
+              - name: NEW
+                secret: true
+                values:
+                  default: new-value
+        "#};
+        let manifest = ParameterManifest::parse(content, false).unwrap();
+        let mut current = ParameterDetailMap::new();
+        current.insert("SAME".to_string(), details("SAME", "same-value"));
+        let mut changed = details("CHANGED", "old-value");
+        changed.rules.push(ParameterRuleDetail {
+            id: "rule-id".to_string(),
+            rule_type: ParamRuleType::Max,
+            constraint: "10".to_string(),
+            created_at: "".to_string(),
+            modified_at: "".to_string(),
+        });
+        current.insert("CHANGED".to_string(), changed);
+        current.insert("EXTRA".to_string(), details("EXTRA", "extra-value"));
+        let mut parent = details("PARENT", "parent-value");
+        parent.project_url = "https://example.com/api/v1/projects/parent-id/".to_string();
+        current.insert("PARENT".to_string(), parent);
+
+        // the value in "other" is inherited from "default"
+        let environments = vec![
+            ManifestEnvironment {
+                id: "env-id".to_string(),
+                name: "default".to_string(),
+                parameters: current.clone(),
+            },
+            ManifestEnvironment {
+                id: "other-id".to_string(),
+                name: "other".to_string(),
+                parameters: current.clone(),
+            },
+        ];
+
+        let plan = manifest
+            .plan("proj-id", &current, &environments, false)
+            .unwrap();
+        let summary: Vec<Vec<String>> = plan
+            .iter()
+            .map(|i| i.get_properties(&["name", "environment", "action", "changes"]))
+            .collect();
+        let expected: Vec<Vec<&str>> = vec![
+            vec!["SAME", "", "no-op", ""],
+            vec!["SAME", "default", "no-op", ""],
+            vec!["CHANGED", "", "update", "description"],
+            vec!["CHANGED", "default", "update", "value"],
+            vec!["CHANGED", "other", "create", "value"],
+            vec!["NEW", "", "create", "secret"],
+            vec!["NEW", "default", "create", "value"],
+        ];
+        assert_eq!(summary, expected);
+        assert_eq!(plan[3].val_id, "CHANGED-val-id");
+        assert_eq!(plan[4].env_id, "other-id");
+
+        // prune only removes parameters from this project
+        let plan = manifest
+            .plan("proj-id", &current, &environments, true)
+            .unwrap();
+        let last = plan.last().unwrap();
+        assert_eq!(last.name, "EXTRA");
+        assert_eq!(last.action, PlanAction::Delete);
+        assert_eq!(plan.len(), 8);
+
+        // parameters from a parent project cannot be set
+        let content = "parameters:\n  - name: PARENT\n";
+        let manifest = ParameterManifest::parse(content, false).unwrap();
+        let result = manifest.plan("proj-id", &current, &environments, false);
+        assert_matches!(result, Err(ManifestError::ParentParameter(_, _)));
+
+        // all the environments in the manifest need to be compared
+        let content = "parameters:\n  - name: NEW\n    values:\n      missing: value\n";
+        let manifest = ParameterManifest::parse(content, false).unwrap();
+        let result = manifest.plan("proj-id", &current, &environments, false);
+        assert_matches!(result, Err(ManifestError::MissingEnvironment(_)));
+    }
+}
@@ -1,6 +1,8 @@
 use cloudtruth_restapi::models::{ParameterRule, ParameterRuleTypeEnum, ParameterTypeRule};
+use regex::Regex;
 use std::fmt;
 use std::fmt::Formatter;
+use std::str::FromStr;
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ParamRuleType {
@@ -60,6 +62,21 @@ impl fmt::Display for ParamRuleType {
     }
 }
 
+impl FromStr for ParamRuleType {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "max" => Ok(Self::Max),
+            "min" => Ok(Self::Min),
+            "max-len" => Ok(Self::MaxLen),
+            "min-len" => Ok(Self::MinLen),
+            "regex" => Ok(Self::Regex),
+            _ => Err(()),
+        }
+    }
+}
+
 impl From<&ParameterRule> for ParameterRuleDetail {
     fn from(api: &ParameterRule) -> Self {
         Self {
@@ -83,3 +100,154 @@ impl From<&ParameterTypeRule> for ParameterRuleDetail {
         }
     }
 }
+
+impl ParameterRuleDetail {
+    /// Checks the value against the rule locally, and describes the violation (if any). Values
+    /// that do not fit the rule (e.g. text for a numeric rule) are left to the type check.
+    ///
+    /// An `Err` describes why the rule cannot be checked locally (e.g. a regular expression that
+    /// uses lookaround, which the `regex` crate does not support), so the server has the final say.
+    pub fn violation(&self, value: &str) -> Result<Option<String>, String> {
+        let number = |text: &str| text.trim().parse::<f64>().ok();
+        let result = match self.rule_type {
+            ParamRuleType::Max => match (number(value), number(&self.constraint)) {
+                (Some(v), Some(max)) if v > max => {
+                    Some(format!("Value must be at most {}", self.constraint))
+                }
+                _ => None,
+            },
+            ParamRuleType::Min => match (number(value), number(&self.constraint)) {
+                (Some(v), Some(min)) if v < min => {
+                    Some(format!("Value must be at least {}", self.constraint))
+                }
+                _ => None,
+            },
+            ParamRuleType::MaxLen => match self.constraint.trim().parse::<usize>() {
+                Ok(max) if value.chars().count() > max => Some(format!(
+                    "Value must be at most {} characters",
+                    self.constraint
+                )),
+                _ => None,
+            },
+            ParamRuleType::MinLen => match self.constraint.trim().parse::<usize>() {
+                Ok(min) if value.chars().count() < min => Some(format!(
+                    "Value must be at least {} characters",
+                    self.constraint
+                )),
+                _ => None,
+            },
+            // The expression is not anchored, since the server's matching is not documented. A
+            // match anywhere in the value is the loosest interpretation, so values the server
+            // accepts are never reported.
+            ParamRuleType::Regex => match Regex::new(&self.constraint) {
+                Ok(re) if re.is_match(value) => None,
+                Ok(_) => Some(format!(
+                    "Value must match the regular expression '{}'",
+                    self.constraint
+                )),
+                Err(e) => {
+                    return Err(format!(
+                        "Cannot check the regular expression '{}': {}",
+                        self.constraint, e
+                    ))
+                }
+            },
+            ParamRuleType::Unknown => None,
+        };
+        Ok(result)
+    }
+}
+
+/// Checks the value against the built-in types, which are the root of every type chain.
+pub fn base_type_violation(type_name: &str, value: &str) -> Option<String> {
+    match type_name {
+        "integer" if value.trim().parse::<i64>().is_err() => {
+            Some("Value is not an integer".to_string())
+        }
+        "boolean" if !matches!(value.to_lowercase().as_str(), "true" | "false") => {
+            Some("Value is not a boolean (true or false)".to_string())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn rule(rule_type: ParamRuleType, constraint: &str) -> ParameterRuleDetail {
+        ParameterRuleDetail {
+            id: "".to_string(),
+            rule_type,
+            constraint: constraint.to_string(),
+            created_at: "".to_string(),
+            modified_at: "".to_string(),
+        }
+    }
+
+    #[test]
+    fn rule_violations() {
+        let max = rule(ParamRuleType::Max, "10");
+        assert_eq!(max.violation("10"), Ok(None));
+        assert_eq!(
+            max.violation("11"),
+            Ok(Some("Value must be at most 10".to_string()))
+        );
+        assert_eq!(max.violation("text"), Ok(None));
+
+        let min = rule(ParamRuleType::Min, "-5");
+        assert_eq!(min.violation("-5"), Ok(None));
+        assert_eq!(
+            min.violation("-6"),
+            Ok(Some("Value must be at least -5".to_string()))
+        );
+
+        let max_len = rule(ParamRuleType::MaxLen, "3");
+        assert_eq!(max_len.violation("été"), Ok(None));
+        assert_eq!(
+            max_len.violation("four"),
+            Ok(Some("Value must be at most 3 characters".to_string()))
+        );
+
+        let min_len = rule(ParamRuleType::MinLen, "2");
+        assert_eq!(
+            min_len.violation("a"),
+            Ok(Some("Value must be at least 2 characters".to_string()))
+        );
+
+        let regex = rule(ParamRuleType::Regex, "^([a-z]+|[0-9]+)$");
+        assert_eq!(regex.violation("abc"), Ok(None));
+        assert_eq!(regex.violation("123"), Ok(None));
+        assert_eq!(
+            regex.violation("abc123"),
+            Ok(Some(
+                "Value must match the regular expression '^([a-z]+|[0-9]+)$'".to_string()
+            ))
+        );
+        // matches anywhere in the value
+        let regex = rule(ParamRuleType::Regex, "[0-9]");
+        assert_eq!(regex.violation("abc1"), Ok(None));
+
+        // lookaround is not supported by the regex crate
+        let lookahead = rule(ParamRuleType::Regex, "(?=a)a");
+        assert!(lookahead
+            .violation("a")
+            .unwrap_err()
+            .starts_with("Cannot check the regular expression '(?=a)a'"));
+    }
+
+    #[test]
+    fn base_type_violations() {
+        assert_eq!(base_type_violation("string", "anything"), None);
+        assert_eq!(base_type_violation("integer", "-12"), None);
+        assert_eq!(
+            base_type_violation("integer", "1.5"),
+            Some("Value is not an integer".to_string())
+        );
+        assert_eq!(base_type_violation("boolean", "True"), None);
+        assert_eq!(
+            base_type_violation("boolean", "yes"),
+            Some("Value is not a boolean (true or false)".to_string())
+        );
+    }
+}
@@ -2,7 +2,8 @@ use crate::database::openapi::key_from_config;
 use crate::database::{
     extract_details, extract_from_json, page_size, response_message, secret_encode_wrap,
     secret_unwrap_decode, CryptoAlgorithm, OpenApiConfig, ParamExportOptions, ParamRuleType,
-    ParameterDetails, ParameterError, TaskStepDetails, NO_PAGE_COUNT, NO_PAGE_SIZE, WRAP_SECRETS,
+    ParameterDetails, ParameterError, ParameterHistory, TaskStepDetails, Users, NO_PAGE_COUNT,
+    NO_PAGE_SIZE, WRAP_SECRETS,
 };
 use cloudtruth_restapi::apis::projects_api::*;
 use cloudtruth_restapi::apis::utils_api::utils_generate_password_create;
@@ -11,6 +12,7 @@ use cloudtruth_restapi::models::{
     ParameterCreate, ParameterRuleCreate, ParameterRuleTypeEnum, PatchedParameter,
     PatchedParameterRule, PatchedValue, ValueCreate,
 };
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::result::Result;
 
@@ -36,8 +38,10 @@ const WRAP_ALGORITHM: CryptoAlgorithm = CryptoAlgorithm::AesGcm;
 
 pub struct Parameters {}
 
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct ParameterValueEntry {
     pub value: String,
+    pub secret: bool,
     pub error: String,
 }
 
@@ -327,6 +331,7 @@ impl Parameters {
         for param in parameters {
             let entry = ParameterValueEntry {
                 value: param.value,
+                secret: param.secret,
                 error: param.error,
             };
             env_vars.insert(param.key, entry);
@@ -524,6 +529,91 @@ impl Parameters {
         Ok(result)
     }
 
+    /// Gets the `ParameterDetails` for each value of the project parameters (or just the named
+    /// parameter), in all environments.
+    ///
+    /// Without `evaluate`, the `value` is the same as the `raw_value` (which has any references to
+    /// other parameters).
+    #[allow(clippy::too_many_arguments)]
+    pub fn get_all_environment_values(
+        &self,
+        rest_cfg: &OpenApiConfig,
+        proj_id: &str,
+        param_name: Option<&str>,
+        immediate_parameters: bool,
+        mask_secrets: bool,
+        evaluate: bool,
+        as_of: Option<String>,
+    ) -> Result<Vec<ParameterDetails>, ParameterError> {
+        let mut result: Vec<ParameterDetails> = Vec::new();
+        let mut page_count = 1;
+        loop {
+            let response = projects_parameters_list(
+                rest_cfg,
+                proj_id,
+                as_of.clone(),
+                NO_DESC_ICONTAINS,
+                None,
+                None, // cannot give an environment, or it will only get for that environment
+                Some(evaluate),
+                NO_ID_IN,
+                immediate_parameters_arg(immediate_parameters),
+                mask_secrets_arg(mask_secrets),
+                param_name,
+                NO_NAME_CONTAINS,
+                NO_NAME_ICONTAINS,
+                NO_NAME_IEXACT,
+                NO_NAME_ISTARTS,
+                NO_NAME_STARTS,
+                NO_ORDERING,
+                Some(page_count),
+                page_size(rest_cfg),
+                PROJECT,
+                PROJECT_CONTAINS,
+                PROJECT_ICONTAINS,
+                PROJECT_IEXACT,
+                PROJECT_ISTARTS,
+                PROJECT_STARTS,
+                ONLY_SECRETS,
+                None, // cannot use a tag without an environment
+                VALUES_TRUE,
+                wrap_secrets_arg(mask_secrets),
+            );
+            match response {
+                Ok(data) => {
+                    let key = key_from_config(rest_cfg);
+                    if let Some(parameters) = data.results {
+                        for api_param in parameters {
+                            let mut details = ParameterDetails::from(&api_param);
+                            for (_, api_value) in api_param.values {
+                                if let Some(value) = api_value {
+                                    details.set_value(&value);
+                                    if WRAP_SECRETS && !mask_secrets && details.encrypted() {
+                                        details.value =
+                                            secret_unwrap_decode(key.as_bytes(), &details.value)?;
+                                    }
+                                    result.push(details.clone());
+                                }
+                            }
+                        }
+                        page_count += 1;
+                    } else {
+                        break;
+                    }
+                    if data.next.is_none() {
+                        break;
+                    }
+                }
+                Err(ResponseError(ref content)) => {
+                    return Err(response_error(&content.status, &content.content))
+                }
+                Err(e) => return Err(ParameterError::UnhandledError(e.to_string())),
+            }
+        }
+        result.sort_by(|l, r| l.key.cmp(&r.key));
+        Ok(result)
+    }
+
     /// Creates the `Parameter` entry.
     ///
     /// There is no `Value` entry created as part of this -- it is just the `Parameter`.
@@ -851,6 +941,76 @@ impl Parameters {
         Ok(total)
     }
 
+    fn resolve_user_ids(&self, rest_cfg: &OpenApiConfig, histories: &mut [ParameterHistory]) {
+        if !histories.is_empty() {
+            let users = Users::new();
+            let user_map = users.get_user_id_to_name_map(rest_cfg);
+            if let Ok(user_map) = user_map {
+                let default_username = "".to_string();
+                for entry in histories {
+                    entry.user_name = user_map
+                        .get(&entry.user_id)
+                        .unwrap_or(&default_username)
+                        .clone();
+                }
+            }
+        }
+    }
+
+    /// Gets the parameter history for all parameters in the project.
+    pub fn get_histories(
+        &self,
+        rest_cfg: &OpenApiConfig,
+        proj_id: &str,
+        as_of: Option<String>,
+        tag: Option<String>,
+    ) -> Result<Vec<ParameterHistory>, ParameterError> {
+        let response =
+            projects_parameters_timelines_retrieve(rest_cfg, proj_id, as_of, tag.as_deref());
+        match response {
+            Ok(data) => {
+                let mut histories: Vec<ParameterHistory> =
+                    data.results.iter().map(ParameterHistory::from).collect();
+                self.resolve_user_ids(rest_cfg, &mut histories);
+                Ok(histories)
+            }
+            Err(ResponseError(ref content)) => {
+                Err(response_error(&content.status, &content.content))
+            }
+            Err(e) => Err(ParameterError::UnhandledError(e.to_string())),
+        }
+    }
+
+    /// Gets the parameter history for a single parameter in the project.
+    pub fn get_history_for(
+        &self,
+        rest_cfg: &OpenApiConfig,
+        proj_id: &str,
+        param_id: &str,
+        as_of: Option<String>,
+        tag: Option<String>,
+    ) -> Result<Vec<ParameterHistory>, ParameterError> {
+        let response = projects_parameters_timeline_retrieve(
+            rest_cfg,
+            param_id,
+            proj_id,
+            as_of,
+            tag.as_deref(),
+        );
+        match response {
+            Ok(data) => {
+                let mut histories: Vec<ParameterHistory> =
+                    data.results.iter().map(ParameterHistory::from).collect();
+                self.resolve_user_ids(rest_cfg, &mut histories);
+                Ok(histories)
+            }
+            Err(ResponseError(ref content)) => {
+                Err(response_error(&content.status, &content.content))
+            }
+            Err(e) => Err(ParameterError::UnhandledError(e.to_string())),
+        }
+    }
+
     /// Use the API to generate a new password according to the provided policy flags
     #[allow(clippy::too_many_arguments)]
     pub fn generate_password(
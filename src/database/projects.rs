@@ -220,7 +220,7 @@ impl Projects {
             .find(|&d| d.name == parent_name)
             .expect("No project found with that name")
             .clone();
-        let descendants = self.get_all_descendants_from_list(&parent.url, &*all_details);
+        let descendants = self.get_all_descendants_from_list(&parent.url, &all_details);
         Ok(descendants)
     }
 
@@ -1,3 +1,4 @@
+#[derive(Clone, Debug)]
 pub struct ResolvedDetails {
     env_name: String,
     env_id: String,
@@ -3,7 +3,6 @@ use cloudtruth_restapi::models::{AwsPullTaskStep, AwsPushTaskStep, GitHubPullTas
 pub struct TaskStepDetails {
     pub url: String,
     pub id: String,
-    pub provider: String,
     pub success: bool,
     pub detail: String,
     pub task_name: String,
@@ -55,7 +54,6 @@ impl From<&AwsPushTaskStep> for TaskStepDetails {
         Self {
             url: api.url.clone(),
             id: api.id.clone(),
-            provider: "aws".to_string(),
             success: api.success,
             detail,
             task_name: "".to_string(), // to be filled in later
@@ -79,7 +77,6 @@ impl From<&AwsPullTaskStep> for TaskStepDetails {
         Self {
             url: api.url.clone(),
             id: api.id.clone(),
-            provider: "aws".to_string(),
             success: api.success,
             detail,
             task_name: "".to_string(), // to be filled in later
@@ -103,7 +100,6 @@ impl From<&GitHubPullTaskStep> for TaskStepDetails {
         Self {
             url: api.url.clone(),
             id: api.id.clone(),
-            provider: "aws".to_string(),
             success: api.success,
             detail,
             task_name: "".to_string(), // to be filled in later
@@ -127,7 +123,6 @@ impl From<&TaskStep> for TaskStepDetails {
         Self {
             url: api.url.clone(),
             id: api.id.clone(),
-            provider: "".to_string(), // to be filled in later?
             success: api.success,
             detail,
             task_name: "".to_string(), // to be filled in later
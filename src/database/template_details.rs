@@ -6,6 +6,7 @@ pub struct TemplateDetails {
     pub name: String,
     pub description: String,
     pub body: String,
+    pub referenced_params: Vec<String>, // urls of the parameters the body references
     pub created_at: String,
     pub modified_at: String,
 }
@@ -17,6 +18,7 @@ impl From<&Template> for TemplateDetails {
             name: api_temp.name.clone(),
             description: api_temp.description.clone().unwrap_or_default(),
             body: api_temp.body.clone().unwrap_or_default(),
+            referenced_params: api_temp.referenced_parameters.clone(),
             created_at: api_temp.created_at.clone(),
             modified_at: api_temp.modified_at.clone(),
         }
@@ -0,0 +1,267 @@
+use crate::subprocess::EnvSettings;
+use serde_json::Value;
+use std::env;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Variables that are ignored by default, since they are set by the shell (or for the CLI).
+pub const DEFAULT_EXCLUDES: &[&str] = &[
+    "PATH",
+    "HOME",
+    "TERM",
+    "PWD",
+    "OLDPWD",
+    "PS1",
+    "USER",
+    "_",
+    // skip all the CLOUDTRUTH_ environment variables for the CLI execution
+    "CLOUDTRUTH_API_KEY",
+    "CLOUDTRUTH_PROFILE",
+    "CLOUDTRUTH_PROJECT",
+    "CLOUDTRUTH_ENVIRONMENT",
+    "CLOUDTRUTH_SERVER_URL",
+    "CLOUDTRUTH_REQUEST_TIMEOUT",
+    "CLOUDTRUTH_REST_DEBUG",
+    "CLOUDTRUTH_REST_SUCCESS",
+    "CLOUDTRUTH_REST_PAGE_SIZE",
+];
+
+#[derive(Debug)]
+pub enum DriftError {
+    ReadError(String, String),
+    FormatError(String, String),
+}
+
+impl Display for DriftError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            DriftError::ReadError(source, msg) => {
+                write!(f, "Failed to read {}: {}", source, msg)
+            }
+            DriftError::FormatError(source, msg) => {
+                write!(f, "Invalid format in {}: {}", source, msg)
+            }
+        }
+    }
+}
+
+impl error::Error for DriftError {}
+
+/// Where the variables compared to the CloudTruth parameters come from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DriftSource {
+    Shell,
+    File(PathBuf),
+    Process(u32),
+}
+
+impl DriftSource {
+    /// Name used for the column of variable values.
+    pub fn label(&self) -> &'static str {
+        match self {
+            DriftSource::Shell => "Shell",
+            DriftSource::File(_) => "File",
+            DriftSource::Process(_) => "Process",
+        }
+    }
+
+    fn description(&self) -> String {
+        match self {
+            DriftSource::Shell => "shell environment".to_string(),
+            DriftSource::File(path) => format!("file '{}'", path.display()),
+            DriftSource::Process(pid) => format!("environment of process {}", pid),
+        }
+    }
+
+    /// Gets the variables from the source. Files ending in `.json`, `.yaml`, or `.yml` are
+    /// documents, and everything else is a dotenv file.
+    pub fn load(&self) -> Result<EnvSettings, DriftError> {
+        let read_err = |e: std::io::Error| DriftError::ReadError(self.description(), e.to_string());
+        let format_err = |msg: String| DriftError::FormatError(self.description(), msg);
+        match self {
+            DriftSource::Shell => Ok(env::vars().collect()),
+            DriftSource::File(path) => {
+                let text = fs::read_to_string(path).map_err(read_err)?;
+                match path.extension().and_then(|e| e.to_str()) {
+                    Some("json") | Some("yaml") | Some("yml") => {
+                        parse_document(&text).map_err(format_err)
+                    }
+                    _ => parse_dotenv(&text).map_err(format_err),
+                }
+            }
+            DriftSource::Process(pid) => {
+                let path = Path::new("/proc").join(pid.to_string()).join("environ");
+                let data = fs::read(path).map_err(read_err)?;
+                Ok(parse_environ(&data))
+            }
+        }
+    }
+}
+
+/// Checks the name against the exclusions, where an exclusion ending with `*` is a prefix.
+pub fn is_excluded(name: &str, excludes: &[String]) -> bool {
+    excludes.iter().any(|e| match e.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == e,
+    })
+}
+
+/// Parses the NUL separated `NAME=VALUE` entries from `/proc/<pid>/environ`.
+pub fn parse_environ(data: &[u8]) -> EnvSettings {
+    String::from_utf8_lossy(data)
+        .split('\0')
+        .filter_map(|entry| entry.split_once('='))
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+}
+
+fn unescape_double_quoted(value: &str) -> String {
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some('t') => result.push('\t'),
+            Some('r') => result.push('\r'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+/// Parses a dotenv file, which has `NAME=VALUE` lines with optional quoting, `export` prefixes,
+/// and `#` comments.
+pub fn parse_dotenv(text: &str) -> Result<EnvSettings, String> {
+    let mut result = EnvSettings::new();
+    for (index, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let (name, value) = match line.split_once('=') {
+            Some((name, value)) if !name.trim().is_empty() => (name.trim(), value.trim()),
+            _ => return Err(format!("line {} is not NAME=VALUE", index + 1)),
+        };
+        let value = if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+            unescape_double_quoted(&value[1..value.len() - 1])
+        } else if value.len() >= 2 && value.starts_with('\'') && value.ends_with('\'') {
+            value[1..value.len() - 1].to_string()
+        } else {
+            // unquoted values can have a trailing comment
+            match value.find(" #") {
+                Some(pos) => value[..pos].trim_end().to_string(),
+                None => value.to_string(),
+            }
+        };
+        result.insert(name.to_string(), value);
+    }
+    Ok(result)
+}
+
+/// Parses a JSON/YAML document with variable names as the top-level keys. Values that are not
+/// strings are compared using their JSON representation.
+pub fn parse_document(text: &str) -> Result<EnvSettings, String> {
+    let value = match serde_json::from_str::<Value>(text) {
+        Ok(value) => value,
+        Err(_) => serde_yaml::from_str::<Value>(text).map_err(|e| e.to_string())?,
+    };
+    let map = match value {
+        Value::Object(map) => map,
+        _ => return Err("expected a map of names to values".to_string()),
+    };
+    Ok(map
+        .into_iter()
+        .map(|(k, v)| {
+            let value = match v {
+                Value::String(s) => s,
+                Value::Null => "".to_string(),
+                other => other.to_string(),
+            };
+            (k, value)
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dotenv_values() {
+        let text = concat!(
+            "# a comment\n",
+            "\n",
+            "PLAIN=value # trailing comment\n",
+            "export EXPORTED=\"say \\\"hi\\\"\\n\"\n",
+            "SINGLE='it is # literal'\n",
+            "EMPTY=\n",
+            "  SPACED = padded  \n",
+        );
+        let vars = parse_dotenv(text).unwrap();
+        assert_eq!(vars.len(), 5);
+        assert_eq!(vars.get("PLAIN").unwrap(), "value");
+        assert_eq!(vars.get("EXPORTED").unwrap(), "say \"hi\"\n");
+        assert_eq!(vars.get("SINGLE").unwrap(), "it is # literal");
+        assert_eq!(vars.get("EMPTY").unwrap(), "");
+        assert_eq!(vars.get("SPACED").unwrap(), "padded");
+
+        assert_eq!(
+            parse_dotenv("GOOD=1\nnot a variable\n").unwrap_err(),
+            "line 2 is not NAME=VALUE"
+        );
+    }
+
+    #[test]
+    fn document_values() {
+        let vars = parse_document(r#"{"A": "text", "B": 12, "C": true, "D": null}"#).unwrap();
+        assert_eq!(vars.get("A").unwrap(), "text");
+        assert_eq!(vars.get("B").unwrap(), "12");
+        assert_eq!(vars.get("C").unwrap(), "true");
+        assert_eq!(vars.get("D").unwrap(), "");
+
+        let vars = parse_document("A: text\nB:\n  nested: 1\n").unwrap();
+        assert_eq!(vars.get("A").unwrap(), "text");
+        assert_eq!(vars.get("B").unwrap(), "{\"nested\":1}");
+
+        assert!(parse_document("[1, 2]").is_err());
+    }
+
+    #[test]
+    fn environ_values() {
+        let vars = parse_environ(b"A=1\0B=with=equals\0NOVALUE\0\0");
+        assert_eq!(vars.len(), 2);
+        assert_eq!(vars.get("A").unwrap(), "1");
+        assert_eq!(vars.get("B").unwrap(), "with=equals");
+    }
+
+    #[test]
+    fn exclusions() {
+        let excludes = vec!["PATH".to_string(), "KUBE_*".to_string()];
+        assert!(is_excluded("PATH", &excludes));
+        assert!(!is_excluded("PATHS", &excludes));
+        assert!(is_excluded("KUBE_HOST", &excludes));
+        assert!(!is_excluded("KUBE", &excludes));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn load_sources() {
+        let source = DriftSource::Process(std::process::id());
+        let vars = source.load().unwrap();
+        assert_eq!(vars.get("PATH"), env::var("PATH").ok().as_ref());
+
+        let source = DriftSource::File(PathBuf::from("/no/such/file.env"));
+        let err = source.load().unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Failed to read file '/no/such/file.env': "));
+    }
+}
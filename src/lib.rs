@@ -246,7 +246,7 @@ impl Write for StringWriter {
             Ok(s) => s,
             Err(e) => {
                 return Err(Error::new(
-                    ErrorKind::Other,
+                    ErrorKind::InvalidData,
                     format!("Cannot decode utf8 string : {}", e),
                 ))
             }
@@ -266,6 +266,8 @@ mod test {
     use super::*;
 
     #[test]
+    // the inputs are literal options, so the same value is passed to both parsers
+    #[allow(clippy::unnecessary_literal_unwrap)]
     fn timedate_and_tag_parsing() {
         // full RFC2822144
         let now = Utc::now();
@@ -75,14 +75,14 @@ pub fn process_login_command(subcmd_args: &ArgMatches, config: &Config) -> Resul
 
         if open_page {
             let open_result = webbrowser::open(&api_key_url);
-            if open_result.is_err() {
+            if let Err(open_err) = open_result {
                 printdoc!(
                     r#"
                     "Failed to open browser:
                     {}
 
                     You must manually open '{}' to generate a new key."#,
-                    open_result.unwrap_err().to_string(),
+                    open_err.to_string(),
                     api_key_url,
                 );
             }
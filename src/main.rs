@@ -1,12 +1,16 @@
+// the shared helpers live in `mod lib`, which is not used as a library crate root
+#![allow(special_module_name)]
 extern crate rpassword;
 
 mod actions;
 mod audit_logs;
 mod backup;
+mod cache;
 mod cli;
 mod config;
 mod configuration;
 mod database;
+mod drift;
 mod environments;
 mod generate;
 mod groups;
@@ -18,8 +22,11 @@ mod login;
 mod logout;
 mod parameters;
 mod projects;
+mod property_filter;
+mod references;
 mod run;
 mod schema;
+mod semantic_diff;
 mod subprocess;
 mod table;
 mod templates;
@@ -30,6 +37,7 @@ mod versions;
 use crate::actions::process_actions_command;
 use crate::audit_logs::process_audit_log_command;
 use crate::backup::process_backup_command;
+use crate::cache::process_cache_command;
 use crate::config::env::ConfigEnv;
 use crate::config::{Action, Config, Updates, CT_PROFILE, DEFAULT_ENV_NAME};
 use crate::configuration::process_config_command;
@@ -43,7 +51,7 @@ use crate::integrations::process_integrations_command;
 use crate::lib::{error_message, help_message, warning_message};
 use crate::login::process_login_command;
 use crate::logout::process_logout_command;
-use crate::parameters::process_parameters_command;
+use crate::parameters::{process_parameters_command, process_parameters_search};
 use crate::projects::process_project_command;
 use crate::run::process_run_command;
 use crate::schema::process_schema_command;
@@ -173,6 +181,11 @@ fn main() -> Result<()> {
         process::exit(0)
     }
 
+    if let Some(matches) = matches.subcommand_matches("cache") {
+        process_cache_command(matches)?;
+        process::exit(0)
+    }
+
     // check for updates based on the configuration (if any)
     if let Some(updates) = Config::load_updates()? {
         check_updates(&updates)?;
@@ -183,8 +196,8 @@ fn main() -> Result<()> {
     // commands will talk to the server
     let cfg_result = Config::load_config(api_key, profile_name, env_name, proj_name);
     if let Err(error) = cfg_result {
-        let profile_info = if profile_name.is_some() {
-            format!(" from profile '{}'", profile_name.unwrap())
+        let profile_info = if let Some(profile_name) = profile_name {
+            format!(" from profile '{}'", profile_name)
         } else {
             "".to_string()
         };
@@ -273,6 +286,22 @@ fn main() -> Result<()> {
         process::exit(0);
     }
 
+    if let Some(matches) = matches.subcommand_matches("run") {
+        // NOTE: run resolves the environment/project, so it can fall back to cached values
+        process_run_command(matches, &rest_cfg, config)?;
+        process::exit(0);
+    }
+
+    if let Some(matches) = matches
+        .subcommand_matches("parameters")
+        .and_then(|m| m.subcommand_matches("search"))
+    {
+        // NOTE: search looks through all the projects, so it does not need a resolved project
+        let env_name = config.environment.as_deref().unwrap_or(DEFAULT_ENV_NAME);
+        process_parameters_search(matches, &rest_cfg, env_name)?;
+        process::exit(0);
+    }
+
     //====================================================
     // Everything below here requires resolved environment/project values
     let resolver = Resolver::new();
@@ -288,10 +317,6 @@ fn main() -> Result<()> {
         process_templates_command(matches, &rest_cfg, &resolved)?;
     }
 
-    if let Some(matches) = matches.subcommand_matches("run") {
-        process_run_command(matches, &rest_cfg, &resolved)?;
-    }
-
     Ok(())
 }
 
@@ -343,13 +368,13 @@ mod main_test {
     #[test]
     fn completions_work_without_config() {
         let mut cmd = cmd();
-        cmd.args(&["completions", "bash"]).assert().success();
+        cmd.args(["completions", "bash"]).assert().success();
     }
 
     #[test]
     fn completions_error_with_bad_shell_name() {
         let mut cmd = cmd();
-        cmd.args(&["completions", "bad"])
+        cmd.args(["completions", "bad"])
             .assert()
             .failure()
             .stderr(contains("'bad' isn't a valid value"));
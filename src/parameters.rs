@@ -1,33 +1,444 @@
 use crate::cli::{
     binary_name, show_values, true_false_option, AS_OF_ARG, CONFIRM_FLAG, DELETE_SUBCMD,
-    DESCRIPTION_OPT, DIFF_SUBCMD, FORMAT_OPT, GET_SUBCMD, IMMEDIATE_PARAMETERS_FLAG, JMES_PATH_ARG,
-    KEY_ARG, LIST_SUBCMD, PUSH_SUBCMD, RENAME_OPT, RULE_MAX_ARG, RULE_MAX_LEN_ARG, RULE_MIN_ARG,
-    RULE_MIN_LEN_ARG, RULE_NO_MAX_ARG, RULE_NO_MAX_LEN_ARG, RULE_NO_MIN_ARG, RULE_NO_MIN_LEN_ARG,
-    RULE_NO_REGEX_ARG, RULE_REGEX_ARG, SECRETS_FLAG, SET_SUBCMD, SHOW_TIMES_FLAG,
+    DESCRIPTION_OPT, DIFF_SUBCMD, EDIT_SUBCMD, FORMAT_OPT, GET_SUBCMD, HISTORY_SUBCMD,
+    IMMEDIATE_PARAMETERS_FLAG, JMES_PATH_ARG, KEY_ARG, LIST_SUBCMD, PUSH_SUBCMD, RENAME_OPT,
+    RULE_MAX_ARG, RULE_MAX_LEN_ARG, RULE_MIN_ARG, RULE_MIN_LEN_ARG, RULE_NO_MAX_ARG,
+    RULE_NO_MAX_LEN_ARG, RULE_NO_MIN_ARG, RULE_NO_MIN_LEN_ARG, RULE_NO_REGEX_ARG, RULE_REGEX_ARG,
+    SECRETS_FLAG, SEMANTIC_FLAG, SET_SUBCMD, SHOW_TIMES_FLAG, TAG_NAME_OPT,
 };
-use crate::config::DEFAULT_ENV_NAME;
+use crate::config::{Config, KeyRules, DEFAULT_ENV_NAME};
 use crate::database::{
-    EnvironmentDetails, Environments, OpenApiConfig, ParamExportFormat, ParamExportOptions,
-    ParamRuleType, ParameterDetails, ParameterError, Parameters, Projects, ResolvedDetails,
-    TaskStepDetails,
+    base_type_violation, last_from_url, EnvironmentDetails, EnvironmentError, Environments,
+    ManifestEnvironment, OpenApiConfig, ParamExportFormat, ParamExportOptions, ParamRuleType,
+    ParameterDetailMap, ParameterDetails, ParameterError, ParameterHistory, ParameterManifest,
+    ParameterRuleDetail, Parameters, PlanAction, Projects, ResolvedDetails, TaskStepDetails,
+    Templates, Types,
 };
+use crate::drift::{is_excluded, DriftSource, DEFAULT_EXCLUDES};
 use crate::lib::{
     error_message, format_param_error, help_message, parse_datetime, parse_tag, user_confirm,
     warn_missing_subcommand, warn_unresolved_params, warning_message, DEL_CONFIRM, FILE_READ_ERR,
-    REDACTED,
+    ISO8601, REDACTED,
 };
+use crate::property_filter::{PropertyFilter, PropertySort};
+use crate::references::{find_references, replace_references};
+use crate::semantic_diff::{flatten, parse_structured};
 use crate::subprocess::EnvSettings;
 use crate::table::Table;
+use chrono::{DateTime, Duration, Utc};
 use clap::ArgMatches;
 use color_eyre::eyre::Result;
 use color_eyre::Report;
 use indoc::printdoc;
+use regex::Regex;
 use rpassword::read_password;
-use std::env;
-use std::fs;
+use similar::TextDiff;
+use std::collections::{BTreeSet, HashMap};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
 use std::process;
 use std::str::FromStr;
 
+/// A template or an evaluated parameter value that references a parameter.
+#[derive(Clone, Debug)]
+struct ParamReferrer {
+    project_name: String,
+    project_id: String,
+    kind: &'static str,
+    /// The template or parameter id
+    id: String,
+    /// The value id, which is empty for templates
+    value_id: String,
+    name: String,
+    environment: String,
+    secret: bool,
+    /// The template body or the unevaluated value
+    text: String,
+    expressions: Vec<String>,
+}
+
+/// Returns `true` when the parameter id is one of the referenced parameter urls.
+fn references_param(referenced_params: &[String], param_id: &str) -> bool {
+    referenced_params
+        .iter()
+        .any(|url| last_from_url(url) == param_id)
+}
+
+/// Gets the expressions referencing the parameter, with a placeholder when the server records a
+/// reference that is not found in the text.
+fn referrer_expressions(text: &str, param_name: &str) -> Vec<String> {
+    let expressions = find_references(text, param_name);
+    if expressions.is_empty() {
+        return vec!["-".to_string()];
+    }
+    expressions.iter().map(|e| e.to_string()).collect()
+}
+
+/// Finds the templates and evaluated parameter values in the project and its descendants that
+/// reference the parameter.
+///
+/// The referrers come from the references the server records for each template and value, so the
+/// text is only used to show (or rewrite) the expressions. Masked secret values show a redacted
+/// expression.
+fn find_param_references(
+    rest_cfg: &OpenApiConfig,
+    proj_name: &str,
+    proj_id: &str,
+    param_id: &str,
+    param_name: &str,
+    mask_secrets: bool,
+) -> Result<Vec<ParamReferrer>> {
+    let projects = Projects::new();
+    let mut scanned = vec![(proj_name.to_string(), proj_id.to_string())];
+    for prj in projects.get_project_descendants(rest_cfg, proj_name)? {
+        scanned.push((prj.name, prj.id));
+    }
+
+    let environments = Environments::new();
+    let env_url_map = environments.get_url_name_map(rest_cfg);
+    let parameters = Parameters::new();
+    let templates = Templates::new();
+    let immediate_parameters = true; // inherited values are scanned with the parent project
+    let mut result: Vec<ParamReferrer> = vec![];
+    for (prj_name, prj_id) in &scanned {
+        for template in templates.get_template_details(rest_cfg, prj_id)? {
+            if references_param(&template.referenced_params, param_id) {
+                result.push(ParamReferrer {
+                    project_name: prj_name.clone(),
+                    project_id: prj_id.clone(),
+                    kind: "template",
+                    id: template.id.clone(),
+                    value_id: "".to_string(),
+                    name: template.name.clone(),
+                    environment: "".to_string(),
+                    secret: false,
+                    expressions: referrer_expressions(&template.body, param_name),
+                    text: template.body,
+                });
+            }
+        }
+        let values = parameters.get_all_environment_values(
+            rest_cfg,
+            prj_id,
+            None,
+            immediate_parameters,
+            mask_secrets,
+            false,
+            None,
+        )?;
+        for details in values.into_iter().filter(|d| d.evaluated) {
+            // values inherited by other environments show up more than once
+            if result.iter().any(|r| r.value_id == details.val_id)
+                || !references_param(&details.referenced_params, param_id)
+            {
+                continue;
+            }
+            let expressions: Vec<String> = if details.secret && mask_secrets {
+                vec![REDACTED.to_string()]
+            } else {
+                referrer_expressions(&details.raw_value, param_name)
+            };
+            result.push(ParamReferrer {
+                project_name: prj_name.clone(),
+                project_id: prj_id.clone(),
+                kind: "parameter",
+                id: details.id.clone(),
+                value_id: details.val_id.clone(),
+                name: details.key.clone(),
+                environment: env_url_map
+                    .get(&details.env_url)
+                    .cloned()
+                    .unwrap_or_default(),
+                secret: details.secret,
+                expressions,
+                text: details.raw_value,
+            });
+        }
+    }
+    result.sort_by(|l, r| {
+        (&l.project_name, l.kind, &l.name, &l.environment).cmp(&(
+            &r.project_name,
+            r.kind,
+            &r.name,
+            &r.environment,
+        ))
+    });
+    Ok(result)
+}
+
+fn proc_param_rename(
+    subcmd_args: &ArgMatches,
+    rest_cfg: &OpenApiConfig,
+    parameters: &Parameters,
+    resolved: &ResolvedDetails,
+) -> Result<()> {
+    let key_name = subcmd_args.value_of(KEY_ARG).unwrap();
+    let new_name = subcmd_args.value_of("NEW_NAME").unwrap();
+    let dry_run = subcmd_args.is_present("dry-run");
+    let confirmed = subcmd_args.is_present(CONFIRM_FLAG);
+    let fmt = subcmd_args.value_of(FORMAT_OPT).unwrap();
+    let proj_name = resolved.project_display_name();
+    let proj_id = resolved.project_id();
+    let env_id = resolved.environment_id();
+    let evaluate = false;
+    let immediate_parameters = false;
+    let mask_secrets = true;
+    let details = parameters.get_details_by_name(
+        rest_cfg,
+        proj_id,
+        env_id,
+        key_name,
+        evaluate,
+        immediate_parameters,
+        mask_secrets,
+        None,
+        None,
+    )?;
+    let param_id = match details {
+        Some(details) if !details.project_url.contains(proj_id) => {
+            error_message(format!(
+                "Parameter '{}' must be renamed in project '{}' -- it is not part of project '{}'",
+                key_name, details.project_name, proj_name
+            ));
+            process::exit(69);
+        }
+        Some(details) => details.id,
+        None => {
+            error_message(format!(
+                "Did not find parameter '{}' in project '{}'",
+                key_name, proj_name
+            ));
+            process::exit(60);
+        }
+    };
+    let existing = parameters.get_details_by_name(
+        rest_cfg,
+        proj_id,
+        env_id,
+        new_name,
+        evaluate,
+        immediate_parameters,
+        mask_secrets,
+        None,
+        None,
+    )?;
+    if existing.is_some() {
+        error_message(format!(
+            "Parameter '{}' already exists in project '{}'",
+            new_name, proj_name
+        ));
+        process::exit(61);
+    }
+
+    // the unmasked text is needed to rewrite the secret values
+    let mask_secrets = false;
+    let referrers = find_param_references(
+        rest_cfg,
+        proj_name,
+        proj_id,
+        &param_id,
+        key_name,
+        mask_secrets,
+    )?;
+    let verb = if dry_run { "Would rename" } else { "Renaming" };
+    println!(
+        "{} parameter '{}' to '{}' in project '{}'.",
+        verb, key_name, new_name, proj_name
+    );
+    if referrers.is_empty() {
+        println!(
+            "No templates or evaluated parameters reference '{}'.",
+            key_name
+        );
+    } else {
+        let mut table = Table::new("parameter-rename");
+        table.set_header(&[
+            "Project",
+            "Type",
+            "Name",
+            "Environment",
+            "Current",
+            "Updated",
+        ]);
+        for referrer in &referrers {
+            for expression in &referrer.expressions {
+                table.add_row(vec![
+                    referrer.project_name.clone(),
+                    referrer.kind.to_string(),
+                    referrer.name.clone(),
+                    referrer.environment.clone(),
+                    expression.clone(),
+                    replace_references(expression, key_name, new_name),
+                ]);
+            }
+        }
+        table.render(fmt)?;
+    }
+
+    if dry_run {
+        return Ok(());
+    }
+    if !confirmed
+        && !user_confirm(
+            format!(
+                "Rename parameter '{}' to '{}' and update {} template(s)/value(s)",
+                key_name,
+                new_name,
+                referrers.len()
+            ),
+            Some(false),
+        )
+    {
+        return Ok(());
+    }
+
+    // rename first, so the updated references are valid
+    parameters.update_parameter(rest_cfg, proj_id, &param_id, new_name, None, None, None)?;
+    println!(
+        "Renamed parameter '{}' to '{}' in project '{}'.",
+        key_name, new_name, proj_name
+    );
+
+    // keep going when a referrer cannot be updated, so the rest of the references are valid
+    let templates = Templates::new();
+    let mut failures: Vec<(&ParamReferrer, String)> = vec![];
+    for referrer in &referrers {
+        let updated = replace_references(&referrer.text, key_name, new_name);
+        if updated == referrer.text {
+            failures.push((referrer, "reference not found in the text".to_string()));
+        } else if referrer.value_id.is_empty() {
+            match templates.update_template(
+                rest_cfg,
+                &referrer.project_id,
+                &referrer.id,
+                &referrer.name,
+                None,
+                Some(&updated),
+            ) {
+                Ok(_) => println!(
+                    "Updated template '{}' in project '{}'.",
+                    referrer.name, referrer.project_name
+                ),
+                Err(err) => failures.push((referrer, err.to_string())),
+            }
+        } else {
+            match parameters.update_parameter_value(
+                rest_cfg,
+                &referrer.project_id,
+                &referrer.id,
+                &referrer.value_id,
+                referrer.secret,
+                Some(&updated),
+                None,
+                None,
+                Some(true),
+            ) {
+                Ok(_) => println!(
+                    "Updated parameter '{}' in environment '{}' of project '{}'.",
+                    referrer.name, referrer.environment, referrer.project_name
+                ),
+                Err(err) => failures.push((referrer, err.to_string())),
+            }
+        }
+    }
+
+    if !failures.is_empty() {
+        let manual: Vec<String> = failures
+            .iter()
+            .map(|(referrer, err)| {
+                let location = if referrer.environment.is_empty() {
+                    format!("project '{}'", referrer.project_name)
+                } else {
+                    format!(
+                        "environment '{}' of project '{}'",
+                        referrer.environment, referrer.project_name
+                    )
+                };
+                format!(
+                    "{} '{}' in {}: {}",
+                    referrer.kind, referrer.name, location, err
+                )
+            })
+            .collect();
+        error_message(format!(
+            "Failed to update {} reference(s) to '{}', which need to be updated manually:\n  {}",
+            manual.len(),
+            new_name,
+            manual.join("\n  ")
+        ));
+        process::exit(64);
+    }
+    Ok(())
+}
+
+fn proc_param_references(
+    subcmd_args: &ArgMatches,
+    rest_cfg: &OpenApiConfig,
+    parameters: &Parameters,
+    resolved: &ResolvedDetails,
+) -> Result<()> {
+    let key_name = subcmd_args.value_of(KEY_ARG).unwrap();
+    let fmt = subcmd_args.value_of(FORMAT_OPT).unwrap();
+    let proj_name = resolved.project_display_name();
+    let proj_id = resolved.project_id();
+    let mask_secrets = true;
+    let evaluate = false;
+    let immediate_parameters = false;
+    let details = parameters.get_details_by_name(
+        rest_cfg,
+        proj_id,
+        resolved.environment_id(),
+        key_name,
+        evaluate,
+        immediate_parameters,
+        mask_secrets,
+        None,
+        None,
+    )?;
+    let param_id = match details {
+        Some(details) => details.id,
+        None => {
+            error_message(format!(
+                "Did not find parameter '{}' in project '{}'",
+                key_name, proj_name
+            ));
+            process::exit(68);
+        }
+    };
+    let references = find_param_references(
+        rest_cfg,
+        proj_name,
+        proj_id,
+        &param_id,
+        key_name,
+        mask_secrets,
+    )?;
+    if references.is_empty() {
+        println!(
+            "No references to parameter '{}' found in project '{}' or its descendants.",
+            key_name, proj_name
+        );
+        return Ok(());
+    }
+
+    let mut table = Table::new("parameter-references");
+    table.set_header(&["Project", "Type", "Name", "Environment", "Reference"]);
+    for referrer in references {
+        for expression in &referrer.expressions {
+            table.add_row(vec![
+                referrer.project_name.clone(),
+                referrer.kind.to_string(),
+                referrer.name.clone(),
+                referrer.environment.clone(),
+                expression.clone(),
+            ]);
+        }
+    }
+    table.render(fmt)?;
+    Ok(())
+}
+
 fn proc_param_delete(
     subcmd_args: &ArgMatches,
     rest_cfg: &OpenApiConfig,
@@ -73,6 +484,35 @@ fn proc_param_delete(
         return Ok(());
     }
 
+    // checking the references is best-effort, so the delete is not blocked by the scan
+    let param_id = param_id.unwrap();
+    match find_param_references(
+        rest_cfg,
+        proj_name,
+        proj_id,
+        &param_id,
+        key_name,
+        mask_secrets,
+    ) {
+        Ok(references) => {
+            let ref_count: usize = references.iter().map(|r| r.expressions.len()).sum();
+            if ref_count > 0 {
+                warning_message(format!(
+                    "Parameter '{}' is referenced {} time(s) in project '{}' and its descendants. Use '{} parameters references {}' to see where.",
+                    key_name,
+                    ref_count,
+                    proj_name,
+                    binary_name(),
+                    key_name,
+                ));
+            }
+        }
+        Err(err) => warning_message(format!(
+            "Could not check for references to parameter '{}': {}",
+            key_name, err
+        )),
+    }
+
     if !confirmed {
         printdoc!(
             r#"
@@ -95,8 +535,7 @@ fn proc_param_delete(
         }
     }
 
-    let result =
-        parameters.delete_parameter_by_id(rest_cfg, proj_id, param_id.unwrap().as_str())?;
+    let result = parameters.delete_parameter_by_id(rest_cfg, proj_id, param_id.as_str())?;
     match result {
         Some(_) => {
             println!(
@@ -116,6 +555,212 @@ fn proc_param_delete(
     Ok(())
 }
 
+/// Edits the secret in a private temporary directory (so editor swap files are private, too), and
+/// overwrites the file before everything is removed.
+fn edit_secret(text: &str) -> Result<String> {
+    let mut builder = tempfile::Builder::new();
+    builder.prefix("cloudtruth-");
+    let dir = if Path::new("/dev/shm").is_dir() {
+        builder.tempdir_in("/dev/shm")?
+    } else {
+        builder.tempdir()?
+    };
+    let path = dir.path().join("value");
+    let mut options = OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    let result = options
+        .open(&path)
+        .and_then(|mut file| file.write_all(text.as_bytes()))
+        .and_then(|_| edit::edit_file(&path))
+        .and_then(|_| fs::read_to_string(&path));
+
+    // the wipe is best-effort, so the directory is always removed
+    let wipe = |path: &Path| -> io::Result<()> {
+        let len = fs::metadata(path)?.len();
+        let mut file = OpenOptions::new().write(true).open(path)?;
+        file.write_all(&vec![0u8; len as usize])?;
+        file.sync_all()
+    };
+    if let Err(e) = wipe(&path) {
+        if e.kind() != io::ErrorKind::NotFound {
+            warning_message(format!("Failed to overwrite the edited value: {}", e));
+        }
+    }
+    let dir_path = dir.path().to_path_buf();
+    if let Err(e) = dir.close() {
+        warning_message(format!(
+            "Failed to remove the temporary directory '{}': {}",
+            dir_path.display(),
+            e
+        ));
+    }
+    Ok(result?)
+}
+
+/// Saves a rejected value in a private temporary file that is kept, so the edits are not lost.
+fn keep_rejected_value(text: &str) -> Result<PathBuf> {
+    let mut file = tempfile::Builder::new()
+        .prefix("cloudtruth-")
+        .suffix(".txt")
+        .tempfile()?;
+    file.write_all(text.as_bytes())?;
+    let (_, path) = file.keep()?;
+    Ok(path)
+}
+
+fn proc_param_edit(
+    subcmd_args: &ArgMatches,
+    rest_cfg: &OpenApiConfig,
+    parameters: &Parameters,
+    resolved: &ResolvedDetails,
+) -> Result<()> {
+    let key_name = subcmd_args.value_of(KEY_ARG).unwrap();
+    let confirmed = subcmd_args.is_present(CONFIRM_FLAG);
+    let show_secrets = subcmd_args.is_present(SECRETS_FLAG);
+    let proj_name = resolved.project_display_name();
+    let proj_id = resolved.project_id();
+    let env_id = resolved.environment_id();
+    let env_name = resolved.environment_display_name();
+    let evaluate = false; // edit the unevaluated value
+    let immediate_parameters = false;
+    let mask_secrets = false;
+    let details = match parameters.get_details_by_name(
+        rest_cfg,
+        proj_id,
+        env_id,
+        key_name,
+        evaluate,
+        immediate_parameters,
+        mask_secrets,
+        None,
+        None,
+    )? {
+        Some(details) => details,
+        None => {
+            error_message(format!(
+                "Did not find parameter '{}' in project '{}'",
+                key_name, proj_name
+            ));
+            process::exit(71);
+        }
+    };
+    if !details.project_url.contains(proj_id) {
+        error_message(format!(
+            "Parameter '{}' must be set from project '{}' -- it is not part of project '{}'",
+            key_name, details.project_name, proj_name
+        ));
+        process::exit(72);
+    }
+    if details.external {
+        warning_message(format!(
+            "Parameter '{}' has an external value in environment '{}', which cannot be edited.",
+            key_name, env_name
+        ));
+        return Ok(());
+    }
+
+    let current = match (details.val_id.is_empty(), details.evaluated) {
+        (true, _) => "".to_string(),
+        (false, true) => details.raw_value.clone(),
+        (false, false) => details.value.clone(),
+    };
+    // a rejected value goes back to the editor, so the changes are not lost
+    let mut text = current.clone();
+    let updated = loop {
+        let mut updated = match details.secret {
+            true => edit_secret(&text)?,
+            false => edit::edit(text.as_bytes())?,
+        };
+        // editors usually add a final newline, which is not wanted for single-line values
+        if !current.ends_with('\n') {
+            let trimmed = updated.trim_end_matches(&['\r', '\n'][..]).len();
+            updated.truncate(trimmed);
+        }
+        if updated == current {
+            println!("Nothing to update in parameter '{}'", key_name);
+            return Ok(());
+        }
+
+        // evaluated values are checked by the server, since the result is not known yet
+        if details.evaluated {
+            break updated;
+        }
+        let violations = value_violations(rest_cfg, &updated, &details.rules, &details.param_type)?;
+        if print_violations(key_name, &violations) {
+            break updated;
+        }
+        if confirmed || !user_confirm("Edit the value again".to_string(), None) {
+            let path = keep_rejected_value(&updated)?;
+            warning_message(format!(
+                "The edited value was saved to '{}' -- remove the file when it is no longer needed.",
+                path.display()
+            ));
+            process::exit(VALIDATE_EXIT_CODE);
+        }
+        text = updated;
+    };
+
+    if details.secret && !show_secrets {
+        println!(
+            "The secret value of parameter '{}' changed (use --secrets to show the changes).",
+            key_name
+        );
+    } else {
+        TextDiff::from_lines(&current, &updated)
+            .unified_diff()
+            .header(env_name, &format!("{} (edited)", env_name))
+            .to_writer(io::stdout())?;
+    }
+    if !confirmed
+        && !user_confirm(
+            format!(
+                "Update parameter '{}' in environment '{}'",
+                key_name, env_name
+            ),
+            None,
+        )
+    {
+        return Ok(());
+    }
+
+    // values inherited from a parent environment get an override
+    if details.val_id.is_empty() || !details.env_url.contains(env_id) {
+        parameters.create_parameter_value(
+            rest_cfg,
+            proj_id,
+            env_id,
+            &details.id,
+            details.secret,
+            Some(&updated),
+            None,
+            None,
+            Some(details.evaluated),
+        )?;
+    } else {
+        parameters.update_parameter_value(
+            rest_cfg,
+            proj_id,
+            &details.id,
+            &details.val_id,
+            details.secret,
+            Some(&updated),
+            None,
+            None,
+            Some(details.evaluated),
+        )?;
+    }
+    println!(
+        "Updated parameter '{}' in project '{}' for environment '{}'.",
+        key_name, proj_name, env_name
+    );
+    Ok(())
+}
+
 fn proc_param_diff(
     subcmd_args: &ArgMatches,
     rest_cfg: &OpenApiConfig,
@@ -131,109 +776,85 @@ fn proc_param_diff(
         .unwrap_or_default()
         .collect();
     let env_list: Vec<&str> = subcmd_args.values_of("ENV").unwrap_or_default().collect();
-    let max_len: usize = 2;
-
-    if env_list.len() > max_len {
-        warning_message(format!(
-            "Can specify a maximum of {} environment values.",
-            max_len
-        ));
-        return Ok(());
-    }
-    if as_list.len() > max_len {
+    let show_all = subcmd_args.is_present("all");
+    let semantic = subcmd_args.is_present(SEMANTIC_FLAG);
+
+    // a single environment or time goes in the right-hand column, and is compared to current
+    let current_env = resolved.environment_display_name();
+    let env_cols: Vec<&str> = match env_list.len() {
+        0 => vec![current_env],
+        1 => vec![current_env, env_list[0]],
+        _ => env_list.clone(),
+    };
+    // a single time goes with the right-hand environment of a 2-column diff, and applies to
+    // every environment when there are more
+    let as_cols: Vec<Option<&str>> = match as_list.len() {
+        0 => vec![None],
+        1 if env_list.len() > 2 => vec![Some(as_list[0])],
+        1 => vec![None, Some(as_list[0])],
+        _ => as_list.iter().copied().map(Some).collect(),
+    };
+    let col_count = env_cols.len().max(as_cols.len()).max(2);
+    if env_cols.len() > 1 && as_cols.len() > 1 && env_cols.len() != as_cols.len() {
         warning_message(format!(
-            "Can specify a maximum of {} as-of values.",
-            max_len
+            concat!(
+                "Cannot compare {} environment(s) to {} time(s) -- use one --as-of for all the ",
+                "environments, or one for each environment."
+            ),
+            env_list.len().max(1),
+            as_list.len()
         ));
         return Ok(());
     }
 
-    let env1_name: String;
-    let env2_name: String;
-    if env_list.len() == 2 {
-        env1_name = env_list[0].to_string();
-        env2_name = env_list[1].to_string();
-    } else if env_list.len() == 1 {
-        env1_name = resolved.environment_display_name().to_string();
-        env2_name = env_list[0].to_string();
-    } else {
-        env1_name = resolved.environment_display_name().to_string();
-        env2_name = resolved.environment_display_name().to_string();
-    }
-
-    let as_tag1: Option<&str>;
-    let as_tag2: Option<&str>;
-    if as_list.len() == 2 {
-        as_tag1 = Some(as_list[0]);
-        as_tag2 = Some(as_list[1]);
-    } else if as_list.len() == 1 {
-        // puts the specified time in other column
-        as_tag1 = None;
-        as_tag2 = Some(as_list[0]);
-    } else {
-        as_tag1 = None;
-        as_tag2 = None;
-    }
-
-    let as_of1 = parse_datetime(as_tag1);
-    let as_of2 = parse_datetime(as_tag2);
-    let tag1 = parse_tag(as_tag1);
-    let tag2 = parse_tag(as_tag2);
-
-    if env1_name == env2_name && as_tag1 == as_tag2 {
-        warning_message("Invalid comparing an environment to itself".to_string());
-        return Ok(());
+    // the (environment, as-of) for each column, where single values apply to every column
+    let columns: Vec<(&str, Option<&str>)> = (0..col_count)
+        .map(|i| {
+            let env_name = env_cols[if env_cols.len() == 1 { 0 } else { i }];
+            let as_tag = as_cols[if as_cols.len() == 1 { 0 } else { i }];
+            (env_name, as_tag)
+        })
+        .collect();
+    for (i, col) in columns.iter().enumerate() {
+        if columns[i + 1..].contains(col) {
+            warning_message("Invalid comparing an environment to itself".to_string());
+            return Ok(());
+        }
     }
 
-    let header1: String;
-    let header2: String;
-    if env1_name == env2_name {
-        header1 = as_tag1.unwrap_or("Current").to_string();
-        header2 = as_tag2.unwrap_or("Unspecified").to_string();
-    } else if as_tag1 == as_tag2 {
-        header1 = env1_name.to_string();
-        header2 = env2_name.to_string();
-    } else {
-        header1 = match as_tag1 {
-            Some(a) => format!("{} ({})", env1_name, a),
-            _ => env1_name.to_string(),
-        };
-        header2 = match as_tag2 {
-            Some(a) => format!("{} ({})", env2_name, a),
-            _ => env2_name.to_string(),
-        };
-    }
+    let same_env = columns.iter().all(|(e, _)| *e == columns[0].0);
+    let same_time = columns.iter().all(|(_, a)| *a == columns[0].1);
+    let headers: Vec<String> = columns
+        .iter()
+        .map(|(env_name, as_tag)| match as_tag {
+            Some(a) if same_env => a.to_string(),
+            None if same_env => "Current".to_string(),
+            Some(a) if !same_time => format!("{} ({})", env_name, a),
+            _ => env_name.to_string(),
+        })
+        .collect();
 
     // fetch all environments once, and then determine id's from the same map that is
     // used to resolve the environment names.
     let environments = Environments::new();
     let env_url_map = environments.get_url_name_map(rest_cfg);
-    let env1_id = environments.id_from_map(&env1_name, &env_url_map)?;
-    let env2_id = environments.id_from_map(&env2_name, &env_url_map)?;
-
     let proj_id = resolved.project_id();
-    let env1_values = parameters.get_parameter_detail_map(
-        rest_cfg,
-        proj_id,
-        &env1_id,
-        immediate_parameters,
-        !show_secrets,
-        as_of1,
-        tag1,
-    )?;
-    let env2_values = parameters.get_parameter_detail_map(
-        rest_cfg,
-        proj_id,
-        &env2_id,
-        immediate_parameters,
-        !show_secrets,
-        as_of2,
-        tag2,
-    )?;
+    let mut env_values: Vec<ParameterDetailMap> = vec![];
+    for (env_name, as_tag) in &columns {
+        let env_id = environments.id_from_map(env_name, &env_url_map)?;
+        env_values.push(parameters.get_parameter_detail_map(
+            rest_cfg,
+            proj_id,
+            &env_id,
+            immediate_parameters,
+            !show_secrets,
+            parse_datetime(*as_tag),
+            parse_tag(*as_tag),
+        )?);
+    }
 
-    // get the names from both lists to make sure we get the added/deleted parameters, too
-    let mut param_list: Vec<String> = env1_values.iter().map(|(k, _)| k.clone()).collect();
-    param_list.append(&mut env2_values.iter().map(|(k, _)| k.clone()).collect());
+    // get the names from all lists to make sure we get the added/deleted parameters, too
+    let mut param_list: Vec<String> = env_values.iter().flat_map(|m| m.keys().cloned()).collect();
     param_list.sort_by_key(|l| l.to_lowercase());
     param_list.dedup();
 
@@ -241,23 +862,60 @@ fn proc_param_diff(
     let mut added = false;
     let mut table = Table::new("parameter");
     let mut errors: Vec<String> = vec![];
-    table.set_header(&["Parameter", &header1, &header2]);
+    let mut hdr: Vec<&str> = vec!["Parameter"];
+    if semantic {
+        hdr.push("Path");
+    }
+    hdr.extend(headers.iter().map(String::as_str));
+    table.set_header(&hdr);
     for param_name in param_list {
-        let details1 = env1_values.get(&param_name).unwrap_or(&default_param);
-        let details2 = env2_values.get(&param_name).unwrap_or(&default_param);
-        let env1 = details1.get_properties(&properties).join(",\n");
-        let env2 = details2.get_properties(&properties).join(",\n");
-        if !details1.error.is_empty() {
-            errors.push(format_param_error(&param_name, &details1.error))
-        }
-        // NOTE: do not put redundant errors on the list, but the errors could be due to
-        //       different FQNs
-        if !details2.error.is_empty() && details1.error != details2.error {
-            errors.push(format_param_error(&param_name, &details2.error))
-        }
-        if env1 != env2 {
-            table.add_row(vec![param_name, env1, env2]);
-            added = true;
+        let mut cells: Vec<String> = vec![];
+        for values in &env_values {
+            let details = values.get(&param_name).unwrap_or(&default_param);
+            // NOTE: do not put redundant errors on the list, but the errors could be due to
+            //       different FQNs
+            if !details.error.is_empty() {
+                let err = format_param_error(&param_name, &details.error);
+                if !errors.contains(&err) {
+                    errors.push(err);
+                }
+            }
+            cells.push(details.get_properties(&properties).join(",\n"));
+        }
+
+        // when all the values are documents, compare each of the key-paths instead of the text
+        let documents: Option<Vec<_>> = match semantic {
+            true => cells.iter().map(|c| parse_structured(c)).collect(),
+            false => None,
+        };
+        let mut rows: Vec<Vec<String>> = vec![];
+        if let Some(documents) = documents {
+            let flattened: Vec<_> = documents.iter().map(flatten).collect();
+            let paths: BTreeSet<&String> = flattened.iter().flat_map(|f| f.keys()).collect();
+            for path in paths {
+                let mut row = vec![param_name.clone(), path.clone()];
+                row.extend(
+                    flattened
+                        .iter()
+                        .map(|f| f.get(path).cloned().unwrap_or_default()),
+                );
+                rows.push(row);
+            }
+        } else {
+            let mut row = vec![param_name.clone()];
+            if semantic {
+                row.push("".to_string());
+            }
+            row.append(&mut cells);
+            rows.push(row);
+        }
+
+        let first = hdr.len() - headers.len();
+        for row in rows {
+            if show_all || row[first + 1..].iter().any(|v| *v != row[first]) {
+                table.add_row(row);
+                added = true;
+            }
         }
     }
     if added {
@@ -290,8 +948,7 @@ fn get_env_order_for(parent_name: &str, environments: &[EnvironmentDetails]) ->
 fn get_env_order(environments: &[EnvironmentDetails]) -> Vec<String> {
     let default_url = environments
         .iter()
-        .filter(|v| v.name == DEFAULT_ENV_NAME)
-        .last()
+        .rfind(|v| v.name == DEFAULT_ENV_NAME)
         .unwrap()
         .url
         .clone();
@@ -393,6 +1050,66 @@ fn proc_param_env(
     Ok(())
 }
 
+/// Exports the parameters locally, using the `key_rules` for the variable names. This is needed for
+/// the key rules (the server does not know about them), and the client-side formats.
+fn export_with_key_rules(
+    rest_cfg: &OpenApiConfig,
+    parameters: &Parameters,
+    resolved: &ResolvedDetails,
+    key_rules: &KeyRules,
+    options: ParamExportOptions,
+) -> Result<String> {
+    let mask_secrets = !options.secrets.unwrap_or(false);
+    // the details (rather than `get_parameter_values()`) are needed to skip the parameters without
+    // a value in the environment, since the value map fills those in with a default value
+    let details = parameters.get_parameter_details(
+        rest_cfg,
+        resolved.project_id(),
+        resolved.environment_id(),
+        false,
+        mask_secrets,
+        true,
+        options.as_of.clone(),
+        options.tag.clone(),
+    )?;
+    let var_names = key_rules.transform_all(details.iter().map(|d| &d.key))?;
+
+    // filters are case-insensitive, and applied to the variable name
+    let filter = |search: &Option<String>, check: fn(&str, &str) -> bool, name: &str| -> bool {
+        match search {
+            Some(s) => check(&name.to_lowercase(), &s.to_lowercase()),
+            None => true,
+        }
+    };
+    let mut items: Vec<(String, String)> = details
+        .into_iter()
+        .filter(|d| !d.val_id.is_empty())
+        .map(|d| (var_names.get(&d.key).unwrap().clone(), d.value))
+        .filter(|(name, _)| {
+            filter(&options.starts_with, |n, s| n.starts_with(s), name)
+                && filter(&options.ends_with, |n, s| n.ends_with(s), name)
+                && filter(&options.contains, |n, s| n.contains(s), name)
+        })
+        .collect();
+    items.sort();
+
+    Ok(options.format.format_body(&items, &options)?)
+}
+
+/// Gets a Kubernetes resource name from the project name, since the name is limited to lowercase
+/// alphanumerics, '-', and '.'.
+fn k8s_resource_name(proj_name: &str) -> String {
+    let name: String = proj_name
+        .to_lowercase()
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() || c == '.' {
+            true => c,
+            false => '-',
+        })
+        .collect();
+    name.trim_matches(|c| c == '-' || c == '.').to_string()
+}
+
 fn proc_param_export(
     subcmd_args: &ArgMatches,
     rest_cfg: &OpenApiConfig,
@@ -409,8 +1126,39 @@ fn proc_param_export(
     let tag = parse_tag(subcmd_args.value_of(AS_OF_ARG));
     let export = subcmd_args.is_present("export");
     let show_secrets = subcmd_args.is_present(SECRETS_FLAG);
+    let format = ParamExportFormat::from_str(template_format).unwrap();
+    if matches!(format, ParamExportFormat::K8sSecret) && !show_secrets {
+        error_message(format!(
+            "The '{}' format needs the secret values -- use the --secrets flag",
+            template_format
+        ));
+        process::exit(63);
+    }
+    let k8s_name = match subcmd_args.value_of("k8s-name") {
+        Some(name) => name.to_string(),
+        None => k8s_resource_name(resolved.project_display_name()),
+    };
+    if k8s_name.is_empty()
+        && matches!(
+            format,
+            ParamExportFormat::K8sSecret | ParamExportFormat::K8sConfigMap
+        )
+    {
+        error_message(format!(
+            "Cannot get a Kubernetes resource name from project '{}' -- use the --k8s-name option",
+            resolved.project_display_name()
+        ));
+        process::exit(73);
+    }
+    let k8s_labels: Vec<(String, String)> = match subcmd_args.values_of("label") {
+        Some(labels) => labels
+            .map(|l| l.split_once('=').unwrap())
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect(),
+        None => vec![],
+    };
     let options = ParamExportOptions {
-        format: ParamExportFormat::from_str(template_format).unwrap(),
+        format,
         starts_with: starts_with.map(|s| s.to_string()),
         ends_with: ends_with.map(|s| s.to_string()),
         contains: contains.map(|s| s.to_string()),
@@ -418,8 +1166,19 @@ fn proc_param_export(
         secrets: Some(show_secrets),
         as_of,
         tag,
+        nest_separator: subcmd_args.value_of("nest").map(String::from),
+        k8s_name: Some(k8s_name),
+        k8s_namespace: subcmd_args.value_of("namespace").map(String::from),
+        k8s_labels,
+    };
+    let key_rules = KeyRules::from_args(subcmd_args, &Config::global().key_rules);
+    let body = if key_rules.is_empty() && !options.format.is_client_side() {
+        parameters.export_parameters(rest_cfg, proj_id, env_id, options)?
+    } else {
+        Some(export_with_key_rules(
+            rest_cfg, parameters, resolved, &key_rules, options,
+        )?)
     };
-    let body = parameters.export_parameters(rest_cfg, proj_id, env_id, options)?;
 
     if let Some(body) = body {
         println!("{}", body)
@@ -523,6 +1282,28 @@ fn proc_param_get(
     Ok(())
 }
 
+/// Gets the column header for the property, which matches the headers of the fixed views.
+fn param_property_header(property: &str) -> &str {
+    match property {
+        "name" => "Name",
+        "value" => "Value",
+        "type" => "Param Type",
+        "environment" => "Source",
+        "fqn" => "FQN",
+        "jmes-path" => "JMES",
+        "description" => "Description",
+        "secret" => "Secret",
+        "created-at" => "Created At",
+        "modified-at" => "Modified At",
+        "rule-count" => "Rules",
+        "raw" => "Raw",
+        "scope" => "Type",
+        "project-url" => "Project URL",
+        "project-name" => "Project",
+        _ => property,
+    }
+}
+
 fn proc_param_list(
     subcmd_args: &ArgMatches,
     rest_cfg: &OpenApiConfig,
@@ -544,7 +1325,18 @@ fn proc_param_list(
     let show_parents = subcmd_args.is_present("parents");
     let show_children = subcmd_args.is_present("children");
     let fmt = subcmd_args.value_of(FORMAT_OPT).unwrap();
-    let include_values = (show_values && !show_rules) || show_external || show_evaluated; // don't get values if not needed
+    let columns: Option<Vec<&str>> = subcmd_args.values_of("columns").map(|v| v.collect());
+    let sort = subcmd_args.value_of("sort").map(PropertySort::from);
+    let mut filters: Vec<PropertyFilter> = vec![];
+    for expr in subcmd_args.values_of("filter").unwrap_or_default() {
+        filters.push(PropertyFilter::from_str(expr)?);
+    }
+    let show_values = show_values || columns.is_some();
+    let include_values = (show_values && !show_rules)
+        || show_external
+        || show_evaluated
+        || !filters.is_empty()
+        || sort.is_some(); // don't get values if not needed
     let mut details = parameters.get_parameter_details(
         rest_cfg,
         proj_id,
@@ -596,7 +1388,21 @@ fn proc_param_list(
         }
     }
 
-    let view_flags = vec![
+    details.retain(|d| {
+        filters
+            .iter()
+            .all(|f| f.is_match(&d.get_property(&f.property)))
+    });
+    if let Some(sort) = &sort {
+        details.sort_by(|l, r| {
+            sort.compare(
+                &l.get_property(&sort.property),
+                &r.get_property(&sort.property),
+            )
+        });
+    }
+
+    let view_flags = [
         show_rules,
         show_external,
         show_evaluated,
@@ -650,7 +1456,10 @@ fn proc_param_list(
         let mut properties: Vec<&str>;
 
         // setup the table headers and properties
-        if show_external {
+        if let Some(columns) = &columns {
+            hdr = columns.iter().map(|c| param_property_header(c)).collect();
+            properties = columns.clone();
+        } else if show_external {
             hdr = vec!["Name", "FQN", "JMES"];
             properties = vec!["name", "fqn", "jmes-path"];
         } else if show_evaluated {
@@ -748,6 +1557,116 @@ fn delete_rule_type(
     Ok(())
 }
 
+/// Exit code when a value does not pass the local validation.
+const VALIDATE_EXIT_CODE: i32 = 62;
+
+/// Checks the value against the rules and the parameter type chain locally, and gets all the
+/// violations.
+fn value_violations(
+    rest_cfg: &OpenApiConfig,
+    value: &str,
+    rules: &[ParameterRuleDetail],
+    type_name: &str,
+) -> Result<Vec<String>> {
+    // rules that cannot be checked locally are left to the server
+    let check = |rule: &ParameterRuleDetail, source: String| -> Option<String> {
+        match rule.violation(value) {
+            Ok(violation) => violation.map(|msg| format!("{}: {}", source, msg)),
+            Err(msg) => {
+                warning_message(format!("The {} was not checked locally. {}", source, msg));
+                None
+            }
+        }
+    };
+    let mut result: Vec<String> = rules
+        .iter()
+        .filter_map(|r| check(r, format!("parameter {} rule", r.rule_type)))
+        .collect();
+
+    let types = Types::new().get_type_details(rest_cfg)?;
+    let mut type_name = type_name.to_string();
+    // the count guards against a loop in the type chain
+    for _ in 0..=types.len() {
+        if let Some(details) = types.iter().find(|t| t.name == type_name) {
+            for rule in &details.rules {
+                let source = format!("type '{}' {} rule", type_name, rule.rule_type);
+                if let Some(msg) = check(rule, source) {
+                    result.push(msg);
+                }
+            }
+            if !details.parent_name.is_empty() {
+                type_name = details.parent_name.clone();
+                continue;
+            }
+        }
+        if let Some(msg) = base_type_violation(&type_name, value) {
+            result.push(format!("type '{}': {}", type_name, msg));
+        }
+        break;
+    }
+    Ok(result)
+}
+
+/// Prints the violations (if any), and returns `true` when the value is valid.
+fn print_violations(key_name: &str, violations: &[String]) -> bool {
+    if violations.is_empty() {
+        println!("Value for parameter '{}' is valid.", key_name);
+        return true;
+    }
+    error_message(format!(
+        "Value for parameter '{}' has {} violation(s):\n  {}",
+        key_name,
+        violations.len(),
+        violations.join("\n  ")
+    ));
+    false
+}
+
+/// Prints the validation result, and exits when there are any violations.
+fn report_violations(key_name: &str, violations: &[String]) {
+    if !print_violations(key_name, violations) {
+        process::exit(VALIDATE_EXIT_CODE);
+    }
+}
+
+fn proc_param_validate(
+    subcmd_args: &ArgMatches,
+    rest_cfg: &OpenApiConfig,
+    parameters: &Parameters,
+    resolved: &ResolvedDetails,
+) -> Result<()> {
+    let key_name = subcmd_args.value_of(KEY_ARG).unwrap();
+    let value = subcmd_args.value_of("VALUE").unwrap();
+    let evaluate = false;
+    let immediate_parameters = false;
+    let mask_secrets = true;
+    let details = parameters.get_details_by_name(
+        rest_cfg,
+        resolved.project_id(),
+        resolved.environment_id(),
+        key_name,
+        evaluate,
+        immediate_parameters,
+        mask_secrets,
+        None,
+        None,
+    )?;
+    let details = match details {
+        Some(details) => details,
+        None => {
+            error_message(format!(
+                "Did not find parameter '{}' in project '{}'",
+                key_name,
+                resolved.project_display_name()
+            ));
+            process::exit(70);
+        }
+    };
+    let violations = value_violations(rest_cfg, value, &details.rules, &details.param_type)?;
+    report_violations(key_name, &violations);
+    Ok(())
+}
+
 fn proc_param_set(
     subcmd_args: &ArgMatches,
     rest_cfg: &OpenApiConfig,
@@ -823,6 +1742,65 @@ fn proc_param_set(
         value = Some(val_str.as_str());
     }
 
+    if subcmd_args.is_present("check") {
+        let value = match value {
+            Some(value) => value,
+            None => {
+                error_message("The --check option needs a value to check".to_string());
+                process::exit(VALIDATE_EXIT_CODE);
+            }
+        };
+        if evaluated == Some(true) {
+            warning_message("Evaluated values cannot be checked locally".to_string());
+            return Ok(());
+        }
+        let original = parameters.get_details_by_name(
+            rest_cfg,
+            proj_id,
+            env_id,
+            key_name,
+            evaluate,
+            immediate_parameters,
+            mask_secrets,
+            None,
+            None,
+        )?;
+        // the rules are checked as they would be after the update
+        let mut rules: Vec<ParameterRuleDetail> = vec![];
+        let mut type_name = "string".to_string();
+        if let Some(original) = original {
+            rules = original.rules;
+            type_name = original.param_type;
+        }
+        if let Some(param_type) = param_type {
+            type_name = param_type.to_string();
+        }
+        let rule_changes = [
+            (ParamRuleType::Max, max_rule, delete_max),
+            (ParamRuleType::Min, min_rule, delete_min),
+            (ParamRuleType::MaxLen, max_len_rule, delete_max_len),
+            (ParamRuleType::MinLen, min_len_rule, delete_min_len),
+            (ParamRuleType::Regex, regex_rule, delete_regex),
+        ];
+        for (rule_type, constraint, delete) in rule_changes {
+            if delete || constraint.is_some() {
+                rules.retain(|r| r.rule_type != rule_type);
+            }
+            if let Some(constraint) = constraint {
+                rules.push(ParameterRuleDetail {
+                    id: "".to_string(),
+                    rule_type,
+                    constraint: constraint.to_string(),
+                    created_at: "".to_string(),
+                    modified_at: "".to_string(),
+                });
+            }
+        }
+        let violations = value_violations(rest_cfg, value, &rules, &type_name)?;
+        report_violations(key_name, &violations);
+        return Ok(());
+    }
+
     let param_field_update =
         description.is_some() || secret.is_some() || param_type.is_some() || rename.is_some();
     let value_field_update =
@@ -930,106 +1908,766 @@ fn proc_param_set(
         )?;
     }
 
-    let param_id = updated.id.as_str();
-    let mut rule_errors: Vec<ParameterError> = Vec::new();
+    let param_id = updated.id.as_str();
+    let mut rule_errors: Vec<ParameterError> = Vec::new();
+
+    struct RuleDeletion(ParamRuleType, bool);
+    let rule_deletions: Vec<RuleDeletion> = vec![
+        RuleDeletion(ParamRuleType::Max, delete_max),
+        RuleDeletion(ParamRuleType::Min, delete_min),
+        RuleDeletion(ParamRuleType::MaxLen, delete_max_len),
+        RuleDeletion(ParamRuleType::MinLen, delete_min_len),
+        RuleDeletion(ParamRuleType::Regex, delete_regex),
+    ];
+
+    for del in rule_deletions {
+        if del.1 {
+            if let Err(e) = delete_rule_type(parameters, rest_cfg, &updated, proj_id, del.0) {
+                rule_errors.push(e);
+            }
+        }
+    }
+
+    // no need to add entries if we've already failed
+    if !rule_errors.is_empty() {
+        // make sure we don't leave stragglers around
+        if param_added {
+            // remove the parameter if added
+            let _ = parameters.delete_parameter_by_id(rest_cfg, proj_id, param_id);
+        }
+        for e in rule_errors {
+            error_message(e.to_string());
+        }
+        process::exit(11);
+    }
+
+    struct RuleDefinition<'a>(ParamRuleType, Option<&'a str>, bool);
+    let rule_defs: Vec<RuleDefinition> = vec![
+        RuleDefinition(ParamRuleType::Max, max_rule, !delete_max),
+        RuleDefinition(ParamRuleType::Min, min_rule, !delete_min),
+        RuleDefinition(ParamRuleType::MaxLen, max_len_rule, !delete_max_len),
+        RuleDefinition(ParamRuleType::MinLen, min_len_rule, !delete_min_len),
+        RuleDefinition(ParamRuleType::Regex, regex_rule, !delete_regex),
+    ];
+
+    for def in rule_defs {
+        if let Some(constraint) = def.1 {
+            if let Err(e) = set_rule_type(
+                parameters, rest_cfg, &updated, proj_id, def.2, def.0, constraint,
+            ) {
+                rule_errors.push(e);
+            }
+        }
+    }
+    if !rule_errors.is_empty() {
+        // make sure we don't leave stragglers around
+        if param_added {
+            // remove the parameter if added
+            let _ = parameters.delete_parameter_by_id(rest_cfg, proj_id, param_id);
+        }
+        for e in rule_errors {
+            error_message(e.to_string());
+        }
+        process::exit(12);
+    }
+
+    // don't do anything if there's nothing to do
+    if value_field_update {
+        env_changed = format!(" for environment '{}'", resolved.environment_display_name());
+        let is_secret = updated.secret;
+        // if any existing environment does not match the desired environment
+        if !updated.env_url.contains(env_id) {
+            set_action = "Set";
+            let value_add_result = parameters.create_parameter_value(
+                rest_cfg, proj_id, env_id, param_id, is_secret, value, fqn, jmes_path, evaluated,
+            );
+            if let Err(err) = value_add_result {
+                if param_added {
+                    let _ = parameters.delete_parameter_by_id(rest_cfg, proj_id, param_id);
+                }
+                return Err(Report::new(err));
+            }
+        } else {
+            parameters.update_parameter_value(
+                rest_cfg,
+                proj_id,
+                param_id,
+                &updated.val_id,
+                is_secret,
+                value,
+                fqn,
+                jmes_path,
+                evaluated,
+            )?;
+        }
+    }
+    println!(
+        "{} parameter '{}' in project '{}'{}.",
+        set_action,
+        final_name,
+        resolved.project_display_name(),
+        env_changed,
+    );
+    Ok(())
+}
+
+/// Makes the parameter rules match the desired rules.
+fn apply_rules(
+    parameters: &Parameters,
+    rest_cfg: &OpenApiConfig,
+    details: &ParameterDetails,
+    proj_id: &str,
+    rules: &[(ParamRuleType, String)],
+) -> Result<(), ParameterError> {
+    for rule in &details.rules {
+        if !rules
+            .iter()
+            .any(|(rule_type, _)| *rule_type == rule.rule_type)
+        {
+            delete_rule_type(parameters, rest_cfg, details, proj_id, rule.rule_type)?;
+        }
+    }
+    for (rule_type, constraint) in rules {
+        set_rule_type(
+            parameters, rest_cfg, details, proj_id, true, *rule_type, constraint,
+        )?;
+    }
+    Ok(())
+}
+
+fn proc_param_apply(
+    subcmd_args: &ArgMatches,
+    rest_cfg: &OpenApiConfig,
+    parameters: &Parameters,
+    resolved: &ResolvedDetails,
+) -> Result<()> {
+    let filename = subcmd_args.value_of("manifest").unwrap();
+    let confirmed = subcmd_args.is_present(CONFIRM_FLAG);
+    let prune = subcmd_args.is_present("prune");
+    let fmt = subcmd_args.value_of(FORMAT_OPT).unwrap();
+    let proj_id = resolved.project_id();
+    let proj_name = resolved.project_display_name();
+    let env_id = resolved.environment_id();
+    let immediate_parameters = false;
+    let mask_secrets = false; // need the secret values to compare
+    let content = fs::read_to_string(filename).expect(FILE_READ_ERR);
+    let extension = Path::new(filename).extension().and_then(|ext| ext.to_str());
+    let json = matches!(extension, Some(ext) if ext.eq_ignore_ascii_case("json"));
+    let manifest = ParameterManifest::parse(&content, json)?;
+
+    let current = parameters.get_parameter_detail_map(
+        rest_cfg,
+        proj_id,
+        env_id,
+        immediate_parameters,
+        mask_secrets,
+        None,
+        None,
+    )?;
+    let environments = Environments::new();
+    let mut manifest_envs: Vec<ManifestEnvironment> = vec![];
+    for env_name in manifest.environment_names() {
+        let id = match environments.get_id(rest_cfg, &env_name)? {
+            Some(id) => id,
+            None => return Err(EnvironmentError::NotFound(env_name).into()),
+        };
+        let env_params = if id == env_id {
+            current.clone()
+        } else {
+            parameters.get_parameter_detail_map(
+                rest_cfg,
+                proj_id,
+                &id,
+                immediate_parameters,
+                mask_secrets,
+                None,
+                None,
+            )?
+        };
+        manifest_envs.push(ManifestEnvironment {
+            id,
+            name: env_name,
+            parameters: env_params,
+        });
+    }
+
+    let mut plan = manifest.plan(proj_id, &current, &manifest_envs, prune)?;
+    plan.retain(|item| item.action != PlanAction::NoChange);
+    if plan.is_empty() {
+        println!("No changes needed for project '{}'.", proj_name);
+        return Ok(());
+    }
+
+    // NOTE: only the property names are shown, so no secret values are displayed
+    let hdr = vec!["Name", "Environment", "Action", "Changes"];
+    let props = vec!["name", "environment", "action", "changes"];
+    let mut table = Table::new("parameter-plan");
+    table.set_header(&hdr);
+    for item in &plan {
+        table.add_row(item.get_properties(&props));
+    }
+    table.render(fmt)?;
+
+    if !confirmed
+        && !user_confirm(
+            format!("Apply {} change(s) to project '{}'", plan.len(), proj_name),
+            Some(false),
+        )
+    {
+        return Ok(());
+    }
+
+    // the parameter identifiers, including the newly created parameters
+    let mut param_ids: HashMap<String, String> = HashMap::new();
+    for item in plan.iter().filter(|i| !i.param_id.is_empty()) {
+        param_ids.insert(item.name.clone(), item.param_id.clone());
+    }
+    for item in &plan {
+        if item.action == PlanAction::Delete {
+            parameters.delete_parameter_by_id(rest_cfg, proj_id, &item.param_id)?;
+            println!("Deleted parameter '{}'.", item.name);
+            continue;
+        }
+
+        let param = manifest
+            .parameters
+            .iter()
+            .find(|p| p.name == item.name)
+            .unwrap();
+        if item.env_name.is_empty() {
+            let details = if item.action == PlanAction::Create {
+                let details = parameters.create_parameter(
+                    rest_cfg,
+                    proj_id,
+                    &param.name,
+                    param.description.as_deref(),
+                    param.secret,
+                    param.param_type.as_deref(),
+                )?;
+                param_ids.insert(param.name.clone(), details.id.clone());
+                details
+            } else {
+                parameters.update_parameter(
+                    rest_cfg,
+                    proj_id,
+                    &item.param_id,
+                    &param.name,
+                    param.description.as_deref(),
+                    param.secret,
+                    param.param_type.as_deref(),
+                )?
+            };
+            if let Some(rules) = param.rules() {
+                apply_rules(parameters, rest_cfg, &details, proj_id, &rules)?;
+            }
+            let action = if item.action == PlanAction::Create {
+                "Created"
+            } else {
+                "Updated"
+            };
+            println!("{} parameter '{}'.", action, item.name);
+            continue;
+        }
+
+        let param_id = param_ids.get(&item.name).unwrap();
+        let value = param.values.get(&item.env_name).unwrap();
+        let is_secret = param
+            .secret
+            .or_else(|| current.get(&item.name).map(|d| d.secret))
+            .unwrap_or(false);
+        if item.action == PlanAction::Create {
+            parameters.create_parameter_value(
+                rest_cfg,
+                proj_id,
+                &item.env_id,
+                param_id,
+                is_secret,
+                value.value.as_deref(),
+                value.fqn.as_deref(),
+                value.jmes_path.as_deref(),
+                Some(value.evaluate),
+            )?;
+        } else {
+            parameters.update_parameter_value(
+                rest_cfg,
+                proj_id,
+                param_id,
+                &item.val_id,
+                is_secret,
+                value.value.as_deref(),
+                value.fqn.as_deref(),
+                value.jmes_path.as_deref(),
+                Some(value.evaluate),
+            )?;
+        }
+        println!(
+            "Set parameter '{}' for environment '{}'.",
+            item.name, item.env_name
+        );
+    }
+    println!(
+        "Applied {} change(s) to project '{}'.",
+        plan.len(),
+        proj_name
+    );
+    Ok(())
+}
+
+/// A value (or external reference) that is set in an environment, which is compared and written
+/// without evaluation, so references round-trip.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ValueSetting {
+    value: Option<String>,
+    fqn: Option<String>,
+    jmes_path: Option<String>,
+    evaluated: bool,
+}
+
+impl ValueSetting {
+    /// Gets the setting when the value is set in the environment (instead of inherited).
+    fn from_details(details: &ParameterDetails, env_id: &str) -> Option<Self> {
+        if details.val_id.is_empty() || !details.env_url.contains(env_id) {
+            None
+        } else {
+            Some(Self::from_value(details))
+        }
+    }
+
+    /// Gets the setting from the value, using the unevaluated value for internal parameters.
+    fn from_value(details: &ParameterDetails) -> Self {
+        if details.external {
+            Self {
+                value: None,
+                fqn: Some(details.fqn.clone()),
+                jmes_path: Some(details.jmes_path.clone()).filter(|j| !j.is_empty()),
+                evaluated: false,
+            }
+        } else {
+            Self {
+                value: Some(details.raw_value.clone()),
+                fqn: None,
+                jmes_path: None,
+                evaluated: details.evaluated,
+            }
+        }
+    }
+
+    fn display(setting: &Option<Self>, secret: bool, show_secrets: bool) -> String {
+        match setting {
+            None => "".to_string(),
+            Some(s) if s.fqn.is_some() => {
+                let mut result = format!("fqn: {}", s.fqn.as_deref().unwrap());
+                if let Some(jmes_path) = &s.jmes_path {
+                    result.push_str(&format!(", jmes-path: {}", jmes_path));
+                }
+                result
+            }
+            Some(_) if secret && !show_secrets => REDACTED.to_string(),
+            Some(s) => s.value.clone().unwrap_or_default(),
+        }
+    }
+}
+
+/// Gets the environment URL, followed by the URLs of its ancestors.
+fn get_env_lineage(env_name: &str, environments: &[EnvironmentDetails]) -> Vec<String> {
+    let mut result = vec![];
+    let mut name = env_name;
+    while let Some(details) = environments.iter().find(|e| e.name == name) {
+        result.push(details.url.clone());
+        name = &details.parent_name;
+    }
+    result
+}
+
+/// Gets the value that is used in the environment, which is the first value set in the lineage.
+fn lineage_value<'a>(
+    values: &'a ParameterDetailMap,
+    lineage: &[String],
+) -> Option<&'a ParameterDetails> {
+    lineage.iter().find_map(|url| values.get(url))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum PromoteAction {
+    Create,
+    Update,
+    Remove,
+}
+
+impl PromoteAction {
+    fn as_str(&self) -> &'static str {
+        match self {
+            PromoteAction::Create => "create",
+            PromoteAction::Update => "update",
+            PromoteAction::Remove => "remove",
+        }
+    }
+}
+
+/// A change to the destination environment for a promoted parameter.
+struct PromoteItem {
+    name: String,
+    param_id: String,
+    secret: bool,
+    action: PromoteAction,
+    dest_val_id: String,
+    current: Option<ValueSetting>,
+    promoted: ValueSetting,
+}
+
+fn proc_param_promote(
+    subcmd_args: &ArgMatches,
+    rest_cfg: &OpenApiConfig,
+    parameters: &Parameters,
+    resolved: &ResolvedDetails,
+) -> Result<()> {
+    let from_name = subcmd_args.value_of("from").unwrap();
+    let to_name = subcmd_args.value_of("to").unwrap();
+    let keys: Vec<&str> = subcmd_args.values_of("keys").unwrap_or_default().collect();
+    let starts_with = subcmd_args.value_of("starts_with");
+    let skip_secrets = subcmd_args.is_present("skip-secrets");
+    let skip_external = subcmd_args.is_present("skip-external");
+    let remove_inherited = subcmd_args.is_present("remove-inherited");
+    let confirmed = subcmd_args.is_present(CONFIRM_FLAG);
+    let show_secrets = subcmd_args.is_present(SECRETS_FLAG);
+    let fmt = subcmd_args.value_of(FORMAT_OPT).unwrap();
+    let proj_id = resolved.project_id();
+    let proj_name = resolved.project_display_name();
+
+    if from_name == to_name {
+        warning_message("Invalid promoting an environment to itself".to_string());
+        return Ok(());
+    }
+
+    let environments = Environments::new();
+    let env_details = environments.get_environment_details(rest_cfg)?;
+    let env_url_map = environments.details_to_map(&env_details);
+    let from_id = environments.id_from_map(from_name, &env_url_map)?;
+    let to_id = environments.id_from_map(to_name, &env_url_map)?;
+    let from_lineage = get_env_lineage(from_name, &env_details);
+    let to_lineage = get_env_lineage(to_name, &env_details);
+    if to_lineage.is_empty() {
+        return Err(EnvironmentError::NotFound(to_name.to_string()).into());
+    }
+
+    // get the list of parameters to promote, without any values
+    let mut param_list: Vec<ParameterDetails> = parameters
+        .get_parameter_details(rest_cfg, proj_id, &from_id, true, true, false, None, None)?
+        .into_iter()
+        .filter(|d| keys.is_empty() || keys.contains(&d.key.as_str()))
+        .filter(|d| match starts_with {
+            Some(prefix) => d.key.starts_with(prefix),
+            None => true,
+        })
+        .collect();
+    for key in &keys {
+        if !param_list.iter().any(|d| d.key == *key) {
+            error_message(format!(
+                "Did not find parameter '{}' in project '{}'.",
+                key, proj_name
+            ));
+            process::exit(56);
+        }
+    }
+    param_list.retain(|d| {
+        let skipped = skip_secrets && d.secret;
+        if skipped && keys.contains(&d.key.as_str()) {
+            warning_message(format!(
+                "Skipping parameter '{}', since it is a secret.",
+                d.key
+            ));
+        }
+        !skipped
+    });
+    param_list.sort_by_key(|d| d.key.to_lowercase());
+
+    let mut changes: Vec<PromoteItem> = vec![];
+    for param in &param_list {
+        let values = parameters
+            .get_parameter_environment_map(rest_cfg, proj_id, &param.key, true, false, None)?;
+        let source = match lineage_value(&values, &from_lineage) {
+            Some(details) => ValueSetting::from_value(details),
+            None => continue,
+        };
+        if skip_external && source.fqn.is_some() {
+            continue;
+        }
+        let current = lineage_value(&values, &to_lineage).map(ValueSetting::from_value);
+        let inherited = lineage_value(&values, &to_lineage[1..]).map(ValueSetting::from_value);
+        let dest_override = values.get(&to_lineage[0]);
+        let dest_setting = dest_override.map(ValueSetting::from_value);
+
+        // when the override is removed, the destination inherits the promoted value
+        let action =
+            if remove_inherited && dest_setting.is_some() && inherited == Some(source.clone()) {
+                PromoteAction::Remove
+            } else if current == Some(source.clone()) {
+                continue;
+            } else if dest_setting.is_some() {
+                PromoteAction::Update
+            } else {
+                PromoteAction::Create
+            };
+        changes.push(PromoteItem {
+            name: param.key.clone(),
+            param_id: param.id.clone(),
+            secret: param.secret,
+            action,
+            dest_val_id: dest_override.map(|d| d.val_id.clone()).unwrap_or_default(),
+            current,
+            promoted: source,
+        });
+    }
+
+    if changes.is_empty() {
+        println!(
+            "No parameters to promote from environment '{}' to '{}' in project '{}'.",
+            from_name, to_name, proj_name
+        );
+        return Ok(());
+    }
 
-    struct RuleDeletion(ParamRuleType, bool);
-    let rule_deletions: Vec<RuleDeletion> = vec![
-        RuleDeletion(ParamRuleType::Max, delete_max),
-        RuleDeletion(ParamRuleType::Min, delete_min),
-        RuleDeletion(ParamRuleType::MaxLen, delete_max_len),
-        RuleDeletion(ParamRuleType::MinLen, delete_min_len),
-        RuleDeletion(ParamRuleType::Regex, delete_regex),
-    ];
+    let mut table = Table::new("parameter-promote");
+    table.set_header(&["Name", "Action", to_name, from_name]);
+    for item in &changes {
+        table.add_row(vec![
+            item.name.clone(),
+            item.action.as_str().to_string(),
+            ValueSetting::display(&item.current, item.secret, show_secrets),
+            ValueSetting::display(&Some(item.promoted.clone()), item.secret, show_secrets),
+        ]);
+    }
+    table.render(fmt)?;
 
-    for del in rule_deletions {
-        if del.1 {
-            if let Err(e) = delete_rule_type(parameters, rest_cfg, &updated, proj_id, del.0) {
-                rule_errors.push(e);
+    if !confirmed
+        && !user_confirm(
+            format!(
+                "Promote {} change(s) from environment '{}' to '{}'",
+                changes.len(),
+                from_name,
+                to_name
+            ),
+            Some(false),
+        )
+    {
+        return Ok(());
+    }
+
+    // tag the source first, so the promoted values can be found later
+    let tag_name = match subcmd_args.value_of(TAG_NAME_OPT) {
+        Some(name) => name.to_string(),
+        None => format!("promote-{}-{}", to_name, Utc::now().format("%Y%m%d-%H%M%S")),
+    };
+    let description = format!("Promoted to environment '{}'", to_name);
+    environments.create_env_tag(rest_cfg, &from_id, &tag_name, Some(&description), None)?;
+    println!("Created tag '{}' in environment '{}'.", tag_name, from_name);
+
+    for item in &changes {
+        let setting = &item.promoted;
+        match item.action {
+            PromoteAction::Remove => {
+                parameters.delete_parameter_value(rest_cfg, proj_id, &to_id, &item.name)?;
+                println!(
+                    "Removed override for parameter '{}' in environment '{}'.",
+                    item.name, to_name
+                );
+                continue;
+            }
+            PromoteAction::Update => {
+                parameters.update_parameter_value(
+                    rest_cfg,
+                    proj_id,
+                    &item.param_id,
+                    &item.dest_val_id,
+                    item.secret,
+                    setting.value.as_deref(),
+                    setting.fqn.as_deref(),
+                    setting.jmes_path.as_deref(),
+                    Some(setting.evaluated),
+                )?;
+            }
+            PromoteAction::Create => {
+                parameters.create_parameter_value(
+                    rest_cfg,
+                    proj_id,
+                    &to_id,
+                    &item.param_id,
+                    item.secret,
+                    setting.value.as_deref(),
+                    setting.fqn.as_deref(),
+                    setting.jmes_path.as_deref(),
+                    Some(setting.evaluated),
+                )?;
             }
         }
+        println!(
+            "Promoted parameter '{}' to environment '{}'.",
+            item.name, to_name
+        );
     }
+    println!(
+        "Promoted {} change(s) from environment '{}' to '{}'.",
+        changes.len(),
+        from_name,
+        to_name
+    );
+    Ok(())
+}
 
-    // no need to add entries if we've already failed
-    if !rule_errors.is_empty() {
-        // make sure we don't leave stragglers around
-        if param_added {
-            // remove the parameter if added
-            let _ = parameters.delete_parameter_by_id(rest_cfg, proj_id, param_id);
+fn proc_param_rollback(
+    subcmd_args: &ArgMatches,
+    rest_cfg: &OpenApiConfig,
+    parameters: &Parameters,
+    resolved: &ResolvedDetails,
+) -> Result<()> {
+    let key_name = subcmd_args.value_of(KEY_ARG);
+    let to = subcmd_args.value_of("to").unwrap();
+    let as_of = parse_datetime(Some(to));
+    let tag = parse_tag(Some(to));
+    let confirmed = subcmd_args.is_present(CONFIRM_FLAG);
+    let show_secrets = subcmd_args.is_present(SECRETS_FLAG);
+    let fmt = subcmd_args.value_of(FORMAT_OPT).unwrap();
+    let proj_id = resolved.project_id();
+    let proj_name = resolved.project_display_name();
+    let env_id = resolved.environment_id();
+    let env_name = resolved.environment_display_name();
+    let evaluate = false; // keep the references, instead of the resolved values
+    let mask_secrets = false; // need the secret values to write them back
+
+    let names: Vec<String> = match key_name {
+        Some(name) => vec![name.to_string()],
+        None => parameters
+            .get_parameter_details(rest_cfg, proj_id, env_id, true, true, false, None, None)?
+            .into_iter()
+            .map(|d| d.key)
+            .collect(),
+    };
+
+    // (current details, current setting, rollback setting)
+    let mut changes: Vec<(ParameterDetails, Option<ValueSetting>, Option<ValueSetting>)> = vec![];
+    for name in &names {
+        let current = parameters.get_details_by_name(
+            rest_cfg,
+            proj_id,
+            env_id,
+            name,
+            evaluate,
+            false,
+            mask_secrets,
+            None,
+            None,
+        )?;
+        let current = match current {
+            Some(details) => details,
+            None => {
+                error_message(format!(
+                    "Did not find parameter '{}' in project '{}'.",
+                    name, proj_name
+                ));
+                process::exit(65);
+            }
+        };
+        if !current.project_url.contains(proj_id) {
+            error_message(format!(
+                "Parameter '{}' must be rolled back from project '{}' -- it is not part of project '{}'",
+                name, current.project_name, proj_name
+            ));
+            process::exit(66);
         }
-        for e in rule_errors {
-            error_message(e.to_string());
+        let target = parameters.get_details_by_name(
+            rest_cfg,
+            proj_id,
+            env_id,
+            name,
+            evaluate,
+            false,
+            mask_secrets,
+            as_of.clone(),
+            tag.clone(),
+        )?;
+        let curr_setting = ValueSetting::from_details(&current, env_id);
+        let target_setting = target.and_then(|d| ValueSetting::from_details(&d, env_id));
+        if curr_setting != target_setting {
+            changes.push((current, curr_setting, target_setting));
         }
-        process::exit(11);
     }
 
-    struct RuleDefinition<'a>(ParamRuleType, Option<&'a str>, bool);
-    let rule_defs: Vec<RuleDefinition> = vec![
-        RuleDefinition(ParamRuleType::Max, max_rule, !delete_max),
-        RuleDefinition(ParamRuleType::Min, min_rule, !delete_min),
-        RuleDefinition(ParamRuleType::MaxLen, max_len_rule, !delete_max_len),
-        RuleDefinition(ParamRuleType::MinLen, min_len_rule, !delete_min_len),
-        RuleDefinition(ParamRuleType::Regex, regex_rule, !delete_regex),
-    ];
+    if changes.is_empty() {
+        println!(
+            "No parameter values to roll back in project '{}' for environment '{}' to '{}'.",
+            proj_name, env_name, to
+        );
+        return Ok(());
+    }
 
-    for def in rule_defs {
-        if let Some(constraint) = def.1 {
-            if let Err(e) = set_rule_type(
-                parameters, rest_cfg, &updated, proj_id, def.2, def.0, constraint,
-            ) {
-                rule_errors.push(e);
-            }
-        }
+    let mut table = Table::new("parameter-rollback");
+    table.set_header(&["Name", "Action", "Current", "Rollback"]);
+    for (details, curr, target) in &changes {
+        let action = match (curr, target) {
+            (None, _) => "set",
+            (_, None) => "unset",
+            _ => "update",
+        };
+        table.add_row(vec![
+            details.key.clone(),
+            action.to_string(),
+            ValueSetting::display(curr, details.secret, show_secrets),
+            ValueSetting::display(target, details.secret, show_secrets),
+        ]);
     }
-    if !rule_errors.is_empty() {
-        // make sure we don't leave stragglers around
-        if param_added {
-            // remove the parameter if added
-            let _ = parameters.delete_parameter_by_id(rest_cfg, proj_id, param_id);
-        }
-        for e in rule_errors {
-            error_message(e.to_string());
-        }
-        process::exit(12);
+    table.render(fmt)?;
+
+    if !confirmed
+        && !user_confirm(
+            format!(
+                "Roll back {} parameter value(s) in environment '{}'",
+                changes.len(),
+                env_name
+            ),
+            Some(false),
+        )
+    {
+        return Ok(());
     }
 
-    // don't do anything if there's nothing to do
-    if value_field_update {
-        env_changed = format!(" for environment '{}'", resolved.environment_display_name());
-        let is_secret = updated.secret;
-        // if any existing environment does not match the desired environment
-        if !updated.env_url.contains(env_id) {
-            set_action = "Set";
-            let value_add_result = parameters.create_parameter_value(
-                rest_cfg, proj_id, env_id, param_id, is_secret, value, fqn, jmes_path, evaluated,
-            );
-            if let Err(err) = value_add_result {
-                if param_added {
-                    let _ = parameters.delete_parameter_by_id(rest_cfg, proj_id, param_id);
+    for (details, curr, target) in &changes {
+        match target {
+            None => {
+                parameters.delete_parameter_value(rest_cfg, proj_id, env_id, &details.key)?;
+            }
+            Some(setting) => {
+                if curr.is_some() {
+                    parameters.update_parameter_value(
+                        rest_cfg,
+                        proj_id,
+                        &details.id,
+                        &details.val_id,
+                        details.secret,
+                        setting.value.as_deref(),
+                        setting.fqn.as_deref(),
+                        setting.jmes_path.as_deref(),
+                        Some(setting.evaluated),
+                    )?;
+                } else {
+                    parameters.create_parameter_value(
+                        rest_cfg,
+                        proj_id,
+                        env_id,
+                        &details.id,
+                        details.secret,
+                        setting.value.as_deref(),
+                        setting.fqn.as_deref(),
+                        setting.jmes_path.as_deref(),
+                        Some(setting.evaluated),
+                    )?;
                 }
-                return Err(Report::new(err));
             }
-        } else {
-            parameters.update_parameter_value(
-                rest_cfg,
-                proj_id,
-                param_id,
-                &updated.val_id,
-                is_secret,
-                value,
-                fqn,
-                jmes_path,
-                evaluated,
-            )?;
         }
+        println!(
+            "Rolled back parameter '{}' in project '{}' for environment '{}'.",
+            details.key, proj_name, env_name
+        );
     }
-    println!(
-        "{} parameter '{}' in project '{}'{}.",
-        set_action,
-        final_name,
-        resolved.project_display_name(),
-        env_changed,
-    );
     Ok(())
 }
 
@@ -1131,6 +2769,203 @@ fn proc_param_push(
     Ok(())
 }
 
+/// Gets the time just before the history entry, to find the value before the change.
+fn history_time_before(date: &str) -> String {
+    match DateTime::parse_from_rfc3339(date) {
+        Ok(datetime) => (datetime.with_timezone(&Utc) - Duration::microseconds(1))
+            .format(ISO8601)
+            .to_string(),
+        Err(_) => date.to_string(),
+    }
+}
+
+/// The values set in each environment (not inherited) at a time, by (parameter ID, environment ID).
+type HistoryValues = HashMap<(String, String), String>;
+
+/// Gets the values at the time, using the cache since many history entries are at the same time
+/// (e.g. from an import). Without a `param_name`, the values for the whole project are fetched.
+fn history_values<'a>(
+    parameters: &Parameters,
+    rest_cfg: &OpenApiConfig,
+    proj_id: &str,
+    param_name: Option<&str>,
+    as_of: String,
+    show_secrets: bool,
+    cache: &'a mut HashMap<String, HistoryValues>,
+) -> Result<&'a HistoryValues, ParameterError> {
+    if !cache.contains_key(&as_of) {
+        let mut values = HistoryValues::new();
+        for details in parameters.get_all_environment_values(
+            rest_cfg,
+            proj_id,
+            param_name,
+            false,
+            !show_secrets,
+            true,
+            Some(as_of.clone()),
+        )? {
+            let value = match details.secret && !show_secrets {
+                true => REDACTED.to_string(),
+                false => details.value.clone(),
+            };
+            let env_id = last_from_url(&details.env_url).to_string();
+            values.insert((details.id, env_id), value);
+        }
+        cache.insert(as_of.clone(), values);
+    }
+    Ok(cache.get(&as_of).unwrap())
+}
+
+/// Gets the value set in the environment, or an empty string when not set.
+fn history_env_value(values: &HistoryValues, param_id: &str, env_id: &str) -> String {
+    values
+        .get(&(param_id.to_string(), env_id.to_string()))
+        .cloned()
+        .unwrap_or_default()
+}
+
+fn proc_param_history(
+    subcmd_args: &ArgMatches,
+    rest_cfg: &OpenApiConfig,
+    parameters: &Parameters,
+    resolved: &ResolvedDetails,
+) -> Result<()> {
+    let proj_name = resolved.project_display_name();
+    let proj_id = resolved.project_id();
+    let as_of = parse_datetime(subcmd_args.value_of(AS_OF_ARG));
+    let tag = parse_tag(subcmd_args.value_of(AS_OF_ARG));
+    let param_name = subcmd_args.value_of(KEY_ARG);
+    let show_secrets = subcmd_args.is_present(SECRETS_FLAG);
+    let fmt = subcmd_args.value_of(FORMAT_OPT).unwrap();
+    let modifier;
+    let add_name;
+    let history: Vec<ParameterHistory>;
+
+    if let Some(param_name) = param_name {
+        let details = parameters.get_details_by_name(
+            rest_cfg,
+            proj_id,
+            "",
+            param_name,
+            false,
+            false,
+            true,
+            as_of.clone(),
+            tag.clone(),
+        )?;
+        let param_id = match details {
+            Some(details) => details.id,
+            None => {
+                error_message(format!(
+                    "Did not find parameter '{}' in project '{}'.",
+                    param_name, proj_name
+                ));
+                process::exit(67);
+            }
+        };
+        modifier = format!("for '{}' ", param_name);
+        add_name = false;
+        history = parameters.get_history_for(rest_cfg, proj_id, &param_id, as_of, tag)?;
+    } else {
+        modifier = "".to_string();
+        add_name = true;
+        history = parameters.get_histories(rest_cfg, proj_id, as_of, tag)?;
+    }
+
+    if history.is_empty() {
+        println!(
+            "No parameter history {}in project '{}'.",
+            modifier, proj_name
+        );
+    } else {
+        let name_index = 3;
+        let mut table = Table::new("parameter-history");
+        let mut hdr: Vec<&str> = vec![
+            "Date",
+            "User",
+            "Action",
+            "Environment",
+            "Old Value",
+            "New Value",
+        ];
+        if add_name {
+            hdr.insert(name_index, "Name");
+        }
+        table.set_header(&hdr);
+
+        // the old value is the new value from the previous change (in time order) to the same
+        // parameter and environment, so the values are only fetched before the first change
+        let mut cache: HashMap<String, HistoryValues> = HashMap::new();
+        let mut latest: HashMap<(String, String), String> = HashMap::new();
+        let mut changes: HashMap<(usize, String), (String, String)> = HashMap::new();
+        let mut order: Vec<usize> = (0..history.len()).collect();
+        order.sort_by(|l, r| history[*l].date.cmp(&history[*r].date));
+        for index in order {
+            let entry = &history[index];
+            if entry.model != "value" {
+                continue;
+            }
+            for (env_id, _) in &entry.environments {
+                let key = (entry.id.clone(), env_id.clone());
+                let old_value = match latest.get(&key) {
+                    Some(value) => value.clone(),
+                    None => {
+                        let before = history_time_before(&entry.date);
+                        let values = history_values(
+                            parameters,
+                            rest_cfg,
+                            proj_id,
+                            param_name,
+                            before,
+                            show_secrets,
+                            &mut cache,
+                        )?;
+                        history_env_value(values, &entry.id, env_id)
+                    }
+                };
+                let values = history_values(
+                    parameters,
+                    rest_cfg,
+                    proj_id,
+                    param_name,
+                    entry.date.clone(),
+                    show_secrets,
+                    &mut cache,
+                )?;
+                let new_value = history_env_value(values, &entry.id, env_id);
+                latest.insert(key, new_value.clone());
+                changes.insert((index, env_id.clone()), (old_value, new_value));
+            }
+        }
+
+        for (index, entry) in history.iter().enumerate() {
+            let action = format!("{} {}", entry.change_type, entry.model);
+            let mut environments = entry.environments.clone();
+            if environments.is_empty() {
+                environments.push(("".to_string(), "".to_string()));
+            }
+            for (env_id, env_name) in environments {
+                let (old_value, new_value) =
+                    changes.get(&(index, env_id)).cloned().unwrap_or_default();
+                let mut row = vec![
+                    entry.date.clone(),
+                    entry.user_name.clone(),
+                    action.clone(),
+                    env_name,
+                    old_value,
+                    new_value,
+                ];
+                if add_name {
+                    row.insert(name_index, entry.name.clone());
+                }
+                table.add_row(row);
+            }
+        }
+        table.render(fmt)?;
+    }
+    Ok(())
+}
+
 #[derive(Debug)]
 pub struct DriftDetails {
     pub name: String,
@@ -1157,6 +2992,9 @@ impl DriftDetails {
     }
 }
 
+// exit code when drift is found, and the caller asked for it
+const DRIFT_EXIT_CODE: i32 = 59;
+
 fn maybe_redact(curr_value: &str, is_shown: bool) -> String {
     if is_shown {
         curr_value.to_string()
@@ -1188,28 +3026,27 @@ fn proc_param_drift(
         as_of,
         tag,
     )?;
-    let excludes = vec![
-        "PATH",
-        "HOME",
-        "TERM",
-        "PWD",
-        "OLDPWD",
-        "PS1",
-        "USER",
-        "_",
-        // skip all the CLOUDTRUTH_ environment variables for the CLI execution
-        "CLOUDTRUTH_API_KEY",
-        "CLOUDTRUTH_PROFILE",
-        "CLOUDTRUTH_PROJECT",
-        "CLOUDTRUTH_ENVIRONMENT",
-        "CLOUDTRUTH_SERVER_URL",
-        "CLOUDTRUTH_REQUEST_TIMEOUT",
-        "CLOUDTRUTH_REST_DEBUG",
-        "CLOUDTRUTH_REST_SUCCESS",
-        "CLOUDTRUTH_REST_PAGE_SIZE",
-    ];
-    let env_vars: EnvSettings = env::vars()
-        .filter(|(ref k, _)| !excludes.contains(&k.as_str()))
+    let mut excludes: Vec<String> = match subcmd_args.is_present("no-default-excludes") {
+        true => vec![],
+        false => DEFAULT_EXCLUDES.iter().map(|e| e.to_string()).collect(),
+    };
+    excludes.extend(
+        subcmd_args
+            .values_of("exclude")
+            .unwrap_or_default()
+            .map(String::from),
+    );
+    let source = if let Some(filename) = subcmd_args.value_of("file") {
+        DriftSource::File(PathBuf::from(filename))
+    } else if let Some(pid) = subcmd_args.value_of("pid") {
+        DriftSource::Process(pid.parse::<u32>()?)
+    } else {
+        DriftSource::Shell
+    };
+    let env_vars: EnvSettings = source
+        .load()?
+        .into_iter()
+        .filter(|(ref k, _)| !is_excluded(k, &excludes))
         .collect();
 
     let mut deltas: Vec<DriftDetails> = vec![];
@@ -1254,7 +3091,8 @@ fn proc_param_drift(
 
     deltas.sort_by_key(|d| d.name.to_lowercase());
 
-    if deltas.is_empty() {
+    let deltas_empty = deltas.is_empty();
+    if deltas_empty {
         println!("No drift found.");
     } else if !show_values {
         let list = deltas
@@ -1263,7 +3101,7 @@ fn proc_param_drift(
             .collect::<Vec<String>>();
         println!("{}", list.join("\n"));
     } else {
-        let hdr = vec!["Name", "Difference", "CloudTruth", "Shell"];
+        let hdr = vec!["Name", "Difference", "CloudTruth", source.label()];
         let props = vec!["name", "action", "server", "current"];
         let mut table = Table::new("parameter-drift");
         table.set_header(&hdr);
@@ -1272,6 +3110,89 @@ fn proc_param_drift(
         }
         table.render(fmt)?;
     }
+    if !deltas_empty && subcmd_args.is_present("exit-code") {
+        process::exit(DRIFT_EXIT_CODE);
+    }
+    Ok(())
+}
+
+/// Process the 'parameters search' sub-command, which looks in all the projects instead of the
+/// resolved project.
+pub fn process_parameters_search(
+    subcmd_args: &ArgMatches,
+    rest_cfg: &OpenApiConfig,
+    default_env: &str,
+) -> Result<()> {
+    let pattern = subcmd_args.value_of("PATTERN").unwrap();
+    let search_values = subcmd_args.is_present("values");
+    let show_secrets = subcmd_args.is_present(SECRETS_FLAG);
+    let param_type = subcmd_args.value_of("param-type");
+    let fmt = subcmd_args.value_of(FORMAT_OPT).unwrap();
+    let env_names: Vec<&str> = match subcmd_args.values_of("environment") {
+        Some(values) => values.collect(),
+        None => vec![default_env],
+    };
+    let matcher = match subcmd_args.is_present("regex") {
+        true => Regex::new(pattern)?,
+        false => Regex::new(&format!("(?i){}", regex::escape(pattern)))?,
+    };
+
+    let environments = Environments::new();
+    let env_url_map = environments.get_url_name_map(rest_cfg);
+    let mut env_ids: Vec<(&str, String)> = vec![];
+    for env_name in env_names {
+        env_ids.push((env_name, environments.id_from_map(env_name, &env_url_map)?));
+    }
+
+    let projects = Projects::new();
+    let parameters = Parameters::new();
+    let mut rows: Vec<Vec<String>> = vec![];
+    for project in projects.get_project_details(rest_cfg)? {
+        for (env_name, env_id) in &env_ids {
+            let details = parameters.get_parameter_details(
+                rest_cfg,
+                &project.id,
+                env_id,
+                true,
+                !show_secrets,
+                true,
+                None,
+                None,
+            )?;
+            for entry in details {
+                if matches!(param_type, Some(t) if t != entry.param_type) {
+                    continue;
+                }
+                let name_match = matcher.is_match(&entry.key);
+                let value_match = search_values
+                    && (show_secrets || !entry.secret)
+                    && matcher.is_match(&entry.value);
+                if name_match || value_match {
+                    rows.push(vec![
+                        project.name.clone(),
+                        entry.key,
+                        env_name.to_string(),
+                        entry.param_type,
+                        entry.value,
+                    ]);
+                }
+            }
+        }
+    }
+
+    if rows.is_empty() {
+        println!("No parameters found matching '{}'.", pattern);
+        return Ok(());
+    }
+
+    // keep the environments in the order given for each parameter
+    rows.sort_by_key(|r| (r[0].to_lowercase(), r[1].to_lowercase()));
+    let mut table = Table::new("parameter-search");
+    table.set_header(&["Project", "Parameter", "Environment", "Type", "Value"]);
+    for row in rows {
+        table.add_row(row);
+    }
+    table.render(fmt)?;
     Ok(())
 }
 
@@ -1284,6 +3205,22 @@ pub fn process_parameters_command(
     let parameters = Parameters::new();
     if let Some(subcmd_args) = subcmd_args.subcommand_matches(LIST_SUBCMD) {
         proc_param_list(subcmd_args, rest_cfg, &parameters, resolved)?;
+    } else if let Some(subcmd_args) = subcmd_args.subcommand_matches(HISTORY_SUBCMD) {
+        proc_param_history(subcmd_args, rest_cfg, &parameters, resolved)?;
+    } else if let Some(subcmd_args) = subcmd_args.subcommand_matches("promote") {
+        proc_param_promote(subcmd_args, rest_cfg, &parameters, resolved)?;
+    } else if let Some(subcmd_args) = subcmd_args.subcommand_matches("rename") {
+        proc_param_rename(subcmd_args, rest_cfg, &parameters, resolved)?;
+    } else if let Some(subcmd_args) = subcmd_args.subcommand_matches("references") {
+        proc_param_references(subcmd_args, rest_cfg, &parameters, resolved)?;
+    } else if let Some(subcmd_args) = subcmd_args.subcommand_matches(EDIT_SUBCMD) {
+        proc_param_edit(subcmd_args, rest_cfg, &parameters, resolved)?;
+    } else if let Some(subcmd_args) = subcmd_args.subcommand_matches("validate") {
+        proc_param_validate(subcmd_args, rest_cfg, &parameters, resolved)?;
+    } else if let Some(subcmd_args) = subcmd_args.subcommand_matches("rollback") {
+        proc_param_rollback(subcmd_args, rest_cfg, &parameters, resolved)?;
+    } else if let Some(subcmd_args) = subcmd_args.subcommand_matches("apply") {
+        proc_param_apply(subcmd_args, rest_cfg, &parameters, resolved)?;
     } else if let Some(subcmd_args) = subcmd_args.subcommand_matches(GET_SUBCMD) {
         proc_param_get(subcmd_args, rest_cfg, &parameters, resolved)?;
     } else if let Some(subcmd_args) = subcmd_args.subcommand_matches(SET_SUBCMD) {
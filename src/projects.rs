@@ -2,7 +2,11 @@ use crate::cli::{
     show_values, CONFIRM_FLAG, DELETE_SUBCMD, DESCRIPTION_OPT, FORMAT_OPT, LIST_SUBCMD, NAME_ARG,
     PARENT_ARG, RENAME_OPT, SET_SUBCMD, SHOW_TIMES_FLAG, TREE_SUBCMD,
 };
-use crate::database::{OpenApiConfig, ProjectDetails, Projects};
+use crate::config::DEFAULT_ENV_NAME;
+use crate::database::{
+    EnvironmentDetails, Environments, OpenApiConfig, ParameterDetails, Parameters, ProjectDetails,
+    Projects, TemplateDetails, Templates,
+};
 use crate::lib::{
     error_message, user_confirm, warn_missing_subcommand, warning_message, DEL_CONFIRM,
 };
@@ -11,6 +15,217 @@ use clap::ArgMatches;
 use color_eyre::eyre::Result;
 use std::process;
 
+fn proc_proj_copy(
+    subcmd_args: &ArgMatches,
+    rest_cfg: &OpenApiConfig,
+    projects: &Projects,
+) -> Result<()> {
+    let src_name = subcmd_args.value_of("source").unwrap();
+    let dest_name = subcmd_args.value_of("destination").unwrap();
+    let description = subcmd_args.value_of(DESCRIPTION_OPT);
+    let copy_templates = subcmd_args.is_present("templates");
+    let dry_run = subcmd_args.is_present("dry-run");
+
+    let src = match projects.get_details_by_name(rest_cfg, src_name, false)? {
+        Some(details) => details,
+        None => {
+            error_message(format!("Project '{}' does not exist", src_name));
+            process::exit(57);
+        }
+    };
+    if projects.get_id(rest_cfg, dest_name)?.is_some() {
+        error_message(format!("Project '{}' already exists", dest_name));
+        process::exit(58);
+    }
+
+    let environments = Environments::new();
+    let env_details = environments.get_environment_details(rest_cfg)?;
+    let env_url_map = environments.details_to_map(&env_details);
+    let default_id = environments.id_from_map(DEFAULT_ENV_NAME, &env_url_map)?;
+    let parameters = Parameters::new();
+    let param_list = parameters.get_parameter_details(
+        rest_cfg,
+        &src.id,
+        &default_id,
+        true,
+        true,
+        false,
+        None,
+        None,
+    )?;
+    let templates = Templates::new();
+    let template_list = match copy_templates {
+        true => templates.get_template_details(rest_cfg, &src.id)?,
+        false => vec![],
+    };
+
+    if dry_run {
+        for param in &param_list {
+            println!(
+                "Would copy parameter '{}' with {} rule(s)",
+                param.key,
+                param.rules.len()
+            );
+        }
+        for template in &template_list {
+            println!("Would copy template '{}'", template.name);
+        }
+        println!(
+            "Would copy {} parameter(s) and {} template(s) from project '{}' to '{}'",
+            param_list.len(),
+            template_list.len(),
+            src_name,
+            dest_name
+        );
+        return Ok(());
+    }
+
+    let description = description.or(Some(src.description.as_str()));
+    let parent_url = Some(src.parent_url.as_str()).filter(|u| !u.is_empty());
+    let dest_id = projects
+        .create_project(rest_cfg, dest_name, description, parent_url)?
+        .unwrap_or_default();
+    println!("Created project '{}'", dest_name);
+
+    let copied = copy_project_contents(
+        rest_cfg,
+        &src.id,
+        &dest_id,
+        &param_list,
+        &template_list,
+        &env_details,
+    );
+    if let Err(err) = copied {
+        // remove the partial copy, so the copy can be retried
+        match projects.delete_project(rest_cfg, &dest_id) {
+            Ok(_) => warning_message(format!(
+                "Deleted project '{}', since the copy failed",
+                dest_name
+            )),
+            Err(del_err) => warning_message(format!(
+                "Project '{}' is only partially copied, and could not be deleted: {}",
+                dest_name, del_err
+            )),
+        }
+        return Err(err);
+    }
+
+    println!(
+        "Copied {} parameter(s) and {} template(s) from project '{}' to '{}'",
+        param_list.len(),
+        template_list.len(),
+        src_name,
+        dest_name
+    );
+    Ok(())
+}
+
+/// Creates the parameters (with their rules and values) and templates in the destination project.
+///
+/// The parameters are created before any values, and the static values before the templates, so
+/// the templates and evaluated values can reference them.
+fn copy_project_contents(
+    rest_cfg: &OpenApiConfig,
+    src_id: &str,
+    dest_id: &str,
+    param_list: &[ParameterDetails],
+    template_list: &[TemplateDetails],
+    env_details: &[EnvironmentDetails],
+) -> Result<()> {
+    let parameters = Parameters::new();
+    let mut param_values: Vec<(String, Vec<ParameterDetails>)> = vec![];
+    for param in param_list {
+        let values = parameters
+            .get_parameter_environment_map(rest_cfg, src_id, &param.key, true, false, None)?;
+        let created = parameters.create_parameter(
+            rest_cfg,
+            dest_id,
+            &param.key,
+            Some(param.description.as_str()).filter(|d| !d.is_empty()),
+            Some(param.secret),
+            Some(param.param_type.as_str()).filter(|t| !t.is_empty()),
+        )?;
+        for rule in &param.rules {
+            parameters.create_parameter_rule(
+                rest_cfg,
+                dest_id,
+                &created.id,
+                rule.rule_type,
+                &rule.constraint,
+            )?;
+        }
+        println!(
+            "Copied parameter '{}' with {} rule(s) and {} value(s)",
+            param.key,
+            param.rules.len(),
+            values.len()
+        );
+        param_values.push((created.id, values.into_values().collect()));
+    }
+
+    copy_values(rest_cfg, dest_id, &param_values, env_details, false)?;
+
+    let templates = Templates::new();
+    for template in template_list {
+        templates.create_template(
+            rest_cfg,
+            dest_id,
+            &template.name,
+            &template.body,
+            Some(template.description.as_str()).filter(|d| !d.is_empty()),
+        )?;
+        println!("Copied template '{}'", template.name);
+    }
+
+    copy_values(rest_cfg, dest_id, &param_values, env_details, true)?;
+    Ok(())
+}
+
+/// Creates the static (or evaluated) values for the parameters in the destination project.
+fn copy_values(
+    rest_cfg: &OpenApiConfig,
+    dest_id: &str,
+    param_values: &[(String, Vec<ParameterDetails>)],
+    env_details: &[EnvironmentDetails],
+    evaluated: bool,
+) -> Result<()> {
+    let parameters = Parameters::new();
+    for (param_id, values) in param_values {
+        for details in values.iter().filter(|d| d.evaluated == evaluated) {
+            let env_id = match env_details.iter().find(|e| details.env_url.contains(&e.id)) {
+                Some(env) => &env.id,
+                None => continue,
+            };
+            if details.external {
+                parameters.create_parameter_value(
+                    rest_cfg,
+                    dest_id,
+                    env_id,
+                    param_id,
+                    details.secret,
+                    None,
+                    Some(&details.fqn),
+                    Some(details.jmes_path.as_str()).filter(|j| !j.is_empty()),
+                    Some(false),
+                )?;
+            } else {
+                parameters.create_parameter_value(
+                    rest_cfg,
+                    dest_id,
+                    env_id,
+                    param_id,
+                    details.secret,
+                    Some(&details.raw_value),
+                    None,
+                    None,
+                    Some(details.evaluated),
+                )?;
+            }
+        }
+    }
+    Ok(())
+}
+
 fn proc_proj_delete(
     subcmd_args: &ArgMatches,
     rest_cfg: &OpenApiConfig,
@@ -164,7 +379,9 @@ fn proc_proj_tree(
 /// Process the 'project' sub-command
 pub fn process_project_command(subcmd_args: &ArgMatches, rest_cfg: &OpenApiConfig) -> Result<()> {
     let projects = Projects::new();
-    if let Some(subcmd_args) = subcmd_args.subcommand_matches(DELETE_SUBCMD) {
+    if let Some(subcmd_args) = subcmd_args.subcommand_matches("copy") {
+        proc_proj_copy(subcmd_args, rest_cfg, &projects)?;
+    } else if let Some(subcmd_args) = subcmd_args.subcommand_matches(DELETE_SUBCMD) {
         proc_proj_delete(subcmd_args, rest_cfg, &projects)?;
     } else if let Some(subcmd_args) = subcmd_args.subcommand_matches(LIST_SUBCMD) {
         proc_proj_list(subcmd_args, rest_cfg, &projects)?;
@@ -0,0 +1,188 @@
+use crate::cli::FILTER_OPERATORS;
+use regex::Regex;
+use std::cmp::Ordering;
+use std::error;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+const DESCENDING_SUFFIX: &str = ":desc";
+
+#[derive(Debug)]
+pub enum PropertyFilterError {
+    InvalidExpression(String),
+    InvalidRegex(String, String),
+}
+
+impl Display for PropertyFilterError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            PropertyFilterError::InvalidExpression(expr) => {
+                write!(
+                    f,
+                    "Invalid filter '{}' -- use <property><operator><value> with one of '=', '~', '>', or '<'",
+                    expr
+                )
+            }
+            PropertyFilterError::InvalidRegex(expr, msg) => {
+                write!(
+                    f,
+                    "Invalid regular expression in filter '{}': {}",
+                    expr, msg
+                )
+            }
+        }
+    }
+}
+
+impl error::Error for PropertyFilterError {}
+
+/// Compares property values as numbers when both are numeric, and as text otherwise (which works
+/// for the ISO-8601 times).
+pub fn compare_values(left: &str, right: &str) -> Ordering {
+    match (left.parse::<f64>(), right.parse::<f64>()) {
+        (Ok(l), Ok(r)) => l.partial_cmp(&r).unwrap_or(Ordering::Equal),
+        _ => left.cmp(right),
+    }
+}
+
+#[derive(Debug)]
+enum FilterCheck {
+    Equal(String),
+    Matches(Regex),
+    Greater(String),
+    Less(String),
+}
+
+/// A `<property><operator><value>` expression, like `type=integer` or `name~^DB_`.
+#[derive(Debug)]
+pub struct PropertyFilter {
+    pub property: String,
+    check: FilterCheck,
+}
+
+impl FromStr for PropertyFilter {
+    type Err = PropertyFilterError;
+
+    fn from_str(expr: &str) -> Result<Self, Self::Err> {
+        let invalid = || PropertyFilterError::InvalidExpression(expr.to_string());
+        let index = expr.find(FILTER_OPERATORS).ok_or_else(invalid)?;
+        let property = expr[..index].trim();
+        if property.is_empty() {
+            return Err(invalid());
+        }
+        let value = &expr[index + 1..];
+        let check =
+            match &expr[index..index + 1] {
+                "=" => FilterCheck::Equal(value.to_string()),
+                "~" => FilterCheck::Matches(Regex::new(value).map_err(|e| {
+                    PropertyFilterError::InvalidRegex(expr.to_string(), e.to_string())
+                })?),
+                ">" => FilterCheck::Greater(value.to_string()),
+                _ => FilterCheck::Less(value.to_string()),
+            };
+        Ok(PropertyFilter {
+            property: property.to_string(),
+            check,
+        })
+    }
+}
+
+impl PropertyFilter {
+    /// Checks the value of the filter's property.
+    pub fn is_match(&self, actual: &str) -> bool {
+        match &self.check {
+            FilterCheck::Equal(value) => actual == value,
+            FilterCheck::Matches(re) => re.is_match(actual),
+            FilterCheck::Greater(value) => compare_values(actual, value) == Ordering::Greater,
+            FilterCheck::Less(value) => compare_values(actual, value) == Ordering::Less,
+        }
+    }
+}
+
+/// A `<property>[:desc]` sort order.
+#[derive(Debug, PartialEq, Eq)]
+pub struct PropertySort {
+    pub property: String,
+    pub descending: bool,
+}
+
+impl From<&str> for PropertySort {
+    fn from(spec: &str) -> Self {
+        match spec.strip_suffix(DESCENDING_SUFFIX) {
+            Some(property) => PropertySort {
+                property: property.to_string(),
+                descending: true,
+            },
+            None => PropertySort {
+                property: spec.strip_suffix(":asc").unwrap_or(spec).to_string(),
+                descending: false,
+            },
+        }
+    }
+}
+
+impl PropertySort {
+    pub fn compare(&self, left: &str, right: &str) -> Ordering {
+        let order = compare_values(left, right);
+        match self.descending {
+            true => order.reverse(),
+            false => order,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn filters() {
+        let filter = PropertyFilter::from_str("secret=true").unwrap();
+        assert_eq!(filter.property, "secret");
+        assert!(filter.is_match("true"));
+        assert!(!filter.is_match("false"));
+
+        let filter = PropertyFilter::from_str("name~^DB_").unwrap();
+        assert!(filter.is_match("DB_HOST"));
+        assert!(!filter.is_match("MY_DB_HOST"));
+
+        // the first operator splits the property and value
+        let filter = PropertyFilter::from_str("value=a=b").unwrap();
+        assert!(filter.is_match("a=b"));
+
+        let filter = PropertyFilter::from_str("modified-at>2026-01-01").unwrap();
+        assert!(filter.is_match("2026-01-05T10:00:00Z"));
+        assert!(!filter.is_match("2025-12-31T23:59:59Z"));
+
+        // numbers are not compared as text
+        let filter = PropertyFilter::from_str("rule-count<10").unwrap();
+        assert!(filter.is_match("9"));
+        assert!(!filter.is_match("10"));
+
+        assert!(matches!(
+            PropertyFilter::from_str("no-operator"),
+            Err(PropertyFilterError::InvalidExpression(_))
+        ));
+        assert!(matches!(
+            PropertyFilter::from_str("=value"),
+            Err(PropertyFilterError::InvalidExpression(_))
+        ));
+        assert!(matches!(
+            PropertyFilter::from_str("name~[a-"),
+            Err(PropertyFilterError::InvalidRegex(_, _))
+        ));
+    }
+
+    #[test]
+    fn sorts() {
+        let sort = PropertySort::from("modified-at:desc");
+        assert_eq!(sort.property, "modified-at");
+        assert!(sort.descending);
+        assert_eq!(sort.compare("a", "b"), Ordering::Greater);
+
+        let sort = PropertySort::from("rule-count");
+        assert!(!sort.descending);
+        assert_eq!(sort.compare("9", "10"), Ordering::Less);
+        assert_eq!(PropertySort::from("name:asc").property, "name");
+    }
+}
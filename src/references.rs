@@ -0,0 +1,83 @@
+use once_cell::sync::OnceCell;
+use regex::{Captures, Regex};
+
+// Parameters are referenced as `{{ name }}` or `{{ cloudtruth.parameters.name }}`, and may have
+// filters (e.g. `{{ name | upper }}`) or whitespace control (e.g. `{{- name -}}`).
+const REFERENCE_PATTERN: &str =
+    r"\{\{-?\s*(?:cloudtruth\.parameters\.)?([^\s{}|]+?)\s*(?:\|[^{}]*?)?-?\}\}";
+
+static REFERENCE_REGEX: OnceCell<Regex> = OnceCell::new();
+
+fn reference_regex() -> &'static Regex {
+    REFERENCE_REGEX.get_or_init(|| Regex::new(REFERENCE_PATTERN).unwrap())
+}
+
+/// Gets the expressions in the text (e.g. a template body or evaluated value) that reference the
+/// parameter.
+pub fn find_references<'a>(text: &'a str, param_name: &str) -> Vec<&'a str> {
+    reference_regex()
+        .captures_iter(text)
+        .filter(|c| &c[1] == param_name)
+        .map(|c| c.get(0).unwrap().as_str())
+        .collect()
+}
+
+/// Rewrites the references to the `old_name` parameter in the text to use the `new_name`, while
+/// keeping the rest of each expression (e.g. filters and spacing).
+pub fn replace_references(text: &str, old_name: &str, new_name: &str) -> String {
+    reference_regex()
+        .replace_all(text, |caps: &Captures| {
+            let whole = caps.get(0).unwrap();
+            let name = caps.get(1).unwrap();
+            if name.as_str() != old_name {
+                return whole.as_str().to_string();
+            }
+            format!(
+                "{}{}{}",
+                &text[whole.start()..name.start()],
+                new_name,
+                &text[name.end()..whole.end()]
+            )
+        })
+        .to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reference_expressions() {
+        let text = concat!(
+            "host={{ db_host }}\n",
+            "port={{db_port}}\n",
+            "upper={{ cloudtruth.parameters.db_host | upper }}\n",
+            "trimmed={{- db_host -}}\n",
+            "other={{ db_host_name }} {{ cloudtruth.environment }} {% if db_host %}\n",
+        );
+        assert_eq!(
+            find_references(text, "db_host"),
+            vec![
+                "{{ db_host }}",
+                "{{ cloudtruth.parameters.db_host | upper }}",
+                "{{- db_host -}}",
+            ]
+        );
+        assert_eq!(find_references(text, "db_port"), vec!["{{db_port}}"]);
+        assert!(find_references(text, "db").is_empty());
+        assert!(find_references("no references", "db_host").is_empty());
+    }
+
+    #[test]
+    fn replace_expressions() {
+        let text = "a={{ db_host }} b={{cloudtruth.parameters.db_host|lower}} c={{ db_host_name }}";
+        assert_eq!(
+            replace_references(text, "db_host", "database_host"),
+            concat!(
+                "a={{ database_host }} b={{cloudtruth.parameters.database_host|lower}} ",
+                "c={{ db_host_name }}"
+            )
+        );
+        assert_eq!(replace_references(text, "other", "new"), text);
+    }
+}
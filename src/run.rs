@@ -1,47 +1,746 @@
-use crate::cli::AS_OF_ARG;
-use crate::database::{OpenApiConfig, Parameters, ResolvedDetails};
+use crate::cli::{AS_OF_ARG, FORMAT_OPT, SECRETS_FLAG};
+use crate::config::{Config, KeyRules, CT_API_KEY, DEFAULT_ENV_NAME};
+use crate::database::{
+    CacheError, CachedValues, EnvironmentError, OpenApiConfig, ParameterCache, ParameterError,
+    ParameterValueMap, Parameters, ProjectError, ResolveError, ResolvedDetails, Resolver,
+    Templates,
+};
 use crate::lib::{
-    format_param_error, parse_datetime, parse_tag, warn_missing_subcommand, warn_unresolved_params,
-    warn_user,
+    error_message, format_param_error, parse_datetime, parse_tag, warn_missing_subcommand,
+    warn_unresolved_params, warn_user, warning_message, REDACTED,
+};
+use crate::subprocess::{
+    changed_keys, exit_code, expand_placeholders, merge_layers, ChangeAction, EnvSettings,
+    EnvSource, Inheritance, RestartPolicy, SubProcess, SubProcessError,
 };
-use crate::subprocess::{EnvSettings, Inheritance, SubProcess};
+use crate::table::Table;
 use clap::ArgMatches;
 use color_eyre::eyre::Result;
+use std::cmp;
+use std::collections::HashMap;
+use std::path::Path;
 use std::process;
 use std::str::FromStr;
+use std::time::{Duration, Instant};
+use subprocess::ExitStatus;
 
-/// Process the 'run' sub-command
-pub fn process_run_command(
-    subcmd_args: &ArgMatches,
+// longest delay between restarts, which is also how long a command must run to reset the delay
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+
+/// A project/environment (at an optional time or tag) that provides parameters for the run.
+struct RunLayer {
+    /// The project/environment names (or IDs) given by the user
+    proj_name: String,
+    env_name: String,
+    resolved: ResolvedDetails,
+    as_of: Option<String>,
+    tag: Option<String>,
+    /// The layer was resolved from the cache, so the values also come from the cache
+    cached: bool,
+}
+
+/// How the local parameter cache is used for the run.
+struct RunCache {
+    cache: Option<ParameterCache>,
+    only: bool,
+    max_age: Option<Duration>,
+}
+
+/// The CloudTruth parameter values for the run environment.
+#[derive(Clone)]
+struct RunValues {
+    vars: EnvSettings,
+    secrets: Vec<String>,
+    errors: Vec<String>,
+    /// The project/environment that provided each variable
+    origins: HashMap<String, String>,
+}
+
+impl RunLayer {
+    /// The tag (or time) of the values, if any.
+    fn as_of_tag(&self) -> Option<&str> {
+        self.tag.as_deref().or(self.as_of.as_deref())
+    }
+
+    /// Description of the layer, e.g. `project/environment@tag`.
+    fn description(&self) -> String {
+        let mut result = format!(
+            "{}/{}",
+            self.resolved.project_display_name(),
+            self.resolved.environment_display_name()
+        );
+        if let Some(as_of) = self.as_of_tag() {
+            result.push('@');
+            result.push_str(as_of);
+        }
+        result
+    }
+}
+
+impl RunCache {
+    /// The cache is used when enabled in the profile, or requested by the arguments (unless
+    /// explicitly disabled).
+    fn from_args(subcmd_args: &ArgMatches, config: &Config) -> Result<Self> {
+        let only = subcmd_args.is_present("cache-only");
+        let enabled = !subcmd_args.is_present("no-cache")
+            && (config.run_cache || only || subcmd_args.is_present("cache"));
+        let cache = match enabled {
+            true => Config::parameter_cache_dir()
+                .map(|dir| ParameterCache::new(&dir, &config.profile_name, &config.api_key)),
+            false => None,
+        };
+        let max_age = match subcmd_args
+            .value_of("cache-max-age")
+            .unwrap()
+            .parse::<u64>()?
+        {
+            0 => None,
+            secs => Some(Duration::from_secs(secs)),
+        };
+        Ok(Self {
+            cache,
+            only,
+            max_age,
+        })
+    }
+
+    fn load(
+        &self,
+        resolved: &ResolvedDetails,
+        as_of_tag: Option<&str>,
+    ) -> Result<CachedValues, CacheError> {
+        match &self.cache {
+            Some(cache) => cache.load(resolved, as_of_tag, self.max_age),
+            None => Err(CacheError::NoCacheDirectory),
+        }
+    }
+
+    /// Loads the cached values for the project/environment names given by the user, using the IDs
+    /// the names resolved to when the values were cached.
+    fn lookup(
+        &self,
+        proj_name: &str,
+        env_name: &str,
+        as_of_tag: Option<&str>,
+    ) -> Result<CachedValues, CacheError> {
+        match &self.cache {
+            Some(cache) => {
+                let resolved = cache.resolve(proj_name, env_name)?;
+                cache.load(&resolved, as_of_tag, self.max_age)
+            }
+            None => Err(CacheError::NoCacheDirectory),
+        }
+    }
+
+    /// Uses the cached values after the server could not be reached, and lets the user know.
+    fn fallback(
+        &self,
+        cached: Result<CachedValues, CacheError>,
+        err: &dyn std::error::Error,
+    ) -> Result<CachedValues, CacheError> {
+        let cached = match cached {
+            Ok(cached) => cached,
+            Err(cache_err) => {
+                warn_user(format!("The server could not be reached: {}", err));
+                return Err(cache_err);
+            }
+        };
+        warn_user(format!(
+            "Using cached parameters ({} seconds old), since the server could not be reached: {}",
+            cached.age.as_secs(),
+            err
+        ));
+        Ok(cached)
+    }
+
+    /// Stores the values, and warns (instead of failing) when they cannot be cached.
+    fn store(&self, layer: &RunLayer, values: &ParameterValueMap) {
+        if let Some(cache) = &self.cache {
+            let result = cache.store(
+                &layer.proj_name,
+                &layer.env_name,
+                &layer.resolved,
+                layer.as_of_tag(),
+                values,
+            );
+            if let Err(err) = result {
+                warn_user(format!("Failed to cache parameters: {}", err));
+            }
+        }
+    }
+}
+
+/// Resolution errors that did not come from a server response (e.g. connection failures).
+fn resolve_unreachable(err: &ResolveError) -> bool {
+    matches!(
+        err,
+        ResolveError::EnvironmentError(EnvironmentError::UnhandledError(_))
+            | ResolveError::ProjectError(ProjectError::UnhandledError(_))
+    )
+}
+
+/// Parameter errors that did not come from a server response (e.g. connection failures).
+fn parameter_unreachable(err: &ParameterError) -> bool {
+    matches!(err, ParameterError::UnhandledError(_))
+}
+
+/// Resolves the project/environment for a layer. The cache is used instead of the server when
+/// requested, or when the server cannot be reached.
+fn resolve_layer(
     rest_cfg: &OpenApiConfig,
-    resolved: &ResolvedDetails,
-) -> Result<()> {
-    let mut sub_proc = SubProcess::new();
-    let as_of = parse_datetime(subcmd_args.value_of(AS_OF_ARG));
-    let tag = parse_tag(subcmd_args.value_of(AS_OF_ARG));
-    let mut arguments: Vec<String>;
-    let command: String;
+    run_cache: &RunCache,
+    proj_name: &str,
+    env_name: &str,
+    as_of_tag: Option<&str>,
+) -> Result<RunLayer> {
+    let as_of = parse_datetime(as_of_tag);
+    let tag = parse_tag(as_of_tag);
+    let cache_key = tag.as_deref().or(as_of.as_deref());
+    let (resolved, cached) = if run_cache.only {
+        (
+            run_cache.lookup(proj_name, env_name, cache_key)?.resolved,
+            true,
+        )
+    } else {
+        match Resolver::new().resolve_ids(rest_cfg, proj_name, env_name) {
+            Ok(resolved) => (resolved, false),
+            Err(err) if run_cache.cache.is_some() && resolve_unreachable(&err) => {
+                let cached =
+                    run_cache.fallback(run_cache.lookup(proj_name, env_name, cache_key), &err)?;
+                (cached.resolved, true)
+            }
+            Err(err) => return Err(err.into()),
+        }
+    };
+    Ok(RunLayer {
+        proj_name: proj_name.to_string(),
+        env_name: env_name.to_string(),
+        resolved,
+        as_of,
+        tag,
+        cached,
+    })
+}
+
+/// Gets the parameter values for the layer from the server (or the cache), and caches the values
+/// from the server.
+fn get_layer_values(
+    rest_cfg: &OpenApiConfig,
+    layer: &RunLayer,
+    run_cache: &RunCache,
+) -> Result<ParameterValueMap> {
+    if layer.cached {
+        return Ok(run_cache.load(&layer.resolved, layer.as_of_tag())?.values);
+    }
 
     let parameters = Parameters::new();
-    let param_map = parameters.get_parameter_values(
+    let result = parameters.get_parameter_values(
         rest_cfg,
-        resolved.project_id(),
-        resolved.environment_id(),
+        layer.resolved.project_id(),
+        layer.resolved.environment_id(),
         false,
         false,
         true,
-        as_of,
-        tag,
-    )?;
+        layer.as_of.clone(),
+        layer.tag.clone(),
+    );
+    match result {
+        Ok(values) => {
+            run_cache.store(layer, &values);
+            Ok(values)
+        }
+        Err(err) if run_cache.cache.is_some() && parameter_unreachable(&err) => {
+            let cached =
+                run_cache.fallback(run_cache.load(&layer.resolved, layer.as_of_tag()), &err)?;
+            Ok(cached.values)
+        }
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Gets the CloudTruth parameter values (and any errors) for the run environment from the layer.
+/// The parameter names are transformed into variable names using the `key_rules`.
+fn get_cloudtruth_vars(
+    rest_cfg: &OpenApiConfig,
+    layer: &RunLayer,
+    key_rules: &KeyRules,
+    run_cache: &RunCache,
+) -> Result<RunValues> {
+    let param_map = get_layer_values(rest_cfg, layer, run_cache)?;
+    let var_names = key_rules.transform_all(param_map.keys())?;
     let mut ct_vars = EnvSettings::new();
+    let mut secrets: Vec<String> = vec![];
     let mut errors: Vec<String> = vec![];
-    for (k, v) in param_map {
+    for (name, v) in param_map {
+        let k = var_names.get(&name).unwrap_or(&name).clone();
         ct_vars.insert(k.clone(), v.value.clone());
+        if v.secret {
+            secrets.push(k.clone());
+        }
         if !v.error.is_empty() {
             errors.push(format_param_error(&k, &v.error))
         }
     }
-    sub_proc.set_cloudtruth_environment(ct_vars);
+    let origin = layer.description();
+    let origins = ct_vars
+        .keys()
+        .map(|k| (k.clone(), origin.clone()))
+        .collect();
+    Ok(RunValues {
+        vars: ct_vars,
+        secrets,
+        errors,
+        origins,
+    })
+}
+
+/// Gets the layers from the `project[:environment[@tag]]` specifications, with the current
+/// project/environment as the last layer (so it takes precedence).
+fn get_run_layers(
+    subcmd_args: &ArgMatches,
+    rest_cfg: &OpenApiConfig,
+    run_cache: &RunCache,
+    base: RunLayer,
+) -> Result<Vec<RunLayer>> {
+    let mut layers: Vec<RunLayer> = vec![];
+    for spec in subcmd_args.values_of_lossy("layer").unwrap_or_default() {
+        let (proj_env, as_of_tag) = match spec.split_once('@') {
+            Some((proj_env, as_of_tag)) => (proj_env, Some(as_of_tag)),
+            None => (spec.as_str(), None),
+        };
+        let (proj_name, env_name) = proj_env
+            .split_once(':')
+            .unwrap_or((proj_env, base.resolved.environment_display_name()));
+        layers.push(resolve_layer(
+            rest_cfg, run_cache, proj_name, env_name, as_of_tag,
+        )?);
+    }
+    layers.push(base);
+    Ok(layers)
+}
+
+/// Gets the CloudTruth parameter values from all the layers, and merges them together.
+fn get_layered_vars(
+    rest_cfg: &OpenApiConfig,
+    layers: &[RunLayer],
+    key_rules: &KeyRules,
+    inherit: Inheritance,
+    run_cache: &RunCache,
+) -> Result<RunValues> {
+    let mut maps: Vec<EnvSettings> = vec![];
+    let mut secrets: Vec<String> = vec![];
+    let mut errors: Vec<String> = vec![];
+    let mut origins: HashMap<String, String> = HashMap::new();
+    for layer in layers {
+        let mut values = get_cloudtruth_vars(rest_cfg, layer, key_rules, run_cache)?;
+        maps.push(values.vars);
+        secrets.append(&mut values.secrets);
+        errors.append(&mut values.errors);
+        // later layers take precedence, so they replace the origin
+        origins.extend(values.origins);
+    }
+    secrets.sort();
+    secrets.dedup();
+    Ok(RunValues {
+        vars: merge_layers(&maps, inherit)?,
+        secrets,
+        errors,
+        origins,
+    })
+}
+
+/// Prints the computed run environment, with the source of each variable, without running
+/// anything. Secret values are redacted, unless requested.
+fn print_run_environment(
+    sub_proc: &SubProcess,
+    subcmd_args: &ArgMatches,
+    ct_values: &RunValues,
+) -> Result<()> {
+    let fmt = subcmd_args.value_of(FORMAT_OPT).unwrap();
+    let show_secrets = subcmd_args.is_present(SECRETS_FLAG);
+    let mut table = Table::new("run-environment");
+    table.set_header(&["Name", "Value", "Source", "Decision"]);
+    for entry in sub_proc.env_provenance() {
+        let secret = entry.name == CT_API_KEY || ct_values.secrets.contains(&entry.name);
+        let value = if secret && !show_secrets {
+            REDACTED.to_string()
+        } else {
+            entry.value
+        };
+        let source = match (&entry.source, &entry.param_file) {
+            (EnvSource::CloudTruth, Some(param)) => format!(
+                "cloudtruth file {} ({})",
+                param,
+                ct_values.origins.get(param).cloned().unwrap_or_default()
+            ),
+            (EnvSource::CloudTruth, None) => format!(
+                "cloudtruth ({})",
+                ct_values
+                    .origins
+                    .get(&entry.name)
+                    .cloned()
+                    .unwrap_or_default()
+            ),
+            (source, _) => source.to_string(),
+        };
+        table.add_row(vec![
+            entry.name,
+            value,
+            source,
+            entry.decision.unwrap_or_default(),
+        ]);
+    }
+    table.render(fmt)?;
+    Ok(())
+}
+
+/// Sets up the environment for the sub-process using the CloudTruth values and CLI arguments.
+fn set_run_environment(
+    sub_proc: &mut SubProcess,
+    subcmd_args: &ArgMatches,
+    resolved: &ResolvedDetails,
+    ct_values: &RunValues,
+) -> Result<()> {
+    let inherit = Inheritance::from_str(subcmd_args.value_of("inheritance").unwrap()).unwrap();
+    let overrides = subcmd_args.values_of_lossy("set").unwrap_or_default();
+    let removals = subcmd_args.values_of_lossy("remove").unwrap_or_default();
+    let permissive = subcmd_args.is_present("permissive");
+    let strict = subcmd_args.is_present("strict");
+    let mut file_names = subcmd_args.values_of_lossy("file").unwrap_or_default();
+    if subcmd_args.is_present("secret-files") {
+        file_names.extend(ct_values.secrets.iter().cloned());
+    }
+    file_names.sort();
+    file_names.dedup();
+    let file_var = subcmd_args.value_of("file-var").unwrap();
+    sub_proc.set_cloudtruth_environment(ct_values.vars.clone());
+    sub_proc.set_cloudtruth_files(&file_names, file_var, strict)?;
+    sub_proc.set_environment(resolved, inherit, &overrides, &removals, strict)?;
+    if !permissive {
+        sub_proc.remove_ct_app_vars();
+    }
+    Ok(())
+}
+
+/// Expands the `{{ct:NAME}}` placeholders in the command and arguments with the CloudTruth values.
+fn expand_command(
+    subcmd_args: &ArgMatches,
+    command: &str,
+    arguments: &[String],
+    ct_values: &RunValues,
+) -> Result<(String, Vec<String>)> {
+    let strict = subcmd_args.is_present("strict");
+    let command = expand_placeholders(command, &ct_values.vars, strict)?;
+    let arguments = arguments
+        .iter()
+        .map(|arg| expand_placeholders(arg, &ct_values.vars, strict))
+        .collect::<Result<Vec<String>, SubProcessError>>()?;
+    Ok((command, arguments))
+}
+
+/// Renders the `--template` files using the values from the layer (without the other layers).
+fn render_templates(
+    sub_proc: &mut SubProcess,
+    subcmd_args: &ArgMatches,
+    rest_cfg: &OpenApiConfig,
+    layer: &RunLayer,
+) -> Result<()> {
+    let specs = subcmd_args.values_of_lossy("template").unwrap_or_default();
+    if specs.is_empty() {
+        return Ok(());
+    }
+    if layer.cached {
+        return Err(SubProcessError::TemplateFileError(
+            "templates cannot be rendered from cached parameters".to_string(),
+        )
+        .into());
+    }
+    let templates = Templates::new();
+    let resolved = &layer.resolved;
+    for spec in specs {
+        let (template_name, path) = spec.split_once('=').unwrap();
+        let details = templates.get_details_by_name(
+            rest_cfg,
+            resolved.project_display_name(),
+            resolved.project_id(),
+            template_name,
+            true,
+            true,
+            Some(resolved.environment_display_name().to_string()),
+            layer.as_of.clone(),
+            layer.tag.clone(),
+        )?;
+        sub_proc.write_template_file(Path::new(path), &details.body)?;
+    }
+    Ok(())
+}
+
+/// Updates the environment and template files after the parameter values change. The previous
+/// template files are kept when they cannot be rendered.
+fn refresh_run_environment(
+    sub_proc: &mut SubProcess,
+    subcmd_args: &ArgMatches,
+    rest_cfg: &OpenApiConfig,
+    layers: &[RunLayer],
+    ct_values: &RunValues,
+) -> Result<()> {
+    let base = layers.last().unwrap();
+    set_run_environment(sub_proc, subcmd_args, &base.resolved, ct_values)?;
+    if let Err(err) = render_templates(sub_proc, subcmd_args, rest_cfg, base) {
+        warn_user(format!("Failed to update template files: {}", err));
+    }
+    Ok(())
+}
+
+/// Runs the command in the background, and polls for parameter changes until the command exits.
+/// The exit status of the command is returned.
+///
+/// Once a change is seen, the values must be stable for the debounce period before the command
+/// is restarted or signalled.
+#[allow(clippy::too_many_arguments)]
+fn watch_command(
+    sub_proc: &mut SubProcess,
+    subcmd_args: &ArgMatches,
+    rest_cfg: &OpenApiConfig,
+    command: &str,
+    arguments: &[String],
+    mut ct_values: RunValues,
+    layers: &[RunLayer],
+    key_rules: &KeyRules,
+    run_cache: &RunCache,
+) -> Result<ExitStatus> {
+    let interval = Duration::from_secs(subcmd_args.value_of("interval").unwrap().parse()?);
+    let debounce = Duration::from_secs(subcmd_args.value_of("debounce").unwrap().parse()?);
+    let action = ChangeAction::from_str(subcmd_args.value_of("on-change").unwrap()).unwrap();
+    let inherit = Inheritance::from_str(subcmd_args.value_of("inheritance").unwrap()).unwrap();
+    let fetch = || get_layered_vars(rest_cfg, layers, key_rules, inherit, run_cache);
+
+    let (expanded, expanded_args) = expand_command(subcmd_args, command, arguments, &ct_values)?;
+    sub_proc.start_command(&expanded, &expanded_args)?;
+    loop {
+        if let Some(status) = sub_proc.wait_command(interval)? {
+            return Ok(status);
+        }
+        let mut latest = match fetch() {
+            Ok(result) => result,
+            Err(err) => {
+                warn_user(format!("Failed to check for parameter changes: {}", err));
+                continue;
+            }
+        };
+        if changed_keys(&ct_values.vars, &latest.vars).is_empty() {
+            continue;
+        }
+
+        // wait for the values to settle down before acting on them
+        loop {
+            if let Some(status) = sub_proc.wait_command(debounce)? {
+                return Ok(status);
+            }
+            match fetch() {
+                Ok(settled) => {
+                    if changed_keys(&latest.vars, &settled.vars).is_empty() {
+                        break;
+                    }
+                    latest = settled;
+                }
+                Err(err) => {
+                    warn_user(format!("Failed to check for parameter changes: {}", err));
+                    break;
+                }
+            }
+        }
+
+        // NOTE: only the names are printed, since the values may be secrets
+        let changed = changed_keys(&ct_values.vars, &latest.vars);
+        if changed.is_empty() {
+            continue;
+        }
+        warning_message(format!(
+            "Parameter changes detected ({}): {}",
+            action,
+            changed.join(", ")
+        ));
+        warn_unresolved_params(&latest.errors);
+        ct_values = latest;
+        if let Err(err) =
+            refresh_run_environment(sub_proc, subcmd_args, rest_cfg, layers, &ct_values)
+        {
+            sub_proc.stop_command()?;
+            return Err(err);
+        }
+        match action {
+            ChangeAction::Restart => {
+                sub_proc.stop_command()?;
+                let (expanded, expanded_args) =
+                    expand_command(subcmd_args, command, arguments, &ct_values)?;
+                sub_proc.start_command(&expanded, &expanded_args)?;
+            }
+            ChangeAction::Hangup | ChangeAction::Terminate => {
+                sub_proc.signal_command(action)?;
+            }
+        }
+    }
+}
+
+/// Runs the command until it exits, watching for parameter changes when requested.
+#[allow(clippy::too_many_arguments)]
+fn run_until_exit(
+    sub_proc: &mut SubProcess,
+    subcmd_args: &ArgMatches,
+    rest_cfg: &OpenApiConfig,
+    command: &str,
+    arguments: &[String],
+    ct_values: RunValues,
+    layers: &[RunLayer],
+    key_rules: &KeyRules,
+    run_cache: &RunCache,
+) -> Result<ExitStatus> {
+    if subcmd_args.is_present("watch") {
+        watch_command(
+            sub_proc,
+            subcmd_args,
+            rest_cfg,
+            command,
+            arguments,
+            ct_values,
+            layers,
+            key_rules,
+            run_cache,
+        )
+    } else {
+        let (expanded, expanded_args) =
+            expand_command(subcmd_args, command, arguments, &ct_values)?;
+        Ok(sub_proc.run_command(&expanded, &expanded_args)?)
+    }
+}
+
+/// Runs the command, and restarts it according to the restart policy. The exit status of the
+/// last run is returned.
+///
+/// The parameters are fetched again before each restart. The delay between restarts doubles
+/// while the command keeps failing quickly.
+#[allow(clippy::too_many_arguments)]
+fn supervise_command(
+    sub_proc: &mut SubProcess,
+    subcmd_args: &ArgMatches,
+    rest_cfg: &OpenApiConfig,
+    command: &str,
+    arguments: &[String],
+    mut ct_values: RunValues,
+    layers: &[RunLayer],
+    key_rules: &KeyRules,
+    run_cache: &RunCache,
+) -> Result<ExitStatus> {
+    let policy = RestartPolicy::from_str(subcmd_args.value_of("restart").unwrap()).unwrap();
+    let max_restarts: u64 = subcmd_args.value_of("max-restarts").unwrap().parse()?;
+    let first_delay = Duration::from_secs(subcmd_args.value_of("restart-delay").unwrap().parse()?);
+    let inherit = Inheritance::from_str(subcmd_args.value_of("inheritance").unwrap()).unwrap();
+    // orphans are reaped whatever the restart policy, since this may be PID 1 in a container
+    sub_proc.set_reap_orphans(true);
+
+    let mut delay = first_delay;
+    let mut restarts: u64 = 0;
+    loop {
+        let started = Instant::now();
+        let status = run_until_exit(
+            sub_proc,
+            subcmd_args,
+            rest_cfg,
+            command,
+            arguments,
+            ct_values.clone(),
+            layers,
+            key_rules,
+            run_cache,
+        )?;
+        if !policy.should_restart(&status) || sub_proc.stop_requested() {
+            return Ok(status);
+        }
+        if max_restarts > 0 && restarts >= max_restarts {
+            warn_user(format!(
+                "Command was restarted {} times, and will not be restarted again.",
+                restarts
+            ));
+            return Ok(status);
+        }
+
+        // a command that ran for a while is restarted without a long delay
+        if started.elapsed() > MAX_RESTART_DELAY {
+            delay = first_delay;
+        }
+        restarts += 1;
+        warning_message(format!(
+            "Command exited with code {}, restarting in {} seconds (restart {})",
+            exit_code(&status),
+            delay.as_secs(),
+            restarts
+        ));
+        if !sub_proc.restart_delay(delay)? {
+            return Ok(status);
+        }
+        delay = cmp::min(delay * 2, MAX_RESTART_DELAY);
+
+        match get_layered_vars(rest_cfg, layers, key_rules, inherit, run_cache) {
+            Ok(latest) => {
+                warn_unresolved_params(&latest.errors);
+                ct_values = latest;
+            }
+            Err(err) => warn_user(format!(
+                "Failed to fetch parameters, restarting with the previous values: {}",
+                err
+            )),
+        }
+        refresh_run_environment(sub_proc, subcmd_args, rest_cfg, layers, &ct_values)?;
+    }
+}
+
+/// Process the 'run' sub-command
+///
+/// The project/environment are resolved here (instead of by the caller), so cached values can be
+/// used when the server cannot be reached.
+pub fn process_run_command(
+    subcmd_args: &ArgMatches,
+    rest_cfg: &OpenApiConfig,
+    config: &Config,
+) -> Result<()> {
+    let mut sub_proc = SubProcess::new();
+    let mut arguments: Vec<String>;
+    let command: String;
+
+    let run_cache = RunCache::from_args(subcmd_args, config)?;
+    let env_name = config.environment.as_deref().unwrap_or(DEFAULT_ENV_NAME);
+    let proj_name = config.project.as_deref().unwrap_or_default();
+    if proj_name.is_empty() {
+        let errors = vec!["No project name was provided!".to_string()];
+        return Err(ResolveError::ResolutionNotFound(errors).into());
+    }
+    let base = resolve_layer(
+        rest_cfg,
+        &run_cache,
+        proj_name,
+        env_name,
+        subcmd_args.value_of(AS_OF_ARG),
+    )?;
+    let resolved = base.resolved.clone();
+
+    let key_rules = KeyRules::from_args(subcmd_args, &config.key_rules);
+    let inherit = Inheritance::from_str(subcmd_args.value_of("inheritance").unwrap()).unwrap();
+    let layers = get_run_layers(subcmd_args, rest_cfg, &run_cache, base)?;
+    let ct_values = get_layered_vars(rest_cfg, &layers, &key_rules, inherit, &run_cache)?;
+
+    if subcmd_args.is_present("dry-run") {
+        warn_unresolved_params(&ct_values.errors);
+        let result = set_run_environment(&mut sub_proc, subcmd_args, &resolved, &ct_values);
+        // NOTE: the environment is printed even with collisions, to show where they came from
+        print_run_environment(&sub_proc, subcmd_args, &ct_values)?;
+        sub_proc.remove_files();
+        if let Err(err) = result {
+            error_message(err.to_string());
+            process::exit(1);
+        }
+        return Ok(());
+    }
 
     if subcmd_args.is_present("command") {
         command = subcmd_args.value_of("command").unwrap().to_string();
@@ -49,6 +748,7 @@ pub fn process_run_command(
     } else if subcmd_args.is_present("arguments") {
         arguments = subcmd_args.values_of_lossy("arguments").unwrap();
         command = arguments.remove(0);
+        // NOTE: the placeholders are not expanded yet, so secret values are never printed here
         if command.contains(' ') {
             warn_user("command contains spaces, and may fail.".to_string());
             let mut reformed = format!("{} {}", command, arguments.join(" "));
@@ -64,19 +764,34 @@ pub fn process_run_command(
     }
 
     // NOTE: do this before running the sub-process, since it could be a long-running task
-    warn_unresolved_params(&errors);
+    warn_unresolved_params(&ct_values.errors);
 
-    // Setup the environment for the sub-process.
-    let inherit = Inheritance::from_str(subcmd_args.value_of("inheritance").unwrap()).unwrap();
-    let overrides = subcmd_args.values_of_lossy("set").unwrap_or_default();
-    let removals = subcmd_args.values_of_lossy("remove").unwrap_or_default();
-    let permissive = subcmd_args.is_present("permissive");
-    let strict = subcmd_args.is_present("strict");
-    sub_proc.set_environment(resolved, inherit, &overrides, &removals, strict)?;
-    if !permissive {
-        sub_proc.remove_ct_app_vars();
-    }
-    sub_proc.run_command(command.as_str(), &arguments)?;
+    // Setup the environment and template files for the sub-process.
+    set_run_environment(&mut sub_proc, subcmd_args, &resolved, &ct_values)?;
+    sub_proc.set_keep_templates(subcmd_args.is_present("keep-templates"));
+    render_templates(&mut sub_proc, subcmd_args, rest_cfg, layers.last().unwrap())?;
+    let status = supervise_command(
+        &mut sub_proc,
+        subcmd_args,
+        rest_cfg,
+        command.as_str(),
+        &arguments,
+        ct_values,
+        &layers,
+        &key_rules,
+        &run_cache,
+    )?;
+
+    // NOTE: cleanup before exiting, since process::exit() does not run destructors
+    sub_proc.remove_files();
 
+    // pass along the exit code of the command
+    if let ExitStatus::Signaled(signal) = status {
+        error_message(SubProcessError::ProcessSignaled(signal).to_string());
+    }
+    let code = exit_code(&status);
+    if code != 0 {
+        process::exit(code);
+    }
     Ok(())
 }
@@ -0,0 +1,176 @@
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Display, Formatter};
+
+// root of the document, when the document itself is the changed item
+const ROOT_PATH: &str = ".";
+
+/// A key-path level change between two structured documents.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SemanticChange {
+    Added(String, String),
+    Removed(String, String),
+    Changed(String, String, String),
+}
+
+impl Display for SemanticChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            SemanticChange::Added(path, value) => write!(f, "+ {}: {}", path, value),
+            SemanticChange::Removed(path, value) => write!(f, "- {}: {}", path, value),
+            SemanticChange::Changed(path, old, new) => {
+                write!(f, "~ {}: {} -> {}", path, old, new)
+            }
+        }
+    }
+}
+
+/// Parses text that is a JSON or YAML document. Scalars are not considered structured, since
+/// practically any text is a valid YAML scalar.
+pub fn parse_structured(text: &str) -> Option<Value> {
+    let value = match serde_json::from_str::<Value>(text) {
+        Ok(value) => value,
+        Err(_) => serde_yaml::from_str::<Value>(text).ok()?,
+    };
+    match value {
+        Value::Object(_) | Value::Array(_) => Some(value),
+        _ => None,
+    }
+}
+
+fn child_path(parent: &str, key: &str) -> String {
+    let simple = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    let key = match simple {
+        true => key.to_string(),
+        false => format!("\"{}\"", key.replace('"', "\\\"")),
+    };
+    match parent.is_empty() {
+        true => key,
+        false => format!("{}.{}", parent, key),
+    }
+}
+
+fn flatten_into(path: String, value: &Value, result: &mut BTreeMap<String, String>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, item) in map {
+                flatten_into(child_path(&path, key), item, result);
+            }
+        }
+        Value::Array(list) if !list.is_empty() => {
+            for (index, item) in list.iter().enumerate() {
+                flatten_into(format!("{}[{}]", path, index), item, result);
+            }
+        }
+        _ => {
+            let path = match path.is_empty() {
+                true => ROOT_PATH.to_string(),
+                false => path,
+            };
+            result.insert(path, value.to_string());
+        }
+    }
+}
+
+/// Gets a map of the key-paths (e.g. `servers[0].host`) to the compact JSON of the leaf values.
+pub fn flatten(value: &Value) -> BTreeMap<String, String> {
+    let mut result = BTreeMap::new();
+    flatten_into("".to_string(), value, &mut result);
+    result
+}
+
+/// Gets the key-path changes from the `left` to the `right` document, ordered by path.
+pub fn semantic_changes(left: &Value, right: &Value) -> Vec<SemanticChange> {
+    let left = flatten(left);
+    let right = flatten(right);
+    let paths: BTreeSet<&String> = left.keys().chain(right.keys()).collect();
+    let mut result = vec![];
+    for path in paths {
+        match (left.get(path), right.get(path)) {
+            (Some(old), None) => result.push(SemanticChange::Removed(path.clone(), old.clone())),
+            (None, Some(new)) => result.push(SemanticChange::Added(path.clone(), new.clone())),
+            (Some(old), Some(new)) if old != new => result.push(SemanticChange::Changed(
+                path.clone(),
+                old.clone(),
+                new.clone(),
+            )),
+            _ => {}
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn structured_values() {
+        assert!(parse_structured(r#"{"a": 1}"#).is_some());
+        assert!(parse_structured("[1, 2]").is_some());
+        assert!(parse_structured("a: 1\nb:\n  - x\n").is_some());
+        assert!(parse_structured("plain text").is_none());
+        assert!(parse_structured("123").is_none());
+        assert!(parse_structured("\"quoted\"").is_none());
+        assert!(parse_structured("").is_none());
+        assert!(parse_structured("{ not json").is_none());
+    }
+
+    #[test]
+    fn flatten_paths() {
+        let value =
+            parse_structured(r#"{"a": {"b": [1, {"c": null}]}, "d.e": "x", "f": {}, "g": []}"#)
+                .unwrap();
+        let flat = flatten(&value);
+        let expected: Vec<(&str, &str)> = vec![
+            ("\"d.e\"", "\"x\""),
+            ("a.b[0]", "1"),
+            ("a.b[1].c", "null"),
+            ("f", "{}"),
+            ("g", "[]"),
+        ];
+        let actual: Vec<(&str, &str)> =
+            flat.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn changes() {
+        // key order and format (JSON vs YAML) do not matter
+        let left = parse_structured(r#"{"name": "app", "port": 80, "tags": ["a", "b"]}"#).unwrap();
+        let right = parse_structured("tags:\n  - a\n  - b\nport: 80\nname: app\n").unwrap();
+        assert!(semantic_changes(&left, &right).is_empty());
+
+        let right =
+            parse_structured(r#"{"port": 8080, "tags": ["a"], "debug": true, "name": "app"}"#)
+                .unwrap();
+        let changes = semantic_changes(&left, &right);
+        assert_eq!(
+            changes,
+            vec![
+                SemanticChange::Added("debug".to_string(), "true".to_string()),
+                SemanticChange::Changed("port".to_string(), "80".to_string(), "8080".to_string()),
+                SemanticChange::Removed("tags[1]".to_string(), "\"b\"".to_string()),
+            ]
+        );
+        let lines: Vec<String> = changes.iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            lines,
+            vec!["+ debug: true", "~ port: 80 -> 8080", "- tags[1]: \"b\""]
+        );
+
+        // documents can be lists, too
+        let left = parse_structured("[1]").unwrap();
+        let right = parse_structured("[]").unwrap();
+        assert_eq!(
+            semantic_changes(&left, &right),
+            vec![
+                SemanticChange::Added(".".to_string(), "[]".to_string()),
+                SemanticChange::Removed("[0]".to_string(), "1".to_string()),
+            ]
+        );
+    }
+}
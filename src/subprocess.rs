@@ -3,17 +3,44 @@ use crate::database::ResolvedDetails;
 use crate::lib::warn_user;
 use color_eyre::eyre::{ErrReport, Result};
 use color_eyre::Report;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::{self, Display, Formatter};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::{env, error};
-use subprocess::{Exec, PopenError};
+use std::time::{Duration, Instant};
+use std::{cmp, env, error, thread};
+use subprocess::{Exec, ExitStatus, Popen, PopenError};
+use tempfile::TempDir;
+
+#[cfg(unix)]
+use signal_hook::iterator::Signals;
+
+// placeholder for the parameter name in the file variable name template
+pub const FILE_VAR_NAME: &str = "{name}";
+
+// placeholders in the command/arguments look like `{{ct:NAME}}`
+const PLACEHOLDER_START: &str = "{{ct:";
+const PLACEHOLDER_END: &str = "}}";
+// preferred location for parameter files, since it is memory-backed
+const SHM_DIR: &str = "/dev/shm";
+// how long to wait for a terminated child to exit before killing it
+const STOP_TIMEOUT: Duration = Duration::from_secs(10);
+// how often to check for signals that get forwarded to the child
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+// signals received by the CLI that are forwarded to the child
+#[cfg(unix)]
+const FORWARDED_SIGNALS: [i32; 3] = [libc::SIGHUP, libc::SIGINT, libc::SIGTERM];
+// forwarded signals that also mean the child should not be restarted
+#[cfg(unix)]
+const STOP_SIGNALS: [i32; 2] = [libc::SIGINT, libc::SIGTERM];
 
 // for improved readability
 pub type EnvSettings = HashMap<String, String>;
 
 // NOTE: Hash and Debug are needed for testing... sigh
-#[derive(PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
 pub enum Inheritance {
     None,
     Underlay,
@@ -41,6 +68,196 @@ impl FromStr for Inheritance {
     }
 }
 
+/// What to do with a running command, when the CloudTruth parameters change.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum ChangeAction {
+    Restart,
+    Hangup,
+    Terminate,
+}
+
+impl Display for ChangeAction {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ChangeAction::Restart => write!(f, "restart"),
+            ChangeAction::Hangup => write!(f, "sighup"),
+            ChangeAction::Terminate => write!(f, "sigterm"),
+        }
+    }
+}
+
+impl FromStr for ChangeAction {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<ChangeAction, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "restart" => Ok(ChangeAction::Restart),
+            "sighup" => Ok(ChangeAction::Hangup),
+            "sigterm" => Ok(ChangeAction::Terminate),
+            _ => Err(()),
+        }
+    }
+}
+
+/// When to restart the command after it exits.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum RestartPolicy {
+    Never,
+    OnFailure,
+    Always,
+}
+
+impl Display for RestartPolicy {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            RestartPolicy::Never => write!(f, "no"),
+            RestartPolicy::OnFailure => write!(f, "on-failure"),
+            RestartPolicy::Always => write!(f, "always"),
+        }
+    }
+}
+
+impl FromStr for RestartPolicy {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<RestartPolicy, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "no" => Ok(RestartPolicy::Never),
+            "on-failure" => Ok(RestartPolicy::OnFailure),
+            "always" => Ok(RestartPolicy::Always),
+            _ => Err(()),
+        }
+    }
+}
+
+impl RestartPolicy {
+    pub fn should_restart(&self, status: &ExitStatus) -> bool {
+        match self {
+            RestartPolicy::Never => false,
+            RestartPolicy::OnFailure => !status.success(),
+            RestartPolicy::Always => true,
+        }
+    }
+}
+
+/// Where a variable in the run environment came from.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum EnvSource {
+    Inherited,
+    CloudTruth,
+    Context,
+    Override,
+    Removed,
+}
+
+impl Display for EnvSource {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            EnvSource::Inherited => write!(f, "inherited"),
+            EnvSource::CloudTruth => write!(f, "cloudtruth"),
+            EnvSource::Context => write!(f, "context"),
+            EnvSource::Override => write!(f, "override"),
+            EnvSource::Removed => write!(f, "removed"),
+        }
+    }
+}
+
+/// The source of a variable in the run environment, and how any collision was decided.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct EnvProvenance {
+    pub name: String,
+    pub value: String,
+    pub source: EnvSource,
+    /// Name of the parameter, when the variable is a parameter file path
+    pub param_file: Option<String>,
+    /// Description of the rule that decided a collision, if there was one
+    pub decision: Option<String>,
+}
+
+/// Gets a sorted list of keys that were added, removed, or had their value changed.
+pub fn changed_keys(old: &EnvSettings, new: &EnvSettings) -> Vec<String> {
+    let mut changed: Vec<String> = old
+        .iter()
+        .filter(|(k, v)| new.get(*k) != Some(v))
+        .map(|(k, _)| k.clone())
+        .collect();
+    changed.extend(new.keys().filter(|k| !old.contains_key(*k)).cloned());
+    changed.sort();
+    changed
+}
+
+/// Replaces the `{{ct:NAME}}` placeholders in the text with the values. Placeholders without a
+/// value are left as-is, unless `strict` is set (which also fails on unset values).
+pub fn expand_placeholders(
+    text: &str,
+    values: &EnvSettings,
+    strict: bool,
+) -> SubProcessResult<String> {
+    let mut result = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find(PLACEHOLDER_START) {
+        let after = &rest[start + PLACEHOLDER_START.len()..];
+        let end = match after.find(PLACEHOLDER_END) {
+            Some(end) => end,
+            None => break,
+        };
+        let name = after[..end].trim();
+        let placeholder =
+            &rest[start..start + PLACEHOLDER_START.len() + end + PLACEHOLDER_END.len()];
+        result.push_str(&rest[..start]);
+        match values.get(name) {
+            Some(value) if strict && value == "-" => {
+                return Err(SubProcessError::StrictRunError(name.to_string()));
+            }
+            Some(value) => result.push_str(value),
+            None if strict => {
+                return Err(SubProcessError::PlaceholderError(name.to_string()));
+            }
+            None => {
+                warn_user(format!("No CloudTruth parameter for placeholder: {}", name));
+                result.push_str(placeholder);
+            }
+        }
+        rest = &rest[placeholder.len() + start..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+/// Merges the layers of CloudTruth values, where later layers take precedence. With
+/// `Inheritance::Exclusive`, differing values for the same key are reported as collisions.
+pub fn merge_layers(layers: &[EnvSettings], inherit: Inheritance) -> SubProcessResult<EnvSettings> {
+    let mut merged = EnvSettings::new();
+    let mut collisions: Vec<String> = vec![];
+    for layer in layers {
+        for (key, value) in layer {
+            if let Some(orig) = merged.get(key) {
+                if inherit == Inheritance::Exclusive && orig != value && !collisions.contains(key) {
+                    collisions.push(key.clone());
+                }
+            }
+            merged.insert(key.clone(), value.clone());
+        }
+    }
+
+    if !collisions.is_empty() {
+        collisions.sort();
+        Err(SubProcessError::EnvironmentCollisions(collisions))
+    } else {
+        Ok(merged)
+    }
+}
+
+/// Gets the exit code for the command's exit status. A command killed by a signal gets 128+N,
+/// like the shells do.
+pub fn exit_code(status: &ExitStatus) -> i32 {
+    match status {
+        ExitStatus::Exited(code) => *code as i32,
+        ExitStatus::Signaled(signal) => 128 + *signal as i32,
+        ExitStatus::Other(_) | ExitStatus::Undetermined => 1,
+    }
+}
+
 pub type SubProcessResult<T> = std::result::Result<T, SubProcessError>;
 
 #[derive(Debug)]
@@ -49,6 +266,12 @@ pub enum SubProcessError {
     ProcessRunError(PopenError),
     ProcessOutputError(ErrReport),
     StrictRunError(String),
+    SignalError(String),
+    ProcessSignaled(u8),
+    ParameterFileError(String),
+    FileNameCollisions(Vec<String>),
+    TemplateFileError(String),
+    PlaceholderError(String),
 }
 
 impl error::Error for SubProcessError {}
@@ -76,6 +299,28 @@ impl fmt::Display for SubProcessError {
                     e
                 )
             }
+            SubProcessError::SignalError(e) => {
+                write!(f, "Failed to signal process: {}", e)
+            }
+            SubProcessError::ProcessSignaled(signal) => {
+                write!(f, "Process was killed by signal {}", signal)
+            }
+            SubProcessError::ParameterFileError(e) => {
+                write!(f, "Failed to write parameter file: {}", e)
+            }
+            SubProcessError::FileNameCollisions(collisions) => {
+                write!(
+                    f,
+                    "Parameter names collide as file names: {}",
+                    collisions.join("; ")
+                )
+            }
+            SubProcessError::TemplateFileError(e) => {
+                write!(f, "Failed to write template file: {}", e)
+            }
+            SubProcessError::PlaceholderError(name) => {
+                write!(f, "No CloudTruth parameter for placeholder: {}", name)
+            }
         }
     }
 }
@@ -92,9 +337,47 @@ impl From<ErrReport> for SubProcessError {
     }
 }
 
+/// Makes this process a "child subreaper", so orphaned descendants are re-parented to it. When
+/// this fails, orphans are only re-parented here when running as PID 1 (e.g. in a container).
+#[cfg(target_os = "linux")]
+fn set_child_subreaper() {
+    unsafe { libc::prctl(libc::PR_SET_CHILD_SUBREAPER, 1, 0, 0, 0) };
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_child_subreaper() {}
+
+/// Gets the process IDs of the children of all the threads of this process.
+#[cfg(target_os = "linux")]
+fn child_pids() -> Vec<u32> {
+    let tasks = match fs::read_dir("/proc/self/task") {
+        Ok(tasks) => tasks,
+        Err(_) => return vec![],
+    };
+    tasks
+        .filter_map(|task| fs::read_to_string(task.ok()?.path().join("children")).ok())
+        .flat_map(|children| {
+            children
+                .split_whitespace()
+                .filter_map(|pid| pid.parse().ok())
+                .collect::<Vec<u32>>()
+        })
+        .collect()
+}
+
 pub struct SubProcess {
     ct_vars: EnvSettings,
     env_vars: EnvSettings,
+    child: Option<Popen>,
+    #[cfg(unix)]
+    signals: Option<Signals>,
+    file_dir: Option<TempDir>,
+    file_vars: HashMap<String, String>,
+    sources: HashMap<String, (EnvSource, Option<String>)>,
+    stop_requested: bool,
+    reap_orphans: bool,
+    template_files: Vec<PathBuf>,
+    keep_templates: bool,
 }
 
 impl SubProcess {
@@ -102,6 +385,16 @@ impl SubProcess {
         Self {
             ct_vars: Default::default(),
             env_vars: Default::default(),
+            child: None,
+            #[cfg(unix)]
+            signals: None,
+            file_dir: None,
+            file_vars: Default::default(),
+            sources: Default::default(),
+            stop_requested: false,
+            reap_orphans: false,
+            template_files: vec![],
+            keep_templates: false,
         }
     }
 
@@ -109,6 +402,130 @@ impl SubProcess {
         self.ct_vars = ct_vars;
     }
 
+    /// Creates a private (owner only) temporary directory for the parameter files.
+    fn create_file_dir() -> std::io::Result<TempDir> {
+        let mut builder = tempfile::Builder::new();
+        builder.prefix("cloudtruth-");
+        if Path::new(SHM_DIR).is_dir() {
+            builder.tempdir_in(SHM_DIR)
+        } else {
+            builder.tempdir()
+        }
+    }
+
+    /// Writes the value to a file that is only readable by the owner (even when the file already
+    /// existed).
+    fn write_private_file(path: &Path, value: &str) -> std::io::Result<()> {
+        let mut options = OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            options.mode(0o600);
+        }
+        let mut file = options.open(path)?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            file.set_permissions(fs::Permissions::from_mode(0o600))?;
+        }
+        file.write_all(value.as_bytes())
+    }
+
+    /// Moves the named CloudTruth values into files in a private temporary directory. Each value
+    /// is replaced by a variable with the file path, named using the `var_template`.
+    ///
+    /// The files are re-written when called again, so a running command sees new values.
+    pub fn set_cloudtruth_files(
+        &mut self,
+        names: &[String],
+        var_template: &str,
+        strict: bool,
+    ) -> SubProcessResult<()> {
+        if names.is_empty() {
+            return Ok(());
+        }
+        let file_name = |name: &str| name.replace(['/', '\\'], "_");
+        // distinct names cannot share a file, since one value would silently replace the other
+        let mut sources: BTreeMap<String, Vec<&str>> = BTreeMap::new();
+        for name in names.iter().filter(|n| self.ct_vars.contains_key(*n)) {
+            let srcs = sources.entry(file_name(name)).or_default();
+            if !srcs.contains(&name.as_str()) {
+                srcs.push(name);
+            }
+        }
+        let collisions: Vec<String> = sources
+            .into_iter()
+            .filter(|(_, srcs)| srcs.len() > 1)
+            .map(|(dest, srcs)| format!("{} -> {}", srcs.join(", "), dest))
+            .collect();
+        if !collisions.is_empty() {
+            return Err(SubProcessError::FileNameCollisions(collisions));
+        }
+        if self.file_dir.is_none() {
+            let dir = Self::create_file_dir()
+                .map_err(|e| SubProcessError::ParameterFileError(e.to_string()))?;
+            self.file_dir = Some(dir);
+        }
+        let dir = self.file_dir.as_ref().unwrap().path().to_path_buf();
+        for name in names {
+            let value = match self.ct_vars.remove(name) {
+                Some(v) => v,
+                None => {
+                    warn_user(format!("Parameter '{}' not found for file", name));
+                    continue;
+                }
+            };
+            if strict && value == "-" {
+                return Err(SubProcessError::StrictRunError(name.clone()));
+            }
+            let path = dir.join(file_name(name));
+            Self::write_private_file(&path, &value)
+                .map_err(|e| SubProcessError::ParameterFileError(e.to_string()))?;
+            let var_name = var_template.replace(FILE_VAR_NAME, name);
+            self.file_vars.insert(var_name.clone(), name.clone());
+            self.ct_vars.insert(var_name, path.display().to_string());
+        }
+        Ok(())
+    }
+
+    /// Writes the rendered template body to the path, so it is only readable by the owner.
+    pub fn write_template_file(&mut self, path: &Path, body: &str) -> SubProcessResult<()> {
+        Self::write_private_file(path, body).map_err(|e| {
+            SubProcessError::TemplateFileError(format!("{}: {}", path.display(), e))
+        })?;
+        if !self.template_files.iter().any(|p| p == path) {
+            self.template_files.push(path.to_path_buf());
+        }
+        Ok(())
+    }
+
+    /// Keeps the template files, instead of removing them with the other files.
+    pub fn set_keep_templates(&mut self, keep: bool) {
+        self.keep_templates = keep;
+    }
+
+    /// Removes the parameter files (and directory) and template files, if any were written.
+    pub fn remove_files(&mut self) {
+        if let Some(dir) = self.file_dir.take() {
+            if let Err(e) = dir.close() {
+                warn_user(format!("Failed to remove parameter files: {}", e));
+            }
+        }
+        let template_files: Vec<PathBuf> = self.template_files.drain(..).collect();
+        if !self.keep_templates {
+            for path in template_files {
+                if let Err(e) = fs::remove_file(&path) {
+                    warn_user(format!(
+                        "Failed to remove template file {}: {}",
+                        path.display(),
+                        e
+                    ));
+                }
+            }
+        }
+    }
+
     fn current_env(&self) -> EnvSettings {
         // Create a EnvSettings from the current set of environment variables (excluding a few).
         let exclude = ["PS1", "TERM"];
@@ -145,6 +562,11 @@ impl SubProcess {
         } else {
             self.current_env()
         };
+        self.sources = self
+            .env_vars
+            .keys()
+            .map(|k| (k.clone(), (EnvSource::Inherited, None)))
+            .collect();
 
         // Add breadcrumbs about which environment.
         self.env_vars.insert(
@@ -155,6 +577,10 @@ impl SubProcess {
             CT_PROJECT.to_string(),
             resolved.project_display_name().to_string(),
         );
+        for key in [CT_ENVIRONMENT, CT_PROJECT] {
+            self.sources
+                .insert(key.to_string(), (EnvSource::Context, None));
+        }
 
         // Add in the items from the CloudTruth environment (looking for collisions)
         let mut collisions: Vec<String> = vec![];
@@ -167,15 +593,31 @@ impl SubProcess {
             }
             if !self.env_vars.contains_key(&key) {
                 // when not already, insert it
+                self.sources
+                    .insert(key.clone(), (EnvSource::CloudTruth, None));
                 self.env_vars.entry(key).or_insert(value);
             } else {
                 let orig = self.env_vars.get(&key).unwrap_or(&empty);
+                let orig_source = self.source_of(&key);
                 if inherit == Inheritance::Exclusive && value != *orig {
+                    let decision = format!("{}: conflicts with {}", inherit, orig_source);
+                    self.sources
+                        .insert(key.clone(), (orig_source, Some(decision)));
                     collisions.push(key);
-                } else if inherit == Inheritance::Overlay {
+                } else if inherit == Inheritance::Underlay {
+                    // if doing Underlay, the local environment value is already set
+                    let decision = format!("{}: {} over cloudtruth", inherit, orig_source);
+                    self.sources.insert(key, (orig_source, Some(decision)));
+                } else {
+                    let decision = if inherit == Inheritance::Exclusive {
+                        format!("{}: same value as {}", inherit, orig_source)
+                    } else {
+                        format!("{}: cloudtruth over {}", inherit, orig_source)
+                    };
+                    self.sources
+                        .insert(key.clone(), (EnvSource::CloudTruth, Some(decision)));
                     self.env_vars.insert(key, value);
                 }
-                // if doing Underlay, the local environment value is already set
             }
         }
 
@@ -183,13 +625,19 @@ impl SubProcess {
         let over_vars = self.process_overrides(overrides)?;
         for (key, value) in over_vars {
             let orig = self.env_vars.get(&key).unwrap_or(&empty);
-            if inherit == Inheritance::Exclusive
-                && self.env_vars.contains_key(&key)
-                && value != *orig
-            {
+            let exists = self.env_vars.contains_key(&key);
+            let orig_source = self.source_of(&key);
+            if inherit == Inheritance::Exclusive && exists && value != *orig {
+                let decision = format!("{}: conflicts with {}", inherit, orig_source);
+                self.sources
+                    .insert(key.clone(), (orig_source, Some(decision)));
                 collisions.push(key);
             } else {
                 // use the "set" value as the final answer, when not worrying about collisions
+                let decision =
+                    exists.then(|| format!("{}: override over {}", inherit, orig_source));
+                self.sources
+                    .insert(key.clone(), (EnvSource::Override, decision));
                 self.env_vars.insert(key, value);
             }
         }
@@ -200,7 +648,7 @@ impl SubProcess {
         } else {
             // Remove the specified values.
             for r in removals {
-                self.env_vars.remove(r.as_str());
+                self.remove_var(r, "removed by request");
             }
             Ok(())
         }
@@ -208,11 +656,49 @@ impl SubProcess {
 
     pub fn remove_ct_app_vars(&mut self) {
         for app_var in CT_APP_REMOVABLE_VARS {
-            self.env_vars.remove(*app_var);
+            self.remove_var(app_var, "CloudTruth application variable");
         }
     }
 
-    pub fn run_command(&self, command: &str, arguments: &[String]) -> SubProcessResult<()> {
+    /// Gets the current source of the variable (assumed inherited when it was not recorded).
+    fn source_of(&self, key: &str) -> EnvSource {
+        self.sources
+            .get(key)
+            .map(|(source, _)| *source)
+            .unwrap_or(EnvSource::Inherited)
+    }
+
+    /// Removes the variable, and records why it was removed.
+    fn remove_var(&mut self, key: &str, reason: &str) {
+        if self.env_vars.remove(key).is_some() {
+            let decision = format!("{} ({})", reason, self.source_of(key));
+            self.sources
+                .insert(key.to_string(), (EnvSource::Removed, Some(decision)));
+        }
+    }
+
+    /// Gets the source of each variable in the environment (including removed variables), sorted
+    /// by name. This reflects the last call to `set_environment()`.
+    pub fn env_provenance(&self) -> Vec<EnvProvenance> {
+        let mut result: Vec<EnvProvenance> = self
+            .sources
+            .iter()
+            .map(|(name, (source, decision))| EnvProvenance {
+                name: name.clone(),
+                value: self.env_vars.get(name).cloned().unwrap_or_default(),
+                source: *source,
+                param_file: match source {
+                    EnvSource::CloudTruth => self.file_vars.get(name).cloned(),
+                    _ => None,
+                },
+                decision: decision.clone(),
+            })
+            .collect();
+        result.sort_by(|a, b| a.name.cmp(&b.name));
+        result
+    }
+
+    fn create_exec(&self, command: &str, arguments: &[String]) -> Exec {
         let mut sub_proc: Exec;
 
         if arguments.is_empty() {
@@ -226,15 +712,201 @@ impl SubProcess {
         for (key, value) in &self.env_vars {
             sub_proc = sub_proc.env(key, value);
         }
+        sub_proc
+    }
+
+    /// Runs the command until it exits, and returns the exit status.
+    pub fn run_command(
+        &mut self,
+        command: &str,
+        arguments: &[String],
+    ) -> SubProcessResult<ExitStatus> {
+        self.start_command(command, arguments)?;
+        loop {
+            if let Some(status) = self.wait_command(POLL_INTERVAL)? {
+                return Ok(status);
+            }
+        }
+    }
+
+    /// Starts the command in the background, so it can be monitored with `wait_command()`.
+    pub fn start_command(&mut self, command: &str, arguments: &[String]) -> SubProcessResult<()> {
+        #[cfg(unix)]
+        if self.signals.is_none() {
+            let signals = Signals::new(FORWARDED_SIGNALS)
+                .map_err(|e| SubProcessError::SignalError(e.to_string()))?;
+            self.signals = Some(signals);
+        }
+        self.child = Some(self.create_exec(command, arguments).popen()?);
+        Ok(())
+    }
+
+    /// Sends any signals received by the CLI on to the started command (if any), and notes when
+    /// the CLI was asked to stop.
+    #[cfg(unix)]
+    fn forward_signals(&mut self) -> SubProcessResult<()> {
+        use subprocess::unix::PopenExt;
+
+        if let Some(signals) = self.signals.as_mut() {
+            for signal in signals.pending() {
+                if STOP_SIGNALS.contains(&signal) {
+                    self.stop_requested = true;
+                }
+                if let Some(child) = self.child.as_ref() {
+                    child
+                        .send_signal(signal)
+                        .map_err(|e| SubProcessError::SignalError(e.to_string()))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn forward_signals(&mut self) -> SubProcessResult<()> {
+        Ok(())
+    }
+
+    /// Returns `true` when the CLI received a signal to stop (e.g. SIGTERM), so the command should
+    /// not be restarted.
+    pub fn stop_requested(&self) -> bool {
+        self.stop_requested
+    }
+
+    /// Enables reaping of orphaned processes. On Linux, this process becomes a "child subreaper",
+    /// so the orphaned descendants of the command are re-parented here (instead of to PID 1) and
+    /// reaped while the command runs. Elsewhere, orphans are left to the init process.
+    pub fn set_reap_orphans(&mut self, reap: bool) {
+        self.reap_orphans = reap;
+        if reap {
+            set_child_subreaper();
+        }
+    }
+
+    /// Reaps any exited processes that were orphaned to this process, while leaving the started
+    /// command for `wait_command()`.
+    #[cfg(target_os = "linux")]
+    fn reap_exited_orphans(&self) {
+        if !self.reap_orphans {
+            return;
+        }
+        let child_pid = self.child.as_ref().and_then(|c| c.pid());
+        loop {
+            // NOTE: WNOWAIT leaves the process, so the started command is not reaped here
+            let mut info: libc::siginfo_t = unsafe { std::mem::zeroed() };
+            let flags = libc::WEXITED | libc::WNOHANG | libc::WNOWAIT;
+            let result = unsafe { libc::waitid(libc::P_ALL, 0, &mut info, flags) };
+            let pid = unsafe { info.si_pid() };
+            if result != 0 || pid <= 0 {
+                break;
+            }
+            if Some(pid as u32) == child_pid {
+                // the exited command hides any other exited processes from waitid(), so the
+                // remaining children are checked one at a time
+                for orphan in child_pids().into_iter().filter(|p| Some(*p) != child_pid) {
+                    unsafe {
+                        libc::waitpid(orphan as libc::pid_t, std::ptr::null_mut(), libc::WNOHANG)
+                    };
+                }
+                break;
+            }
+            unsafe { libc::waitpid(pid, std::ptr::null_mut(), libc::WNOHANG) };
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn reap_exited_orphans(&self) {}
+
+    /// Waits for the `delay` before restarting the command. Returns `false` if the CLI was asked
+    /// to stop while waiting.
+    pub fn restart_delay(&mut self, delay: Duration) -> SubProcessResult<bool> {
+        let start = Instant::now();
+        loop {
+            self.forward_signals()?;
+            self.reap_exited_orphans();
+            if self.stop_requested {
+                return Ok(false);
+            }
+            let remaining = delay.checked_sub(start.elapsed()).unwrap_or_default();
+            if remaining.is_zero() {
+                return Ok(true);
+            }
+            thread::sleep(cmp::min(remaining, POLL_INTERVAL));
+        }
+    }
 
-        sub_proc.join()?;
+    /// Waits up to `timeout` for the started command to exit, and returns the exit status when
+    /// it has exited. Signals received while waiting are forwarded to the command.
+    pub fn wait_command(&mut self, timeout: Duration) -> SubProcessResult<Option<ExitStatus>> {
+        let start = Instant::now();
+        loop {
+            self.forward_signals()?;
+            self.reap_exited_orphans();
+            let remaining = timeout.checked_sub(start.elapsed()).unwrap_or_default();
+            let status = match self.child.as_mut() {
+                Some(child) => child.wait_timeout(cmp::min(remaining, POLL_INTERVAL))?,
+                None => return Ok(Some(ExitStatus::Undetermined)),
+            };
+            if status.is_some() {
+                self.child = None;
+                return Ok(status);
+            }
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Sends the signal for the `action` to the started command.
+    pub fn signal_command(&mut self, action: ChangeAction) -> SubProcessResult<()> {
+        if let Some(child) = self.child.as_mut() {
+            let result = match action {
+                #[cfg(unix)]
+                ChangeAction::Hangup => {
+                    use subprocess::unix::PopenExt;
+                    child.send_signal(libc::SIGHUP)
+                }
+                #[cfg(not(unix))]
+                ChangeAction::Hangup => {
+                    return Err(SubProcessError::SignalError(
+                        "SIGHUP is not supported on this platform".to_string(),
+                    ))
+                }
+                ChangeAction::Restart | ChangeAction::Terminate => child.terminate(),
+            };
+            result.map_err(|e| SubProcessError::SignalError(e.to_string()))?;
+        }
         Ok(())
     }
+
+    /// Stops the started command, killing it if it does not exit after being terminated.
+    pub fn stop_command(&mut self) -> SubProcessResult<()> {
+        if let Some(mut child) = self.child.take() {
+            child
+                .terminate()
+                .map_err(|e| SubProcessError::SignalError(e.to_string()))?;
+            if child.wait_timeout(STOP_TIMEOUT)?.is_none() {
+                child
+                    .kill()
+                    .map_err(|e| SubProcessError::SignalError(e.to_string()))?;
+                child.wait()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Drop for SubProcess {
+    fn drop(&mut self) {
+        // NOTE: files are removed explicitly before process::exit(), since it skips destructors
+        self.remove_files();
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use assert_matches::assert_matches;
 
     #[test]
     fn inherit_to_string() {
@@ -263,4 +935,262 @@ mod test {
             assert_eq!(Inheritance::from_str(sv.as_str()), iv);
         }
     }
+
+    #[test]
+    fn change_action_strings() {
+        for action in [
+            ChangeAction::Restart,
+            ChangeAction::Hangup,
+            ChangeAction::Terminate,
+        ] {
+            assert_eq!(ChangeAction::from_str(&action.to_string()), Ok(action));
+        }
+        assert_eq!(ChangeAction::from_str("SIGHUP"), Ok(ChangeAction::Hangup));
+        assert_eq!(ChangeAction::from_str("sigint"), Err(()));
+    }
+
+    #[test]
+    fn restart_policy() {
+        for policy in [
+            RestartPolicy::Never,
+            RestartPolicy::OnFailure,
+            RestartPolicy::Always,
+        ] {
+            assert_eq!(RestartPolicy::from_str(&policy.to_string()), Ok(policy));
+        }
+        assert_eq!(RestartPolicy::from_str("sometimes"), Err(()));
+
+        let success = ExitStatus::Exited(0);
+        let failure = ExitStatus::Exited(1);
+        let signaled = ExitStatus::Signaled(9);
+        assert!(!RestartPolicy::Never.should_restart(&failure));
+        assert!(!RestartPolicy::OnFailure.should_restart(&success));
+        assert!(RestartPolicy::OnFailure.should_restart(&failure));
+        assert!(RestartPolicy::OnFailure.should_restart(&signaled));
+        assert!(RestartPolicy::Always.should_restart(&success));
+    }
+
+    #[test]
+    fn placeholders() {
+        let mut values = EnvSettings::new();
+        values.insert("USER".to_string(), "admin".to_string());
+        values.insert("PASSWORD".to_string(), "p@ss word".to_string());
+        values.insert("UNSET".to_string(), "-".to_string());
+
+        let text = "--user={{ct:USER}} --password '{{ct: PASSWORD }}'";
+        assert_eq!(
+            expand_placeholders(text, &values, true).unwrap(),
+            "--user=admin --password 'p@ss word'"
+        );
+        assert_eq!(
+            expand_placeholders("{{ct:USER}}{{ct:USER}}", &values, false).unwrap(),
+            "adminadmin"
+        );
+        for text in ["no placeholders", "{{USER}}", "{{ct:USER", "{{ct:MISSING}}"] {
+            assert_eq!(expand_placeholders(text, &values, false).unwrap(), text);
+        }
+        assert_eq!(
+            expand_placeholders("{{ct:UNSET}}", &values, false).unwrap(),
+            "-"
+        );
+        assert_matches!(
+            expand_placeholders("{{ct:MISSING}}", &values, true),
+            Err(SubProcessError::PlaceholderError(name)) if name == "MISSING"
+        );
+        assert_matches!(
+            expand_placeholders("{{ct:UNSET}}", &values, true),
+            Err(SubProcessError::StrictRunError(_))
+        );
+    }
+
+    #[test]
+    fn exit_codes() {
+        assert_eq!(exit_code(&ExitStatus::Exited(0)), 0);
+        assert_eq!(exit_code(&ExitStatus::Exited(3)), 3);
+        assert_eq!(exit_code(&ExitStatus::Signaled(15)), 143);
+        assert_eq!(exit_code(&ExitStatus::Undetermined), 1);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn run_command_exit_status() {
+        let mut sub_proc = SubProcess::new();
+        let status = sub_proc.run_command("exit 3", &[]).unwrap();
+        assert_eq!(status, ExitStatus::Exited(3));
+        let status = sub_proc.run_command("kill -TERM $$", &[]).unwrap();
+        assert_eq!(status, ExitStatus::Signaled(libc::SIGTERM as u8));
+        assert_eq!(exit_code(&status), 143);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn child_processes() {
+        let mut child = std::process::Command::new("sleep")
+            .arg("5")
+            .spawn()
+            .unwrap();
+        assert!(child_pids().contains(&child.id()));
+        child.kill().unwrap();
+        child.wait().unwrap();
+        assert!(!child_pids().contains(&child.id()));
+    }
+
+    #[test]
+    fn cloudtruth_files() {
+        let mut sub_proc = SubProcess::new();
+        let mut ct_vars = EnvSettings::new();
+        ct_vars.insert("PLAIN".to_string(), "plain-value".to_string());
+        ct_vars.insert("my/secret".to_string(), "secret-value".to_string());
+        sub_proc.set_cloudtruth_environment(ct_vars);
+        let names = vec!["my/secret".to_string(), "MISSING".to_string()];
+        sub_proc
+            .set_cloudtruth_files(&names, "{name}_FILE", false)
+            .unwrap();
+
+        assert_eq!(sub_proc.ct_vars.get("PLAIN").unwrap(), "plain-value");
+        assert!(!sub_proc.ct_vars.contains_key("my/secret"));
+        assert!(!sub_proc.ct_vars.contains_key("MISSING_FILE"));
+        let filename = sub_proc.ct_vars.get("my/secret_FILE").unwrap().clone();
+        assert_eq!(std::fs::read_to_string(&filename).unwrap(), "secret-value");
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&filename).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        sub_proc.remove_files();
+        assert!(!Path::new(&filename).exists());
+    }
+
+    #[test]
+    fn cloudtruth_file_collisions() {
+        let mut sub_proc = SubProcess::new();
+        let mut ct_vars = EnvSettings::new();
+        ct_vars.insert("a/b".to_string(), "first".to_string());
+        ct_vars.insert("a_b".to_string(), "second".to_string());
+        sub_proc.set_cloudtruth_environment(ct_vars);
+        let names = vec!["a_b".to_string(), "a/b".to_string()];
+        let err = sub_proc
+            .set_cloudtruth_files(&names, "{name}_FILE", false)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Parameter names collide as file names: a_b, a/b -> a_b"
+        );
+        assert!(sub_proc.file_dir.is_none());
+    }
+
+    #[test]
+    fn merged_layers() {
+        let mut first = EnvSettings::new();
+        first.insert("SHARED".to_string(), "first".to_string());
+        first.insert("SAME".to_string(), "value".to_string());
+        first.insert("FIRST".to_string(), "value".to_string());
+        let mut second = EnvSettings::new();
+        second.insert("SHARED".to_string(), "second".to_string());
+        second.insert("SAME".to_string(), "value".to_string());
+        let layers = vec![first, second];
+
+        let merged = merge_layers(&layers, Inheritance::Overlay).unwrap();
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged.get("SHARED").unwrap(), "second");
+        assert_eq!(merged.get("FIRST").unwrap(), "value");
+
+        let result = merge_layers(&layers, Inheritance::Exclusive);
+        assert_matches!(result, Err(SubProcessError::EnvironmentCollisions(keys)) => {
+            assert_eq!(keys, vec!["SHARED"]);
+        });
+    }
+
+    #[test]
+    fn changed_keys_sorted() {
+        let mut old = EnvSettings::new();
+        old.insert("SAME".to_string(), "value".to_string());
+        old.insert("REMOVED".to_string(), "value".to_string());
+        old.insert("CHANGED".to_string(), "before".to_string());
+        let mut new = EnvSettings::new();
+        new.insert("SAME".to_string(), "value".to_string());
+        new.insert("CHANGED".to_string(), "after".to_string());
+        new.insert("ADDED".to_string(), "value".to_string());
+        assert_eq!(
+            changed_keys(&old, &new),
+            vec!["ADDED", "CHANGED", "REMOVED"]
+        );
+        assert!(changed_keys(&new, &new).is_empty());
+    }
+
+    #[test]
+    fn environment_provenance() {
+        let resolved = ResolvedDetails::new(
+            "my-env".to_string(),
+            "env-id".to_string(),
+            "my-proj".to_string(),
+            "proj-id".to_string(),
+        );
+        let exclude = ["PS1", "TERM", CT_ENVIRONMENT, CT_PROJECT];
+        let (inherited, _) = env::vars()
+            .find(|(k, _)| !exclude.contains(&k.as_str()))
+            .unwrap();
+        let mut ct_vars = EnvSettings::new();
+        ct_vars.insert(inherited.clone(), "ct-value".to_string());
+        ct_vars.insert("CT_ONLY".to_string(), "ct-only".to_string());
+        ct_vars.insert("CT_REMOVED".to_string(), "removed".to_string());
+        let overrides = vec!["CT_ONLY=set-value".to_string()];
+        let removals = vec!["CT_REMOVED".to_string()];
+
+        let mut sub_proc = SubProcess::new();
+        sub_proc.set_cloudtruth_environment(ct_vars);
+        sub_proc
+            .set_environment(
+                &resolved,
+                Inheritance::Underlay,
+                &overrides,
+                &removals,
+                false,
+            )
+            .unwrap();
+        let provenance = sub_proc.env_provenance();
+
+        let entry = find_entry(&provenance, &inherited);
+        assert_eq!(entry.source, EnvSource::Inherited);
+        assert_eq!(
+            entry.decision,
+            Some("underlay: inherited over cloudtruth".to_string())
+        );
+        let entry = find_entry(&provenance, "CT_ONLY");
+        assert_eq!(entry.source, EnvSource::Override);
+        assert_eq!(entry.value, "set-value");
+        assert_eq!(
+            entry.decision,
+            Some("underlay: override over cloudtruth".to_string())
+        );
+        let entry = find_entry(&provenance, "CT_REMOVED");
+        assert_eq!(entry.source, EnvSource::Removed);
+        assert_eq!(entry.value, "");
+        assert_eq!(
+            find_entry(&provenance, CT_PROJECT).source,
+            EnvSource::Context
+        );
+
+        // collisions are recorded, even though it is an error
+        let result = sub_proc.set_environment(
+            &resolved,
+            Inheritance::Exclusive,
+            &overrides,
+            &removals,
+            false,
+        );
+        assert_matches!(result, Err(SubProcessError::EnvironmentCollisions(_)));
+        let entry = find_entry(&sub_proc.env_provenance(), &inherited);
+        assert_eq!(entry.source, EnvSource::Inherited);
+        assert_eq!(
+            entry.decision,
+            Some("exclusive: conflicts with inherited".to_string())
+        );
+    }
+
+    fn find_entry(provenance: &[EnvProvenance], name: &str) -> EnvProvenance {
+        provenance.iter().find(|p| p.name == name).unwrap().clone()
+    }
 }
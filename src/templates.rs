@@ -1,13 +1,14 @@
 use crate::cli::{
     show_values, AS_OF_ARG, CONFIRM_FLAG, DELETE_SUBCMD, DESCRIPTION_OPT, DIFF_SUBCMD, EDIT_SUBCMD,
     FORMAT_OPT, GET_SUBCMD, HISTORY_SUBCMD, LIST_SUBCMD, NAME_ARG, RAW_FLAG, RENAME_OPT,
-    SECRETS_FLAG, SET_SUBCMD, SHOW_TIMES_FLAG, TEMPLATE_FILE_OPT,
+    SECRETS_FLAG, SEMANTIC_FLAG, SET_SUBCMD, SHOW_TIMES_FLAG, TEMPLATE_FILE_OPT,
 };
 use crate::database::{HistoryAction, OpenApiConfig, ResolvedDetails, TemplateHistory, Templates};
 use crate::lib::{
     error_message, parse_datetime, parse_tag, user_confirm, warn_missing_subcommand,
     warning_message, DEL_CONFIRM, FILE_READ_ERR,
 };
+use crate::semantic_diff::{parse_structured, semantic_changes};
 use crate::table::Table;
 use clap::ArgMatches;
 use color_eyre::eyre::Result;
@@ -187,6 +188,7 @@ fn proc_template_diff(
     let raw = subcmd_args.is_present(RAW_FLAG);
     let template_name = subcmd_args.value_of(NAME_ARG).unwrap();
     let context = subcmd_args.value_of("lines").unwrap().parse::<usize>()?;
+    let semantic = subcmd_args.is_present(SEMANTIC_FLAG);
     let proj_id = resolved.project_id();
     let proj_name = resolved.project_display_name();
     let as_list: Vec<&str> = subcmd_args
@@ -284,6 +286,21 @@ fn proc_template_diff(
         tag2,
     )?;
 
+    if semantic {
+        let left = parse_structured(&details1.body);
+        let right = parse_structured(&details2.body);
+        if let (Some(left), Some(right)) = (left, right) {
+            let changes = semantic_changes(&left, &right);
+            if !changes.is_empty() {
+                println!("--- {}\n+++ {}", header1, header2);
+                for change in changes {
+                    println!("{}", change);
+                }
+            }
+            return Ok(());
+        }
+    }
+
     let diff = TextDiff::from_lines(&details1.body, &details2.body);
     diff.unified_diff()
         .header(&header1, &header2)